@@ -1,16 +1,25 @@
-use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use chrono::{Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime};
 use serde_json::{json, Value};
+use std::collections::HashSet;
 use std::fs;
 use std::fs::File;
 use std::io;
-use std::io::BufRead;
-use std::path::Path;
+use std::io::{BufRead, Read};
+use std::path::{Path, PathBuf};
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+use std::sync::Mutex;
+use std::time::SystemTime;
 
 #[cfg(any(target_os = "windows", target_os = "linux"))]
 use rs_drivelist::drive_list;
 
+pub use crate::common::{BerlingerModel, ModelCapabilities};
+
 use crate::common::{
-    BreachType, Sensor, SensorType, TemperatureBreach, TemperatureBreachConfig, TemperatureLog,
+    clamp_to_day, detect_consecutive_breaches_with_min_samples, split_breach_by_day,
+    split_breach_by_local_day, BreachType, Celsius, IntervalUnit, ParseOptions, ParseWarning,
+    ParsedSensor, Sensor, SensorType, TemperatureBreach, TemperatureBreachConfig, TemperatureLog,
+    TemperatureSensorError,
 };
 
 #[derive(Debug)]
@@ -19,6 +28,35 @@ enum SensorSubType {
     QTag,
 }
 
+/// Identifies the sensor model from the header of a Berlinger txt file, without parsing
+/// the rest of it. Returns None if the "Device:" line is missing or names an unknown model.
+pub fn detect_sensor_model(txt: &str) -> Option<BerlingerModel> {
+    let device = txt
+        .lines()
+        .map(|line| line.trim_start_matches('\u{feff}').trim())
+        .find(|line| !line.is_empty())?
+        .strip_prefix("Device:")?;
+
+    model_from_device_name(device)
+}
+
+fn model_from_device_name(device: &str) -> Option<BerlingerModel> {
+    let device = device.trim();
+
+    // check the longer Fridge-tag names first, as they share a prefix with Fridge-tag 2
+    if device.ends_with("Fridge-tag Ultra Low") {
+        Some(BerlingerModel::FridgeTagUL)
+    } else if device.ends_with("Fridge-tag 2 L") {
+        Some(BerlingerModel::FridgeTag2L)
+    } else if device.ends_with("Fridge-tag 2") {
+        Some(BerlingerModel::FridgeTag2)
+    } else if device.starts_with("Q-tag CLm") {
+        Some(BerlingerModel::QtagCLm)
+    } else {
+        None
+    }
+}
+
 fn read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
 where
     P: AsRef<Path>,
@@ -27,6 +65,70 @@ where
     Ok(io::BufReader::new(file).lines())
 }
 
+/// Removes the UTF-8 byte order mark which some Windows tools add to the start of the file.
+fn strip_bom(file_bytes: &[u8]) -> &[u8] {
+    file_bytes
+        .strip_prefix(b"\xEF\xBB\xBF")
+        .unwrap_or(file_bytes)
+}
+
+#[cfg(feature = "flate2")]
+/// Decompresses gzip-compressed file contents (e.g. from archival storage), leaving anything
+/// without the gzip magic bytes unchanged.
+fn decompress_gzip(file_bytes: Vec<u8>) -> Result<Vec<u8>, TemperatureSensorError> {
+    if !file_bytes.starts_with(&[0x1f, 0x8b]) {
+        return Ok(file_bytes);
+    }
+
+    let mut decompressed = Vec::new();
+    flate2::read::MultiGzDecoder::new(file_bytes.as_slice())
+        .read_to_end(&mut decompressed)
+        .map_err(|error| TemperatureSensorError::ParseError {
+            line: 0,
+            content: error.to_string(),
+            field: "gzip data".to_string(),
+        })?;
+    Ok(decompressed)
+}
+
+/// Converts CRLF and CR line endings to LF.
+fn normalize_line_endings(file_bytes: &[u8]) -> Vec<u8> {
+    let mut normalized = Vec::with_capacity(file_bytes.len());
+    let mut bytes = file_bytes.iter().peekable();
+    while let Some(byte) = bytes.next() {
+        if *byte == b'\r' {
+            if bytes.peek() != Some(&&b'\n') {
+                normalized.push(b'\n');
+            }
+        } else {
+            normalized.push(*byte);
+        }
+    }
+    normalized
+}
+
+/// Returns whether a comma is used as the decimal separator, i.e. there is a comma between
+/// two digits (fields are separated by a comma and a space, so never match).
+fn uses_decimal_commas(file_bytes: &[u8]) -> bool {
+    file_bytes
+        .windows(3)
+        .any(|window| window[0].is_ascii_digit() && window[1] == b',' && window[2].is_ascii_digit())
+}
+
+/// Converts decimal commas (e.g. in "8,8") to decimal points.
+fn normalize_decimal_commas(file_bytes: &[u8]) -> Vec<u8> {
+    let mut normalized = file_bytes.to_vec();
+    for index in 1..normalized.len().saturating_sub(1) {
+        if normalized[index] == b','
+            && normalized[index - 1].is_ascii_digit()
+            && normalized[index + 1].is_ascii_digit()
+        {
+            normalized[index] = b'.';
+        }
+    }
+    normalized
+}
+
 fn count_whitespace_at_start(input: &str) -> usize {
     input
         .chars()
@@ -34,7 +136,172 @@ fn count_whitespace_at_start(input: &str) -> usize {
         .count()
 }
 
-fn read_sensor_to_json(file_path: &str) -> Value {
+/// Column positions in a tab-delimited (Data or Marker) table, as read from its header
+/// row. The optional columns vary by sensor configuration, so rows are parsed by the
+/// header positions rather than fixed positions.
+#[derive(Debug, Default)]
+struct TableColumns {
+    timestamp: Option<usize>,
+    temperature: Option<usize>,
+    breach: Option<usize>,
+    number: Option<usize>,
+}
+
+impl TableColumns {
+    fn from_header(header: &[&str]) -> Self {
+        let mut columns = TableColumns::default();
+
+        for (index, column_name) in header.iter().enumerate() {
+            match column_name.trim() {
+                "T" => columns.temperature = Some(index),
+                "Out of limits" => columns.breach = Some(index),
+                "Number" => columns.number = Some(index),
+                name if name.starts_with("yyyy") => columns.timestamp = Some(index),
+                _ => {} // ignore unknown columns (e.g. humidity)
+            }
+        }
+
+        columns
+    }
+
+    fn value<'a>(&self, row: &[&'a str], column: Option<usize>) -> &'a str {
+        // trailing empty columns are trimmed from the row
+        column
+            .and_then(|index| row.get(index))
+            .map(|value| value.trim())
+            .unwrap_or("")
+    }
+}
+
+/// Define the expected format of the txt fields which are parsed into the sensor.
+enum FieldFormat {
+    Float,
+    Integer,
+    Interval,
+    Date,
+    TimeOrTimestamp,
+    AlarmType,
+}
+
+fn field_format(level_1: &str, level_2: &str, tag: &str) -> Option<FieldFormat> {
+    match tag {
+        "T AL" | "Min T" | "Max T" | "Avrg T" | "T M" | "Temperature" => Some(FieldFormat::Float),
+        "Temp Scale" => Some(FieldFormat::Float),
+        "t AL" | "t Acc" | "t A" => Some(FieldFormat::Integer),
+        "Logging Interval" => Some(FieldFormat::Interval),
+        "Date" if level_1 == "Hist" => Some(FieldFormat::Date),
+        "TS Actv" | "TS Report Creation" | "TS Start" | "TS Stop" | "TS Min T" | "TS Max T"
+        | "TS A" | "TS S" | "TS E" | "TS M" | "TS Batt Temp" | "TS Batt Crit" | "Timestamp" => {
+            Some(FieldFormat::TimeOrTimestamp)
+        }
+        "Type" if level_1 == "Conf" && level_2 == "Alarm" => Some(FieldFormat::AlarmType),
+        _ => None, // not parsed, so any format is accepted
+    }
+}
+
+fn is_missing_reading(field_value: &str) -> bool {
+    // e.g. "--.-" when the external sensor was disconnected
+    !field_value.is_empty() && field_value.chars().all(|ch| ch == '-' || ch == '.')
+}
+
+fn is_valid_field(field_format: &FieldFormat, field_value: &str) -> bool {
+    let json_value = Value::from(field_value);
+
+    match field_format {
+        FieldFormat::Float => parse_float(&json_value).is_some() || is_missing_reading(field_value),
+        FieldFormat::Integer => parse_int(&json_value).is_some(),
+        FieldFormat::Interval => parse_interval(&json_value, IntervalUnit::Minutes).is_some(),
+        FieldFormat::Date => parse_date(&json_value).is_some(),
+        FieldFormat::TimeOrTimestamp => {
+            parse_time(&json_value).is_some() || parse_timestamp(&json_value).is_some()
+        }
+        FieldFormat::AlarmType => matches!(parse_int(&json_value), Some(0..=4)), // 0 => not used
+    }
+}
+
+fn validate_field(
+    level_1: &str,
+    level_2: &str,
+    tag: &str,
+    field_value: &str,
+    line_number: usize,
+    line: &str,
+) -> Result<(), TemperatureSensorError> {
+    if let Some(field_format) = field_format(level_1, level_2, tag) {
+        if !is_valid_field(&field_format, field_value) {
+            return Err(TemperatureSensorError::ParseError {
+                line: line_number,
+                content: line.to_string(),
+                field: tag.to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Returns the line of a txt file error, if known.
+fn error_line(error: &TemperatureSensorError) -> Option<usize> {
+    match error {
+        TemperatureSensorError::ParseError { line, .. } => Some(*line),
+        _ => None,
+    }
+}
+
+/// Returns the contents of the file before the specified line, if the rest of the file
+/// after it is blank (i.e. it's the last line).
+fn lines_before_last(file_bytes: &[u8], line_number: usize) -> Option<&[u8]> {
+    let mut lines = file_bytes.split(|byte| *byte == b'\n');
+    let start: usize = lines
+        .by_ref()
+        .take(line_number.checked_sub(1)?)
+        .map(|line| line.len() + 1)
+        .sum();
+    lines.next()?;
+    lines
+        .all(|line| line.iter().all(u8::is_ascii_whitespace))
+        .then(|| &file_bytes[..start.min(file_bytes.len())])
+}
+
+/// Reads the contents of a sensor txt file into JSON. If lenient, a file whose last line
+/// can't be parsed (e.g. if it was cut off part way through writing) is read up to that
+/// line, returning its line number too.
+fn read_sensor_to_json<R: Read>(
+    mut reader: R,
+    decimal_comma: Option<bool>,
+    lenient: bool,
+) -> Result<(Value, Option<usize>), TemperatureSensorError> {
+    let mut file_bytes = Vec::new();
+    if let Err(error) = reader.read_to_end(&mut file_bytes) {
+        return Err(TemperatureSensorError::ParseError {
+            line: 0,
+            content: error.to_string(),
+            field: "file".to_string(),
+        });
+    }
+    #[cfg(feature = "flate2")]
+    let file_bytes = decompress_gzip(file_bytes)?;
+    let mut file_bytes = normalize_line_endings(strip_bom(&file_bytes));
+    if decimal_comma.unwrap_or_else(|| uses_decimal_commas(&file_bytes)) {
+        file_bytes = normalize_decimal_commas(&file_bytes);
+    }
+
+    match txt_to_json(&file_bytes) {
+        Err(error) if lenient => {
+            if let Some((line_number, lines_before)) = error_line(&error).and_then(|line| {
+                lines_before_last(&file_bytes, line).map(|lines_before| (line, lines_before))
+            }) {
+                // a garbled header still fails, as there are no lines before it
+                if let Ok(json) = txt_to_json(lines_before) {
+                    return Ok((json, Some(line_number)));
+                }
+            }
+            Err(error)
+        }
+        result => result.map(|json| (json, None)),
+    }
+}
+
+fn txt_to_json(file_bytes: &[u8]) -> Result<Value, TemperatureSensorError> {
     let mut current_json = json!({});
     let mut data_timestamps: Vec<Value> = Vec::new();
     let mut data_temperatures: Vec<Value> = Vec::new();
@@ -42,6 +309,8 @@ fn read_sensor_to_json(file_path: &str) -> Value {
     let mut marker_timestamps: Vec<Value> = Vec::new();
     let mut marker_temperatures: Vec<Value> = Vec::new();
     let mut marker_numbers: Vec<Value> = Vec::new();
+    let mut data_columns: Option<TableColumns> = None;
+    let mut marker_columns: Option<TableColumns> = None;
     let mut breach_start_timestamps: Vec<Value> = Vec::new();
     let mut breach_end_timestamps: Vec<Value> = Vec::new();
     let mut breach_durations: Vec<Value> = Vec::new();
@@ -54,163 +323,223 @@ fn read_sensor_to_json(file_path: &str) -> Value {
     let mut level_4 = String::new();
     let mut json_tag; // = String::new();
     let mut json_value; // = "";
+    let mut header_found = false;
 
-    if let Ok(lines) = read_lines(file_path) {
-        for line in lines {
-            if let Ok(contents) = line {
-                let level = count_whitespace_at_start(&contents);
-                let elements = contents.split(", ");
+    for (line_index, line_bytes) in file_bytes.split(|byte| *byte == b'\n').enumerate() {
+        let line_number = line_index + 1;
+        let contents = match std::str::from_utf8(line_bytes) {
+            Ok(contents) => contents,
+            Err(_) => {
+                return Err(TemperatureSensorError::ParseError {
+                    line: line_number,
+                    content: String::from_utf8_lossy(line_bytes).to_string(),
+                    field: "text".to_string(),
+                });
+            }
+        };
+        if !header_found {
+            if contents.trim().is_empty() {
+                continue;
+            }
+            // Berlinger txt files always start with the device name
+            if !contents.starts_with("Device:") {
+                return Err(TemperatureSensorError::ParseError {
+                    line: line_number,
+                    content: contents.to_string(),
+                    field: "Device".to_string(),
+                });
+            }
+            header_found = true;
+        }
+        let level = count_whitespace_at_start(contents);
+        let elements = contents.split(", ");
+
+        for element in elements {
+            let json_elements: Vec<&str> = element.trim().split(": ").collect();
 
-                for element in elements {
-                    let json_elements: Vec<&str> = element.trim().split(": ").collect();
+            if !json_elements[0].is_empty() {
+                let last_char = json_elements[0].chars().last().unwrap(); // should be safe as we've checked for non-empty element
+                let mut new_level = false;
+                let element_count = json_elements.len();
 
-                    if json_elements[0].len() > 0 {
-                        let last_char = json_elements[0].chars().last().unwrap(); // should be safe as we've checked for non-empty element
-                        let mut new_level = false;
-                        let element_count = json_elements.len();
+                json_tag = json_elements[0].to_string();
 
-                        json_tag = json_elements[0].to_string();
+                if last_char == ':' {
+                    json_tag.pop(); // remove trailing :
+
+                    if element_count == 1 {
+                        // new level if only one element in the line
+                        new_level = true;
+                    }
+                }
 
-                        if last_char == ':' {
-                            json_tag.pop(); // remove trailing :
+                if new_level {
+                    // start of new level
 
-                            if element_count == 1 {
-                                // new level if only one element in the line
-                                new_level = true;
+                    match level {
+                        0 => {
+                            level_1 = json_tag.clone();
+                            if level_1 != "Data" && level_1 != "Marker" {
+                                // regular format (Data and Marker sections are tab-delimited)
+                                current_json[&level_1] = json!({});
+                            }
+                        }
+                        1 => {
+                            level_2 = json_tag.clone();
+                            current_json[&level_1][&level_2] = json!({});
+                        }
+                        2 => {
+                            if level_1 == "Res" && level_2 == "Alarm" {
+                                // QTag can have multiple alarms for the same breach type - initialise here
+                                breach_start_timestamps = Vec::new();
+                                breach_end_timestamps = Vec::new();
+                                breach_durations = Vec::new();
+                                breach_temperatures = Vec::new();
+                                breach_timestamps = Vec::new();
+                                breach_activation_timestamps = Vec::new();
                             }
+                            level_3 = json_tag.clone();
+                            current_json[&level_1][&level_2][&level_3] = json!({});
                         }
+                        3 => {
+                            level_4 = json_tag.clone();
+                            current_json[&level_1][&level_2][&level_3][&level_4] = json!({});
+                        }
+                        _ => {} // do nothing - max level expected is 4
+                    }
+                } else {
+                    if element_count > 1 {
+                        // regular line format
 
-                        if new_level {
-                            // start of new level
+                        json_value = json_elements[1];
+                        validate_field(
+                            &level_1,
+                            &level_2,
+                            &json_tag,
+                            json_value,
+                            line_number,
+                            contents,
+                        )?;
 
-                            match level {
-                                0 => {
-                                    level_1 = json_tag.clone();
-                                    if level_1 != "Data" && level_1 != "Marker" {
-                                        // regular format (Data and Marker sections are tab-delimited)
-                                        current_json[&level_1] = json!({});
-                                    }
-                                }
-                                1 => {
-                                    level_2 = json_tag.clone();
-                                    current_json[&level_1][&level_2] = json!({});
-                                }
-                                2 => {
-                                    if level_1 == "Res" && level_2 == "Alarm" {
-                                        // QTag can have multiple alarms for the same breach type - initialise here
-                                        breach_start_timestamps = Vec::new();
-                                        breach_end_timestamps = Vec::new();
-                                        breach_durations = Vec::new();
-                                        breach_temperatures = Vec::new();
-                                        breach_timestamps = Vec::new();
-                                        breach_activation_timestamps = Vec::new();
+                        match level {
+                            0 => current_json[json_tag] = json_value.into(),
+                            1 => current_json[&level_1][json_tag] = json_value.into(),
+                            2 => current_json[&level_1][&level_2][json_tag] = json_value.into(),
+                            3 => {
+                                if level_1 == "Res" && level_2 == "Alarm" {
+                                    // QTag breach
+                                    match json_tag.as_str() {
+                                        "TS S" => {
+                                            // breach start timestamp
+                                            breach_start_timestamps.push(json_value.into());
+                                            current_json[&level_1][&level_2][&level_3][json_tag] =
+                                                Value::Array(breach_start_timestamps.clone());
+                                        }
+                                        "TS E" => {
+                                            // breach end timestamp (optional)
+                                            breach_end_timestamps.push(json_value.into());
+                                            current_json[&level_1][&level_2][&level_3][json_tag] =
+                                                Value::Array(breach_end_timestamps.clone());
+                                        }
+                                        "t A" => {
+                                            // breach duration
+                                            breach_durations.push(json_value.into());
+                                            current_json[&level_1][&level_2][&level_3][json_tag] =
+                                                Value::Array(breach_durations.clone());
+                                        }
+                                        "T M" => {
+                                            // max/min breach temperature
+                                            breach_temperatures.push(json_value.into());
+                                            current_json[&level_1][&level_2][&level_3][json_tag] =
+                                                Value::Array(breach_temperatures.clone());
+                                        }
+                                        "TS M" => {
+                                            //max/min breach timestamp
+                                            breach_timestamps.push(json_value.into());
+                                            current_json[&level_1][&level_2][&level_3][json_tag] =
+                                                Value::Array(breach_timestamps.clone());
+                                        }
+                                        "TS A" => {
+                                            // breach activation timestamp
+                                            breach_activation_timestamps.push(json_value.into());
+                                            current_json[&level_1][&level_2][&level_3][json_tag] =
+                                                Value::Array(breach_activation_timestamps.clone());
+                                        }
+                                        _ => {} // do nothing - no other tags expected,
                                     }
-                                    level_3 = json_tag.clone();
-                                    current_json[&level_1][&level_2][&level_3] = json!({});
+                                } else {
+                                    current_json[&level_1][&level_2][&level_3][json_tag] =
+                                        json_value.into()
                                 }
-                                3 => {
-                                    level_4 = json_tag.clone();
-                                    current_json[&level_1][&level_2][&level_3][&level_4] =
-                                        json!({});
-                                }
-                                _ => {} // do nothing - max level expected is 4
                             }
-                        } else {
-                            if element_count > 1 {
-                                // regular line format
-
-                                json_value = json_elements[1];
+                            4 => {
+                                current_json[&level_1][&level_2][&level_3][&level_4][json_tag] =
+                                    json_value.into()
+                            }
+                            _ => {} // do nothing - 4 is maximum level expected
+                        }
+                    } else {
+                        // tab-delimited line format
 
-                                match level {
-                                    0 => current_json[json_tag] = json_value.into(),
-                                    1 => current_json[&level_1][json_tag] = json_value.into(),
-                                    2 => {
-                                        current_json[&level_1][&level_2][json_tag] =
-                                            json_value.into()
-                                    }
-                                    3 => {
-                                        if level_1 == "Res" && level_2 == "Alarm" {
-                                            // QTag breach
-                                            match json_tag.as_str() {
-                                                "TS S" => {
-                                                    // breach start timestamp
-                                                    breach_start_timestamps.push(json_value.into());
-                                                    current_json[&level_1][&level_2][&level_3]
-                                                        [json_tag] = Value::Array(
-                                                        breach_start_timestamps.clone(),
-                                                    );
-                                                }
-                                                "TS E" => {
-                                                    // breach end timestamp (optional)
-                                                    breach_end_timestamps.push(json_value.into());
-                                                    current_json[&level_1][&level_2][&level_3]
-                                                        [json_tag] =
-                                                        Value::Array(breach_end_timestamps.clone());
-                                                }
-                                                "t A" => {
-                                                    // breach duration
-                                                    breach_durations.push(json_value.into());
-                                                    current_json[&level_1][&level_2][&level_3]
-                                                        [json_tag] =
-                                                        Value::Array(breach_durations.clone());
-                                                }
-                                                "T M" => {
-                                                    // max/min breach temperature
-                                                    breach_temperatures.push(json_value.into());
-                                                    current_json[&level_1][&level_2][&level_3]
-                                                        [json_tag] =
-                                                        Value::Array(breach_temperatures.clone());
-                                                }
-                                                "TS M" => {
-                                                    //max/min breach timestamp
-                                                    breach_timestamps.push(json_value.into());
-                                                    current_json[&level_1][&level_2][&level_3]
-                                                        [json_tag] =
-                                                        Value::Array(breach_timestamps.clone());
-                                                }
-                                                "TS A" => {
-                                                    // breach activation timestamp
-                                                    breach_activation_timestamps
-                                                        .push(json_value.into());
-                                                    current_json[&level_1][&level_2][&level_3]
-                                                        [json_tag] = Value::Array(
-                                                        breach_activation_timestamps.clone(),
-                                                    );
-                                                }
-                                                _ => {} // do nothing - no other tags expected,
-                                            }
-                                        } else {
-                                            current_json[&level_1][&level_2][&level_3][json_tag] =
-                                                json_value.into()
+                        let tab_elements: Vec<&str> = json_tag.split('\t').collect();
+                        if level_1 == "Data" {
+                            match &data_columns {
+                                None => {
+                                    // first row is the header
+                                    data_columns = Some(TableColumns::from_header(&tab_elements))
+                                }
+                                Some(columns) => {
+                                    for (column, column_name) in [
+                                        (columns.timestamp, "timestamp"),
+                                        (columns.temperature, "temperature"),
+                                    ] {
+                                        if column.is_some_and(|index| index >= tab_elements.len()) {
+                                            return Err(TemperatureSensorError::ParseError {
+                                                line: line_number,
+                                                content: contents.to_string(),
+                                                field: column_name.to_string(),
+                                            });
                                         }
                                     }
-                                    4 => {
-                                        current_json[&level_1][&level_2][&level_3][&level_4]
-                                            [json_tag] = json_value.into()
+                                    let timestamp = columns.value(&tab_elements, columns.timestamp);
+                                    let temperature =
+                                        columns.value(&tab_elements, columns.temperature);
+                                    for (field, field_value) in
+                                        [("Timestamp", timestamp), ("Temperature", temperature)]
+                                    {
+                                        validate_field(
+                                            &level_1,
+                                            &level_2,
+                                            field,
+                                            field_value,
+                                            line_number,
+                                            contents,
+                                        )?;
                                     }
-                                    _ => {} // do nothing - 4 is maximum level expected
+                                    data_timestamps.push(timestamp.into());
+                                    data_temperatures.push(temperature.into());
+                                    // optional breach flag column
+                                    data_breaches.push(Value::Bool(
+                                        !columns.value(&tab_elements, columns.breach).is_empty(),
+                                    ));
                                 }
-                            } else {
-                                // tab-delimited line format
-
-                                let tab_elements: Vec<&str> = json_tag.split("\t").collect();
-                                if level_1 == "Data" {
-                                    // timestamp & temperature columns expected
-                                    data_timestamps.push(tab_elements[0].into());
-                                    data_temperatures.push(tab_elements[1].into());
-
-                                    if tab_elements.len() > 2 {
-                                        // optional breach flag column
-                                        data_breaches.push(Value::Bool(true));
-                                    } else {
-                                        data_breaches.push(Value::Bool(false));
-                                    }
+                            }
+                        }
+                        if level_1 == "Marker" {
+                            match &marker_columns {
+                                None => {
+                                    // first row is the header
+                                    marker_columns = Some(TableColumns::from_header(&tab_elements))
                                 }
-                                if level_1 == "Marker" {
-                                    // 3 columns expected: index, temperature & timestamp
-                                    marker_numbers.push(tab_elements[0].into());
-                                    marker_temperatures.push(tab_elements[1].into());
-                                    marker_timestamps.push(tab_elements[2].into());
+                                Some(columns) => {
+                                    marker_numbers
+                                        .push(columns.value(&tab_elements, columns.number).into());
+                                    marker_temperatures.push(
+                                        columns.value(&tab_elements, columns.temperature).into(),
+                                    );
+                                    marker_timestamps.push(
+                                        columns.value(&tab_elements, columns.timestamp).into(),
+                                    );
                                 }
                             }
                         }
@@ -220,35 +549,30 @@ fn read_sensor_to_json(file_path: &str) -> Value {
         }
     }
 
+    if !header_found {
+        // the file is empty, or only has blank lines
+        return Err(TemperatureSensorError::ParseError {
+            line: 0,
+            content: "file is empty or contains no sensor header".to_string(),
+            field: "Device".to_string(),
+        });
+    }
+
     // Add in tab-delimited data and markers
 
-    if data_timestamps.len() > 1 {
-        data_timestamps.remove(0); // remove first element as it is a header
+    if !data_timestamps.is_empty() {
         current_json["Data"]["Timestamp"] = Value::Array(data_timestamps);
-    }
-    if data_temperatures.len() > 1 {
-        data_temperatures.remove(0); // remove first element as it is a header
         current_json["Data"]["Temperature"] = Value::Array(data_temperatures);
-    }
-    if data_breaches.len() > 0 {
-        data_breaches.remove(0); // remove first element as it is a header
         current_json["Data"]["Breach"] = Value::Array(data_breaches);
     }
 
-    if marker_timestamps.len() > 1 {
-        marker_timestamps.remove(0); // remove first element as it is a header
+    if !marker_timestamps.is_empty() {
         current_json["Marker"]["Timestamp"] = Value::Array(marker_timestamps);
-    }
-    if marker_temperatures.len() > 1 {
-        marker_temperatures.remove(0); // remove first element as it is a header
         current_json["Marker"]["Temperature"] = Value::Array(marker_temperatures);
-    }
-    if marker_numbers.len() > 0 {
-        marker_numbers.remove(0); // remove first element as it is a header
         current_json["Marker"]["Number"] = Value::Array(marker_numbers);
     }
 
-    current_json
+    Ok(current_json)
 }
 
 fn parse_string(json_str: &Value) -> String {
@@ -256,8 +580,11 @@ fn parse_string(json_str: &Value) -> String {
 }
 
 fn parse_timestamp(json_str: &Value) -> Option<NaiveDateTime> {
+    // usually to the minute, but seconds (and fractions of a second) are kept if present
     let parsed_string = parse_string(json_str);
-    NaiveDateTime::parse_from_str(&parsed_string, "%Y-%m-%d %H:%M").ok()
+    NaiveDateTime::parse_from_str(&parsed_string, "%Y-%m-%d %H:%M")
+        .or_else(|_| NaiveDateTime::parse_from_str(&parsed_string, "%Y-%m-%d %H:%M:%S%.f"))
+        .ok()
 }
 
 fn parse_date(json_str: &Value) -> Option<NaiveDate> {
@@ -267,7 +594,19 @@ fn parse_date(json_str: &Value) -> Option<NaiveDate> {
 
 fn parse_time(json_str: &Value) -> Option<NaiveTime> {
     let parsed_string = parse_string(json_str);
-    NaiveTime::parse_from_str(&parsed_string, "%H:%M").ok()
+    NaiveTime::parse_from_str(&parsed_string, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(&parsed_string, "%H:%M:%S%.f"))
+        .ok()
+}
+
+fn parse_timezone(json_str: &Value) -> Option<FixedOffset> {
+    // e.g. "+05:30", optionally prefixed with "UTC"
+    let parsed_string = parse_string(json_str);
+    parsed_string
+        .trim()
+        .trim_start_matches("UTC")
+        .parse::<FixedOffset>()
+        .ok()
 }
 
 fn parse_int(json_str: &Value) -> Option<i64> {
@@ -280,14 +619,30 @@ fn parse_float(json_str: &Value) -> Option<f64> {
     parsed_string.parse::<f64>().ok()
 }
 
+fn parse_temperature(json_str: &Value) -> Option<Celsius> {
+    parse_float(json_str).map(Celsius)
+}
+
 fn parse_duration(json_str: &Value) -> Option<Duration> {
     // in minutes
 
-    if let Some(minutes) = parse_int(json_str) {
-        Some(Duration::minutes(minutes))
-    } else {
-        None
-    }
+    parse_int(json_str).map(Duration::minutes)
+}
+
+fn parse_interval(json_str: &Value, default_unit: IntervalUnit) -> Option<Duration> {
+    // e.g. "5", or with the unit stated, "300 s" or "5 min"
+    let parsed_string = parse_string(json_str);
+    let unit_start = parsed_string
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(parsed_string.len());
+    let amount = parsed_string[..unit_start].parse::<i64>().ok()?;
+    let unit = match parsed_string[unit_start..].trim() {
+        "" => default_unit,
+        "s" | "sec" | "secs" | "seconds" => IntervalUnit::Seconds,
+        "min" | "mins" | "minutes" => IntervalUnit::Minutes,
+        _ => return None,
+    };
+    Some(unit.duration(amount))
 }
 
 fn parse_subtype(json_str: &Value) -> SensorSubType {
@@ -304,15 +659,15 @@ fn parse_breach_configs(
     sensor_subtype: &SensorSubType,
 ) -> Option<Vec<TemperatureBreachConfig>> {
     let mut breach_configs: Vec<TemperatureBreachConfig> = Vec::new();
-    let max_breach_temperature = 100.0; // boiling point of water (should be safe default max!)
-    let min_breach_temperature = -273.0; // absolute zero (should be safe default min!)
+    let max_breach_temperature = Celsius(100.0); // boiling point of water (should be safe default max!)
+    let min_breach_temperature = Celsius(-273.0); // absolute zero (should be safe default min!)
     let mut max_temperature; // = 0.0;
     let mut min_temperature; // = 0.0;
 
     match sensor_subtype {
         SensorSubType::FridgeTag => {
             // duplicate cumulative breach config as a consecutive breach as well
-            if let Some(temperature) = parse_float(&json_str["0"]["T AL"]) {
+            if let Some(temperature) = parse_temperature(&json_str["0"]["T AL"]) {
                 // COLD
                 max_temperature = max_breach_temperature;
                 min_temperature = temperature;
@@ -322,18 +677,18 @@ fn parse_breach_configs(
                         breach_type: BreachType::ColdConsecutive,
                         maximum_temperature: max_temperature,
                         minimum_temperature: min_temperature,
-                        duration: duration,
+                        duration,
                     });
                     breach_configs.push(TemperatureBreachConfig {
                         breach_type: BreachType::ColdCumulative,
                         maximum_temperature: max_temperature,
                         minimum_temperature: min_temperature,
-                        duration: duration,
+                        duration,
                     });
                 }
             }
 
-            if let Some(temperature) = parse_float(&json_str["1"]["T AL"]) {
+            if let Some(temperature) = parse_temperature(&json_str["1"]["T AL"]) {
                 // HOT
                 min_temperature = min_breach_temperature;
                 max_temperature = temperature;
@@ -343,13 +698,13 @@ fn parse_breach_configs(
                         breach_type: BreachType::HotConsecutive,
                         maximum_temperature: max_temperature,
                         minimum_temperature: min_temperature,
-                        duration: duration,
+                        duration,
                     });
                     breach_configs.push(TemperatureBreachConfig {
                         breach_type: BreachType::HotCumulative,
                         maximum_temperature: max_temperature,
                         minimum_temperature: min_temperature,
-                        duration: duration,
+                        duration,
                     });
                 }
             }
@@ -363,7 +718,7 @@ fn parse_breach_configs(
                     // skip blank alarm
                     continue;
                 } else {
-                    if let Some(temperature) = parse_float(&json_config["T AL"]) {
+                    if let Some(temperature) = parse_temperature(&json_config["T AL"]) {
                         // breach temperature
                         if let Some(duration) = parse_duration(&json_config["t AL"]) {
                             // breach duration threshold
@@ -403,7 +758,7 @@ fn parse_breach_configs(
                                         breach_type: temperature_breach_type,
                                         maximum_temperature: max_temperature,
                                         minimum_temperature: min_temperature,
-                                        duration: duration,
+                                        duration,
                                     })
                                 }
                             }
@@ -414,7 +769,7 @@ fn parse_breach_configs(
         }
     }
 
-    if breach_configs.len() > 0 {
+    if !breach_configs.is_empty() {
         Some(breach_configs)
     } else {
         None
@@ -429,8 +784,7 @@ fn parse_fridgetag_breach(
 ) -> Option<TemperatureBreach> {
     let mut breach_duration = Duration::seconds(0);
     let mut config_duration = Duration::seconds(0);
-    let zero_time = NaiveTime::parse_from_str("00:00", "%H:%M").unwrap(); // hard-coded -> should always work!
-    let mut start_time = zero_time;
+    let mut trigger_time = None;
     let mut valid_breach; // = true;
 
     if let Some(duration) = parse_duration(&json_breach["t Acc"]) {
@@ -449,42 +803,58 @@ fn parse_fridgetag_breach(
         } else {
             valid_breach = false;
         }
-        // Subtract breach duration from activation time to get start time
         if let Some(breach_time) = parse_time(&json_breach["TS A"]) {
             // breach activation time
-            if breach_time > zero_time + config_duration {
-                // need to add zero_time to duration to make it a NaiveTime
-                start_time = breach_time - config_duration
-            } else {
-                start_time = zero_time;
-            }
+            trigger_time = Some(NaiveDateTime::new(breach_date, breach_time));
         } else {
             valid_breach = false;
         }
     }
 
-    if valid_breach {
-        let breach_start_timestamp = NaiveDateTime::new(breach_date, start_time);
-        let mut breach_end_timestamp = breach_start_timestamp + breach_duration; // only true for consecutive breaches, but this is all the data we have for FridgeTags
-
-        if breach_end_timestamp.date() > breach_date {
-            // FridgeTag breach can't go into next day
-            breach_end_timestamp = NaiveDateTime::new(breach_date, zero_time) + Duration::days(1);
-        }
-
-        let temperature_breach = TemperatureBreach {
-            breach_type: breach_type,
-            start_timestamp: breach_start_timestamp,
-            end_timestamp: breach_end_timestamp,
-            duration: breach_duration,
-            acknowledged: false,
-        };
-        Some(temperature_breach)
+    if let (true, Some(trigger_timestamp)) = (valid_breach, trigger_time) {
+        Some(fridgetag_breach(
+            breach_type,
+            breach_date,
+            trigger_timestamp,
+            config_duration,
+            breach_duration,
+        ))
     } else {
         None
     }
 }
 
+/// Returns a Fridge-tag cumulative breach from the alarm trigger time & total duration
+/// recorded for the day.
+fn fridgetag_breach(
+    breach_type: BreachType,
+    breach_date: NaiveDate,
+    trigger_timestamp: NaiveDateTime,
+    config_duration: Duration,
+    breach_duration: Duration,
+) -> TemperatureBreach {
+    // Subtract the config duration from the activation time to get the start time, and
+    // add the total duration to that to get the end time (only true for consecutive
+    // breaches, but this is all the data we have for FridgeTags) - neither can go into
+    // another day
+    let (breach_start_timestamp, breach_end_timestamp) = clamp_to_day(
+        breach_date,
+        trigger_timestamp - config_duration,
+        breach_duration,
+    );
+
+    TemperatureBreach {
+        breach_type,
+        start_timestamp: breach_start_timestamp,
+        end_timestamp: breach_end_timestamp,
+        duration: breach_duration,
+        acknowledged: false,
+        peak_temperature: None,
+        trigger_timestamp: Some(trigger_timestamp),
+        annotation: None,
+    }
+}
+
 fn qtag_breach_type(alarm_type: i64) -> Option<BreachType> {
     match alarm_type {
         1 => Some(BreachType::ColdConsecutive),
@@ -495,7 +865,11 @@ fn qtag_breach_type(alarm_type: i64) -> Option<BreachType> {
     }
 }
 
-fn parse_qtag_breach(json_breach: &Value, alarm_type: i64) -> Option<Vec<TemperatureBreach>> {
+fn parse_qtag_breach(
+    json_breach: &Value,
+    alarm_type: i64,
+    timezone: Option<FixedOffset>,
+) -> Option<Vec<TemperatureBreach>> {
     let mut temperature_breaches: Vec<TemperatureBreach> = Vec::new();
     let mut breach_index; // = 1;
     breach_index = 0; // for some weird reason, it didn't work if I just initialised it to zero above??
@@ -518,22 +892,35 @@ fn parse_qtag_breach(json_breach: &Value, alarm_type: i64) -> Option<Vec<Tempera
 
                     if let Some(breach_type) = qtag_breach_type(alarm_type) {
                         // lookup breach type
-                        temperature_breaches.push(TemperatureBreach {
-                            breach_type: breach_type,
+                        let mut breach = TemperatureBreach {
+                            breach_type,
                             start_timestamp: breach_start_timestamp,
                             end_timestamp: breach_end_timestamp,
                             duration: breach_duration,
                             acknowledged: false,
-                        });
+                            peak_temperature: None,
+                            trigger_timestamp: parse_timestamp(&json_breach["TS A"][breach_index]),
+                            annotation: None,
+                        };
+                        // e.g. an end timestamp before the start, or a negative duration
+                        breach.clamp_end_to_start();
+                        match breach.breach_type {
+                            // cumulative breaches are counted per (device) day
+                            BreachType::HotCumulative | BreachType::ColdCumulative => {
+                                temperature_breaches
+                                    .extend(split_breach_by_local_day(&breach, timezone))
+                            }
+                            _ => temperature_breaches.push(breach),
+                        }
                     }
                 }
             }
         }
 
-        breach_index = breach_index + 1;
+        breach_index += 1;
     }
 
-    if temperature_breaches.len() > 0 {
+    if !temperature_breaches.is_empty() {
         Some(temperature_breaches)
     } else {
         None
@@ -543,6 +930,7 @@ fn parse_qtag_breach(json_breach: &Value, alarm_type: i64) -> Option<Vec<Tempera
 fn parse_breaches(
     json_str: &Value,
     sensor_subtype: &SensorSubType,
+    timezone: Option<FixedOffset>,
 ) -> Option<Vec<TemperatureBreach>> {
     let mut breaches: Vec<TemperatureBreach> = Vec::new();
     let mut alarm_index = 1;
@@ -578,7 +966,7 @@ fn parse_breaches(
                     }
                 }
             }
-            alarm_index = alarm_index + 1;
+            alarm_index += 1;
         },
         SensorSubType::QTag => {
             // 5 fixed alarms, not all populated
@@ -594,12 +982,10 @@ fn parse_breaches(
                     // breach type
                     {
                         if let Some(temperature_breaches) =
-                            parse_qtag_breach(&json_alarm, alarm_type)
+                            parse_qtag_breach(json_alarm, alarm_type, timezone)
                         // can be multiple breaches
                         {
-                            for breach_index in 0..=temperature_breaches.len() - 1 {
-                                breaches.push(temperature_breaches[breach_index].clone());
-                            }
+                            breaches.extend(temperature_breaches);
                         }
                     }
                 }
@@ -607,15 +993,39 @@ fn parse_breaches(
         }
     }
 
-    if breaches.len() > 0 {
-        breaches
-            .sort_unstable_by_key(|breaches| (breaches.start_timestamp, breaches.end_timestamp));
+    if !breaches.is_empty() {
         Some(breaches)
     } else {
         None
     }
 }
 
+fn parse_battery_low(json_str: &Value, sensor_subtype: &SensorSubType) -> Option<bool> {
+    match sensor_subtype {
+        // only Q-tags report the battery, with the timestamps when it reached the warning
+        // & critical levels (blank until then)
+        SensorSubType::FridgeTag => None,
+        SensorSubType::QTag => {
+            let battery_timestamps = [
+                &json_str["Res"]["TS Batt Temp"],
+                &json_str["Res"]["TS Batt Crit"],
+            ];
+            if battery_timestamps
+                .iter()
+                .all(|json_value| json_value.is_null())
+            {
+                None
+            } else {
+                Some(
+                    battery_timestamps
+                        .iter()
+                        .any(|json_value| parse_timestamp(json_value).is_some()),
+                )
+            }
+        }
+    }
+}
+
 fn parse_logs(json_str: &Value, sensor_subtype: &SensorSubType) -> Option<Vec<TemperatureLog>> {
     let mut logs: Vec<TemperatureLog> = Vec::new();
     let mut log_index = 1;
@@ -630,7 +1040,7 @@ fn parse_logs(json_str: &Value, sensor_subtype: &SensorSubType) -> Option<Vec<Te
                 break;
             } else {
                 if let Some(log_date) = parse_date(&json_log["Date"]) {
-                    if let Some(temperature_max) = parse_float(&json_log["Max T"]) {
+                    if let Some(temperature_max) = parse_temperature(&json_log["Max T"]) {
                         if let Some(temperature_max_time) = parse_time(&json_log["TS Max T"]) {
                             let temperature_max_log = TemperatureLog {
                                 timestamp: NaiveDateTime::new(log_date, temperature_max_time),
@@ -639,7 +1049,7 @@ fn parse_logs(json_str: &Value, sensor_subtype: &SensorSubType) -> Option<Vec<Te
                             logs.push(temperature_max_log);
                         }
                     }
-                    if let Some(temperature_min) = parse_float(&json_log["Min T"]) {
+                    if let Some(temperature_min) = parse_temperature(&json_log["Min T"]) {
                         if let Some(temperature_min_time) = parse_time(&json_log["TS Min T"]) {
                             let temperature_min_log = TemperatureLog {
                                 timestamp: NaiveDateTime::new(log_date, temperature_min_time),
@@ -650,11 +1060,11 @@ fn parse_logs(json_str: &Value, sensor_subtype: &SensorSubType) -> Option<Vec<Te
                     }
                 }
 
-                log_index = log_index + 1;
+                log_index += 1;
             }
         },
         SensorSubType::QTag => {
-            if let Some(temperature_min) = parse_float(&json_str["Res"]["Min T"]) {
+            if let Some(temperature_min) = parse_temperature(&json_str["Res"]["Min T"]) {
                 // min temperature
                 if let Some(timestamp_min) = parse_timestamp(&json_str["Res"]["TS Min T"]) {
                     // min timestamp
@@ -664,7 +1074,7 @@ fn parse_logs(json_str: &Value, sensor_subtype: &SensorSubType) -> Option<Vec<Te
                     })
                 }
             }
-            if let Some(temperature_max) = parse_float(&json_str["Res"]["Max T"]) {
+            if let Some(temperature_max) = parse_temperature(&json_str["Res"]["Max T"]) {
                 // max temperature
                 if let Some(timestamp_max) = parse_timestamp(&json_str["Res"]["TS Max T"]) {
                     // max timestamp
@@ -691,7 +1101,7 @@ fn parse_logs(json_str: &Value, sensor_subtype: &SensorSubType) -> Option<Vec<Te
                             break;
                         } else {
                             if let Some(log_temperature) =
-                                parse_float(&json_alarm["T M"][log_index])
+                                parse_temperature(&json_alarm["T M"][log_index])
                             // alarm temperature
                             {
                                 if let Some(log_timestamp) =
@@ -705,7 +1115,7 @@ fn parse_logs(json_str: &Value, sensor_subtype: &SensorSubType) -> Option<Vec<Te
                                 }
                             }
                         }
-                        log_index = log_index + 1;
+                        log_index += 1;
                     }
                 }
             }
@@ -725,7 +1135,9 @@ fn parse_logs(json_str: &Value, sensor_subtype: &SensorSubType) -> Option<Vec<Te
         } else {
             if let Some(log_timestamp) = parse_timestamp(&json_log["Timestamp"][log_index]) {
                 // timestamp
-                if let Some(log_temperature) = parse_float(&json_log["Temperature"][log_index]) {
+                if let Some(log_temperature) =
+                    parse_temperature(&json_log["Temperature"][log_index])
+                {
                     // temperature
                     logs.push(TemperatureLog {
                         timestamp: log_timestamp,
@@ -733,12 +1145,11 @@ fn parse_logs(json_str: &Value, sensor_subtype: &SensorSubType) -> Option<Vec<Te
                     })
                 }
             }
-            log_index = log_index + 1;
+            log_index += 1;
         }
     }
 
-    if logs.len() > 0 {
-        logs.sort_unstable_by_key(|logs| (logs.timestamp));
+    if !logs.is_empty() {
         Some(logs)
     } else {
         None
@@ -746,53 +1157,424 @@ fn parse_logs(json_str: &Value, sensor_subtype: &SensorSubType) -> Option<Vec<Te
 }
 
 /// Reads sensor data from the specified sensor txt file.
-pub fn read_sensor_from_file(file_path: &str) -> Option<Sensor> {
-    if Path::new(file_path).exists() {
-        let file_as_json = read_sensor_to_json(file_path);
+/// Returns a ParseError with the line number and content if a field which is needed
+/// for the sensor data can't be parsed.
+/// The last connected timestamp is the report creation (FridgeTag) or stop (QTag)
+/// timestamp, or if that is missing, the latest log timestamp, or failing that the
+/// latest breach end timestamp.
+/// With the flate2 feature, gzip-compressed files are decompressed before being read.
+/// Firmware which records temperatures as integer tenths of a degree says so with a
+/// "Temp Scale: 10" line in the Conf section, and they are converted to degrees (this can
+/// also be set with the temperature_scale parse option, which takes precedence). A Temp
+/// Scale which isn't positive is ignored with a warning.
+/// The logging interval is in minutes unless the file states the unit (e.g. "Logging
+/// Interval: 300 s"), or the log_interval_unit parse option says otherwise.
+pub fn read_sensor_from_file(file_path: &str) -> Result<Sensor, TemperatureSensorError> {
+    read_sensor_from_file_with_options(file_path, &ParseOptions::default())
+}
+
+/// As read_sensor_from_file, applying the specified parse options to the sensor data.
+pub fn read_sensor_from_file_with_options(
+    file_path: &str,
+    options: &ParseOptions,
+) -> Result<Sensor, TemperatureSensorError> {
+    match File::open(file_path) {
+        Ok(file) => {
+            let mut sensor = read_sensor_from_reader(io::BufReader::new(file), options)?;
+            set_file_origin(&mut sensor, file_path);
+            Ok(sensor)
+        }
+        Err(_) => {
+            log::error!("File not found: {}", file_path);
+            Err(TemperatureSensorError::FileNotFound(file_path.to_string()))
+        }
+    }
+}
+
+/// Records the sensor file the sensor was read from, and when.
+fn set_file_origin(sensor: &mut Sensor, file_path: &str) {
+    sensor.source_path = Some(file_path.to_string());
+    sensor.parsed_at = Some(Local::now().naive_local());
+}
+
+/// Returns whether the temperature scale can convert raw units to degrees.
+fn is_valid_temperature_scale(temperature_scale: f64) -> bool {
+    temperature_scale > 0.0 && temperature_scale.is_finite()
+}
+
+/// Converts the log temperatures and config limits from raw units to degrees. Only the
+/// limit used by each config is scaled, as the other end of the range is a default.
+fn scale_temperatures(sensor: &mut Sensor, temperature_scale: f64) {
+    let scaled = |temperature: Celsius| Celsius(temperature.0 / temperature_scale);
+
+    for log in sensor.logs.iter_mut().flatten() {
+        log.temperature = scaled(log.temperature);
+    }
+    for config in sensor.configs.iter_mut().flatten() {
+        *config.limit_mut() = scaled(config.limit());
+    }
+}
+
+/// Reads sensor data from the contents of a sensor txt file.
+fn read_sensor_from_reader<R: BufRead>(
+    reader: R,
+    options: &ParseOptions,
+) -> Result<Sensor, TemperatureSensorError> {
+    read_parsed_sensor_from_reader(reader, options).map(|parsed_sensor| parsed_sensor.sensor)
+}
+
+/// As read_sensor_from_reader, also returning a Truncated warning if the file was only
+/// read up to an unparseable last line (see ParseOptions::lenient).
+fn read_parsed_sensor_from_reader<R: BufRead>(
+    reader: R,
+    options: &ParseOptions,
+) -> Result<ParsedSensor, TemperatureSensorError> {
+    if let Some(temperature_scale) = options.temperature_scale {
+        if !is_valid_temperature_scale(temperature_scale) {
+            return Err(TemperatureSensorError::InvalidOption(format!(
+                "temperature_scale must be positive, not {}",
+                temperature_scale
+            )));
+        }
+    }
+    let (file_as_json, truncated_at_line) =
+        read_sensor_to_json(reader, options.decimal_comma, options.lenient)?;
+
+    let sensor_subtype = parse_subtype(&file_as_json);
+
+    let report_timestamp = match sensor_subtype {
+        // last timestamp in different places depending on sensor type
+        SensorSubType::FridgeTag => parse_timestamp(&file_as_json["Hist"]["TS Report Creation"]),
+        SensorSubType::QTag => parse_timestamp(&file_as_json["Res"]["TS Stop"]),
+    };
+
+    // the trip starts when a Fridge-tag is activated, or when a Q-tag run starts (after
+    // any start delay), and only Q-tags record when it stops
+    let (activation_timestamp, stop_timestamp) = match sensor_subtype {
+        SensorSubType::FridgeTag => (parse_timestamp(&file_as_json["Hist"]["TS Actv"]), None),
+        SensorSubType::QTag => (
+            parse_timestamp(&file_as_json["Res"]["TS Start"]),
+            report_timestamp,
+        ),
+    };
+
+    // only files recording the zone have UTC timestamps, others are already local
+    let file_timezone = parse_timezone(&file_as_json["Time Zone"]);
+    let model = model_from_device_name(&parse_string(&file_as_json["Device"]));
+    // known models all record alarms, so an empty list means there weren't any breaches,
+    // but an unknown device mightn't record them at all
+    let breaches = parse_breaches(&file_as_json, &sensor_subtype, file_timezone)
+        .or_else(|| model.map(|_| Vec::new()));
+    let battery_low = parse_battery_low(&file_as_json, &sensor_subtype);
+    let logs = parse_logs(&file_as_json, &sensor_subtype);
 
-        let report_timestamp: Option<NaiveDateTime>; // = None;
-        let sensor_subtype = parse_subtype(&file_as_json);
+    // if the report timestamp is missing, fall back to the latest log, then the latest breach end
+    let last_connected_timestamp = report_timestamp
+        .or_else(|| logs.iter().flatten().map(|log| log.timestamp).max())
+        .or_else(|| {
+            breaches
+                .iter()
+                .flatten()
+                .map(|breach| breach.end_timestamp)
+                .max()
+        });
 
-        match sensor_subtype {
-            // last timestamp in different places depending on sensor type
-            SensorSubType::FridgeTag => {
-                report_timestamp = parse_timestamp(&file_as_json["Hist"]["TS Report Creation"]);
+    let mut sensor = Sensor {
+        sensor_type: SensorType::Berlinger(model),
+        serial: parse_string(&file_as_json["Conf"]["Serial"]),
+        name: parse_string(&file_as_json["Device"]),
+        last_connected_timestamp,
+        activation_timestamp,
+        stop_timestamp,
+        log_interval: parse_interval(
+            &file_as_json["Conf"]["Logging Interval"],
+            options.log_interval_unit,
+        ),
+        breaches,
+        configs: parse_breach_configs(&file_as_json["Conf"]["Alarm"], &sensor_subtype),
+        logs,
+        excluded_logs: None,
+        battery_low,
+        // the zone recorded by the device takes precedence over the caller's default
+        timezone: file_timezone.or(options.timezone),
+        source: None,
+        source_path: None,
+        parsed_at: None,
+    };
+
+    if let Some(timezone) = file_timezone {
+        // the timestamps are UTC, so convert them to the device's local time
+        sensor.shift_timestamps(Duration::seconds(timezone.local_minus_utc().into()));
+    }
+    let file_temperature_scale =
+        parse_float(&file_as_json["Conf"]["Temp Scale"]).filter(|temperature_scale| {
+            let is_valid = is_valid_temperature_scale(*temperature_scale);
+            if !is_valid {
+                log::warn!("Ignoring invalid Temp Scale: {}", temperature_scale);
             }
-            SensorSubType::QTag => {
-                report_timestamp = parse_timestamp(&file_as_json["Res"]["TS Stop"]);
+            is_valid
+        });
+    if let Some(temperature_scale) = options.temperature_scale.or(file_temperature_scale) {
+        scale_temperatures(&mut sensor, temperature_scale);
+    }
+    if let Some(configs) = &options.override_configs {
+        sensor.configs = Some(configs.clone());
+    }
+    if options.truncate_to_seconds {
+        sensor.truncate_timestamps_to_seconds();
+    }
+    if options.exclude_inactive_logs {
+        sensor.exclude_inactive_logs();
+    }
+    if let Some(decimal_places) = options.round_temperatures_to {
+        sensor.round_temperatures(decimal_places);
+    }
+    if options.override_configs.is_some() {
+        // the recorded breaches were for the recorded configs, so recompute them
+        sensor.normalize();
+        let logs: Vec<TemperatureLog> = sensor
+            .logs
+            .iter()
+            .flatten()
+            .filter(|log| !(options.skip_self_test && sensor.is_self_test(log.timestamp)))
+            .cloned()
+            .collect();
+        let breaches = detect_consecutive_breaches_with_min_samples(
+            &logs,
+            sensor.configs.as_deref().unwrap_or_default(),
+            options.min_breach_samples,
+        );
+        sensor.breaches = Some(breaches);
+    }
+    if options.skip_self_test {
+        sensor.drop_self_test_breaches();
+    }
+    if options.reclassify_continuous_cumulative {
+        sensor.reclassify_continuous_cumulative_breaches();
+    }
+    if options.snap_breach_endpoints_to_logs {
+        sensor.snap_breach_endpoints_to_logs();
+    }
+    sensor.update_peak_temperatures();
+    sensor.normalize();
+
+    let warnings = truncated_at_line
+        .map(|at_line| ParseWarning::Truncated { at_line })
+        .into_iter()
+        .collect();
+    Ok(ParsedSensor { sensor, warnings })
+}
+
+/// As read_sensor_from_file, also returning warnings for anything odd about the sensor
+/// files which didn't stop the sensor being read (e.g. a missing PDF file).
+pub fn read_sensor_from_file_verbose(
+    file_path: &str,
+) -> Result<ParsedSensor, TemperatureSensorError> {
+    read_sensor_from_file_verbose_with_options(file_path, &ParseOptions::default())
+}
+
+/// As read_sensor_from_file_verbose, applying the specified parse options to the sensor data.
+pub fn read_sensor_from_file_verbose_with_options(
+    file_path: &str,
+    options: &ParseOptions,
+) -> Result<ParsedSensor, TemperatureSensorError> {
+    read_sensor_from_file_verbose_with_extractor(file_path, options, &TextOperatorExtractor)
+}
+
+/// As read_sensor_from_file_verbose_with_options, reading the PDF report which is checked
+/// against the txt file with the specified extractor (see parse_pdf_with).
+pub fn read_sensor_from_file_verbose_with_extractor<E: PdfExtractor + ?Sized>(
+    file_path: &str,
+    options: &ParseOptions,
+    extractor: &E,
+) -> Result<ParsedSensor, TemperatureSensorError> {
+    let ParsedSensor {
+        mut sensor,
+        mut warnings,
+    } = match File::open(file_path) {
+        Ok(file) => read_parsed_sensor_from_reader(io::BufReader::new(file), options)?,
+        Err(_) => {
+            log::error!("File not found: {}", file_path);
+            return Err(TemperatureSensorError::FileNotFound(file_path.to_string()));
+        }
+    };
+    set_file_origin(&mut sensor, file_path);
+
+    if model_from_device_name(&sensor.name).is_none() {
+        warnings.push(ParseWarning::UnknownDevice(sensor.name.clone()));
+    }
+    if sensor.log_interval.is_none() {
+        warnings.push(ParseWarning::MissingLogInterval);
+    }
+
+    let txt_file_path = Path::new(file_path);
+    let pdf_file_paths = txt_file_path
+        .parent()
+        .map(pdf_files_in_dir)
+        .unwrap_or_default();
+    match matching_pdf_file(txt_file_path, &pdf_file_paths) {
+        Some(pdf_file_path) => {
+            if let Some(warning) =
+                check_pdf_file(Path::new(&pdf_file_path), &sensor.serial, extractor)
+            {
+                warnings.push(warning);
             }
         }
+        None => warnings.push(ParseWarning::MissingPdf),
+    }
+
+    Ok(ParsedSensor { sensor, warnings })
+}
+
+fn qtag_alarm_type(breach_type: &BreachType) -> i64 {
+    match breach_type {
+        BreachType::ColdConsecutive => 1,
+        BreachType::HotConsecutive => 2,
+        BreachType::ColdCumulative => 3,
+        BreachType::HotCumulative => 4,
+    }
+}
+
+/// Writes the sensor out in the (Q-tag) Berlinger txt format, so that reading it back
+/// gives the same serial, name, last connected & activation timestamps, logging interval,
+/// configs, breaches and logs. Q-tags only record one stop timestamp, which is read back
+/// as both the stop and last connected timestamps, so the stop timestamp is written if
+/// there is one and the last connected timestamp otherwise. A low battery is written as
+/// reaching the warning level at that timestamp, as only whether it is low is kept.
+/// Timestamps and durations are written to the minute, as in the sensor files, apart from
+/// a logging interval which isn't whole minutes, which is written in seconds. Only the
+/// first 5 configs can be written, each with a single threshold (as on the sensor), and
+/// breaches are written under the first config of the same type, so breaches without a
+/// matching config are left out.
+pub fn write_sensor_to_txt(sensor: &Sensor) -> String {
+    let timestamp_format = "%Y-%m-%d %H:%M";
+    // files recording the timezone have UTC timestamps
+    let timezone = sensor.timezone;
+    let utc_sensor;
+    let sensor = match timezone {
+        Some(_) => {
+            utc_sensor = sensor.clone().to_utc();
+            &utc_sensor
+        }
+        None => sensor,
+    };
+    let configs: Vec<&TemperatureBreachConfig> = sensor.configs.iter().flatten().take(5).collect();
+    let mut txt = String::new();
 
-        let sensor = Sensor {
-            sensor_type: SensorType::Berlinger,
-            serial: parse_string(&file_as_json["Conf"]["Serial"]),
-            name: parse_string(&file_as_json["Device"]),
-            last_connected_timestamp: report_timestamp,
-            log_interval: parse_duration(&file_as_json["Conf"]["Logging Interval"]),
-            breaches: parse_breaches(&file_as_json, &sensor_subtype),
-            configs: parse_breach_configs(&file_as_json["Conf"]["Alarm"], &sensor_subtype),
-            logs: parse_logs(&file_as_json, &sensor_subtype),
-        };
+    txt.push_str(&format!("Device: {}\n", sensor.name));
+    if let Some(timezone) = timezone {
+        txt.push_str(&format!("Time Zone: {}\n", timezone));
+    }
+    txt.push_str("Conf:\n");
+    txt.push_str(&format!(" Serial: {}\n", sensor.serial));
+    if let Some(log_interval) = sensor.log_interval {
+        if log_interval.num_seconds() % 60 == 0 {
+            txt.push_str(&format!(
+                " Logging Interval: {}\n",
+                log_interval.num_minutes()
+            ));
+        } else {
+            txt.push_str(&format!(
+                " Logging Interval: {} s\n",
+                log_interval.num_seconds()
+            ));
+        }
+    }
+    txt.push_str(" Alarm:\n");
+    for (index, config) in configs.iter().enumerate() {
+        let threshold = config.limit();
+        txt.push_str(&format!("  {}:\n", index + 1));
+        txt.push_str(&format!(
+            "   Type: {}, T AL: {}, t AL: {}\n",
+            qtag_alarm_type(&config.breach_type),
+            threshold.0,
+            config.duration.num_minutes()
+        ));
+    }
 
-        Some(sensor)
-    } else {
-        log::error!("File not found: {}", file_path);
-        None
+    txt.push_str("Res:\n");
+    if let Some(activation_timestamp) = sensor.activation_timestamp {
+        txt.push_str(&format!(
+            " TS Start: {}\n",
+            activation_timestamp.format(timestamp_format)
+        ));
+    }
+    let stop_timestamp = sensor.stop_timestamp.or(sensor.last_connected_timestamp);
+    if let Some(stop_timestamp) = stop_timestamp {
+        txt.push_str(&format!(
+            " TS Stop: {}\n",
+            stop_timestamp.format(timestamp_format)
+        ));
     }
+    if let Some(battery_low) = sensor.battery_low {
+        let battery_warning_timestamp = stop_timestamp
+            .filter(|_| battery_low)
+            .map(|timestamp| timestamp.format(timestamp_format).to_string())
+            .unwrap_or_default();
+        txt.push_str(&format!(" TS Batt Temp: {}\n", battery_warning_timestamp));
+        txt.push_str(" TS Batt Crit:\n");
+    }
+    txt.push_str(" Alarm:\n");
+    for (index, config) in configs.iter().enumerate() {
+        if configs[..index]
+            .iter()
+            .any(|earlier_config| earlier_config.breach_type == config.breach_type)
+        {
+            continue; // breaches are all written under the first config of the type
+        }
+        txt.push_str(&format!("  {}:\n", index + 1));
+        for breach in sensor
+            .breaches
+            .iter()
+            .flatten()
+            .filter(|breach| breach.breach_type == config.breach_type)
+        {
+            let trigger = breach
+                .trigger_timestamp
+                .map(|trigger| format!(", TS A: {}", trigger.format(timestamp_format)))
+                .unwrap_or_default();
+            txt.push_str(&format!(
+                "   TS S: {}, t A: {}{}, TS E: {}\n",
+                breach.start_timestamp.format(timestamp_format),
+                breach.duration.num_minutes(),
+                trigger,
+                breach.end_timestamp.format(timestamp_format)
+            ));
+        }
+    }
+
+    if let Some(logs) = &sensor.logs {
+        txt.push_str("Data:\n");
+        txt.push_str("\tyyyy-MM-dd hh:mm\tT\tOut of limits\n");
+        for log in logs {
+            let out_of_limits = configs
+                .iter()
+                .any(|config| config.is_breaching(log.temperature));
+            txt.push_str(&format!(
+                "\t{}\t{}\t{}\n",
+                log.timestamp.format(timestamp_format),
+                log.temperature.0,
+                if out_of_limits { "x" } else { "" }
+            ));
+        }
+    }
+
+    txt
 }
 
+/// USB drives larger than this aren't expected to be sensors, so aren't scanned.
+const MAX_SENSOR_DRIVE_BYTES: u64 = 8 * 1024 * 1024 * 1024;
+
 #[cfg(target_os = "macos")]
-fn sensor_volume_paths() -> Vec<String> {
-    let mut volume_list: Vec<String> = Vec::new();
+fn mounted_drives() -> Vec<(String, Option<u64>)> {
+    let mut volume_list: Vec<(String, Option<u64>)> = Vec::new();
 
     if let Ok(entries) = fs::read_dir("/Volumes") {
-        // loop over folders in Volumes
-        for entry in entries {
-            if let Ok(entry) = entry {
-                if entry.path().is_dir() {
-                    if let Some(txt_file_path) = entry.path().to_str() {
-                        volume_list.push(txt_file_path.to_string())
-                    }
+        // loop over folders in Volumes (capacity isn't known)
+        for entry in entries.flatten() {
+            if entry.path().is_dir() {
+                if let Some(volume_path) = entry.path().to_str() {
+                    volume_list.push((volume_path.to_string(), None))
                 }
             }
         }
@@ -801,17 +1583,15 @@ fn sensor_volume_paths() -> Vec<String> {
 }
 
 #[cfg(target_os = "android")]
-fn sensor_volume_paths() -> Vec<String> {
-    let mut volume_list: Vec<String> = Vec::new();
+fn mounted_drives() -> Vec<(String, Option<u64>)> {
+    let mut volume_list: Vec<(String, Option<u64>)> = Vec::new();
 
     if let Ok(entries) = fs::read_dir("/mnt/media_rw") {
-        // loop over mounted media folders
-        for entry in entries {
-            if let Ok(entry) = entry {
-                if entry.path().is_dir() {
-                    if let Some(txt_file_path) = entry.path().to_str() {
-                        volume_list.push(txt_file_path.to_string())
-                    }
+        // loop over mounted media folders (capacity isn't known)
+        for entry in entries.flatten() {
+            if entry.path().is_dir() {
+                if let Some(volume_path) = entry.path().to_str() {
+                    volume_list.push((volume_path.to_string(), None))
                 }
             }
         }
@@ -820,24 +1600,30 @@ fn sensor_volume_paths() -> Vec<String> {
 }
 
 #[cfg(any(target_os = "windows", target_os = "linux"))]
-fn sensor_volume_paths() -> Vec<String> {
-    let mut volume_list: Vec<String> = Vec::new();
+/// Serializes drive enumeration, as the OS queries behind drive_list (WMI on Windows, lsblk
+/// on Linux) aren't guaranteed to be safe to run from several threads at once.
+static DRIVE_LIST_LOCK: Mutex<()> = Mutex::new(());
+
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+fn mounted_drives() -> Vec<(String, Option<u64>)> {
+    let mut volume_list: Vec<(String, Option<u64>)> = Vec::new();
 
-    match drive_list() {
+    // a panic in another scan doesn't leave the drive list in a bad state, so carry on
+    let drives = {
+        let _lock = DRIVE_LIST_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        drive_list()
+    };
+    match drives {
         Err(err) => log::error!("No drives found: {}", err),
         Ok(drives) => {
-            for drive_index in 0..drives.len() {
+            for drive in drives {
                 // loop over all detected drives
 
-                let mount_points = &drives[drive_index].mountpoints;
-                for partition_index in 0..mount_points.len() {
+                for mount_point in &drive.mountpoints {
                     // loop over partitions
-                    let mount_point = &mount_points[partition_index];
-
-                    if mount_point.totalBytes < Some(8 * 1024 * 1024 * 1024) {
-                        // possible USB drive if < 8 GB
-                        volume_list.push(mount_point.path.clone());
-                    }
+                    volume_list.push((mount_point.path.clone(), mount_point.totalBytes));
                 }
             }
         }
@@ -846,38 +1632,646 @@ fn sensor_volume_paths() -> Vec<String> {
     volume_list
 }
 
-fn sensor_file_list() -> Vec<String> {
-    let mut file_list: Vec<String> = Vec::new();
+fn is_within_size_limit(capacity_bytes: Option<u64>) -> bool {
+    // drives of unknown size are scanned
+    capacity_bytes.is_none_or(|capacity_bytes| capacity_bytes < MAX_SENSOR_DRIVE_BYTES)
+}
 
-    for volume_root in sensor_volume_paths() {
-        // loop over volumes
-
-        if let Ok(entries) = fs::read_dir(&volume_root) {
-            // loop over files in the volume root
-            for entry in entries {
-                if let Ok(entry) = entry {
-                    if let Some(extension) = entry.path().extension() {
-                        if extension == "txt" {
-                            // might be a sensor txt file
-                            if let Some(txt_file_path) = entry.path().to_str() {
-                                let pdf_file_path = txt_file_path.replace(".txt", ".pdf");
-
-                                if Path::new(&pdf_file_path).exists() {
-                                    // but only if it has a matching PDF
-                                    file_list.push(txt_file_path.to_string())
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
+fn sensor_volume_paths() -> Vec<String> {
+    mounted_drives()
+        .into_iter()
+        .filter(|(_, capacity_bytes)| is_within_size_limit(*capacity_bytes))
+        .map(|(path, _)| path)
+        .collect()
+}
 
-    file_list
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Define what was found on a mounted drive when scanning for sensors (see diagnose_usb).
+pub struct DriveDiagnostic {
+    pub path: String,
+    pub capacity_bytes: Option<u64>, // None if the OS doesn't report it
+    pub within_size_limit: bool,     // drives over 8GB are skipped when reading sensors
+    pub sensor_files: Vec<String>, // sensor txt files in the drive root, each followed by its PDF (if any)
 }
 
-fn sensor_serial_from_file_path(txt_file_path: &str) -> Option<String> {
+fn drive_diagnostic(path: &str, capacity_bytes: Option<u64>) -> DriveDiagnostic {
+    let sensor_files = sensor_files_in_dir(Path::new(path))
+        .into_iter()
+        .flat_map(|sensor_files| {
+            std::iter::once(sensor_files.txt_file_path).chain(sensor_files.pdf_file_path)
+        })
+        .collect();
+
+    DriveDiagnostic {
+        path: path.to_string(),
+        capacity_bytes,
+        within_size_limit: is_within_size_limit(capacity_bytes),
+        sensor_files,
+    }
+}
+
+/// Returns each mounted drive with its capacity, whether it is within the size limit for
+/// sensor drives, and the sensor files found in its root folder (listed even for drives
+/// which are too large, to show why a sensor wasn't read). Nothing is parsed, so this is
+/// safe to call for support diagnostics when a sensor isn't detected.
+pub fn diagnose_usb() -> Vec<DriveDiagnostic> {
+    mounted_drives()
+        .into_iter()
+        .map(|(path, capacity_bytes)| drive_diagnostic(&path, capacity_bytes))
+        .collect()
+}
+
+/// A sensor txt file together with the PDF file matched to it (if any).
+#[derive(Debug, Clone)]
+struct SensorFiles {
+    txt_file_path: String,
+    pdf_file_path: Option<String>,
+}
+
+fn is_sensor_txt_file(txt_file_path: &Path) -> bool {
+    // Berlinger txt files always start with the device name
+    if let Ok(mut lines) = read_lines(txt_file_path) {
+        if let Some(Ok(first_line)) = lines.next() {
+            return first_line
+                .trim_start_matches('\u{feff}')
+                .starts_with("Device:");
+        }
+    }
+    false
+}
+
+fn file_modified(file_path: &Path) -> Option<SystemTime> {
+    fs::metadata(file_path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+fn is_pdf_for_serial(pdf_stem: &str, serial: &str) -> bool {
+    pdf_stem == serial || pdf_stem.starts_with(&format!("{}_", serial))
+}
+
+fn matching_pdf_file(txt_file_path: &Path, pdf_file_paths: &[PathBuf]) -> Option<String> {
+    let txt_stem = txt_file_path.file_stem()?.to_str()?;
+    let serial = txt_stem.split('_').next()?;
+
+    // PDF with the same name as the txt file is always the best match
+    if let Some(pdf_file_path) = pdf_file_paths.iter().find(|pdf_file_path| {
+        pdf_file_path.file_stem().and_then(|stem| stem.to_str()) == Some(txt_stem)
+    }) {
+        return pdf_file_path.to_str().map(|path| path.to_string());
+    }
+
+    // otherwise fall back to any PDF for the same serial
+    let mut candidates: Vec<&PathBuf> = pdf_file_paths
+        .iter()
+        .filter(|pdf_file_path| {
+            if let Some(pdf_stem) = pdf_file_path.file_stem().and_then(|stem| stem.to_str()) {
+                is_pdf_for_serial(pdf_stem, serial)
+            } else {
+                false
+            }
+        })
+        .collect();
+
+    if candidates.len() > 1 {
+        log::warn!(
+            "{} PDF files found for serial {}: using the most recently modified one",
+            candidates.len(),
+            serial
+        );
+        candidates.sort_by_key(|pdf_file_path| file_modified(pdf_file_path));
+    }
+
+    candidates
+        .last()
+        .and_then(|pdf_file_path| pdf_file_path.to_str())
+        .map(|path| path.to_string())
+}
+
+/// Returns the strings shown by the text operators of a PDF (i.e. "(...) Tj"), which
+/// Berlinger reports write uncompressed.
+fn pdf_text_strings(pdf: &str) -> Vec<&str> {
+    let mut strings: Vec<&str> = Vec::new();
+    let mut rest = pdf;
+
+    while let Some(open) = rest.find('(') {
+        let after_open = &rest[open + 1..];
+        let Some(close) = after_open.find(')') else {
+            break;
+        };
+        rest = &after_open[close + 1..];
+        if rest.trim_start().starts_with("Tj") {
+            strings.push(&after_open[..close]);
+        }
+    }
+
+    strings
+}
+
+/// Returns the sensor serial shown in a Berlinger PDF report (None if there isn't one),
+/// reading its text with the extractor (returning any error from it).
+fn pdf_serial<E: PdfExtractor + ?Sized>(
+    pdf_contents: &[u8],
+    extractor: &E,
+) -> Result<Option<String>, TemperatureSensorError> {
+    let extracted_strings = extractor.extract_strings(pdf_contents)?;
+    let strings: Vec<&str> = extracted_strings.iter().map(String::as_str).collect();
+    Ok(serial_from_pdf_strings(&strings))
+}
+
+/// Returns the serial from the identification number, which either follows the label
+/// (Fridge-tags) or is part of it (Q-tags, which also append the run number and report
+/// timestamp).
+fn serial_from_pdf_strings(strings: &[&str]) -> Option<String> {
+    let label_index = strings
+        .iter()
+        .position(|string| string.to_lowercase().starts_with("identification number:"))?;
+
+    let identification_number = match strings[label_index].split_once(':') {
+        Some((_, number)) if !number.trim().is_empty() => number.trim(),
+        _ => strings.get(label_index + 1)?.trim(),
+    };
+    identification_number
+        .split('_')
+        .next()
+        .map(|serial| serial.to_string())
+}
+
+/// Returns whether the PDF is encrypted (password protected), i.e. its trailer refers to
+/// an encryption dictionary. The text of an encrypted PDF can't be read.
+fn is_encrypted_pdf(pdf_contents: &[u8]) -> bool {
+    pdf_contents
+        .windows(b"/Encrypt".len())
+        .any(|window| window == b"/Encrypt")
+}
+
+/// Returns a warning if the PDF report paired with a txt file can't be checked against it
+/// because it's encrypted (EncryptedPdf), or if it shows a different serial to the txt file
+/// (SerialMismatch, e.g. when files have been copied between drives by hand). Either way the
+/// sensor is still read from the txt file.
+fn check_pdf_file<E: PdfExtractor + ?Sized>(
+    pdf_file_path: &Path,
+    txt_serial: &str,
+    extractor: &E,
+) -> Option<ParseWarning> {
+    let pdf_contents = fs::read(pdf_file_path).ok()?;
+    check_pdf(
+        &pdf_file_path.display().to_string(),
+        &pdf_contents,
+        txt_serial,
+        extractor,
+    )
+}
+
+/// As check_pdf_file, for the contents of the named PDF file (e.g. a zip archive entry).
+fn check_pdf<E: PdfExtractor + ?Sized>(
+    pdf_name: &str,
+    pdf_contents: &[u8],
+    txt_serial: &str,
+    extractor: &E,
+) -> Option<ParseWarning> {
+    let pdf_serial = match pdf_serial(pdf_contents, extractor) {
+        Ok(pdf_serial) => pdf_serial?,
+        Err(TemperatureSensorError::EncryptedPdf) => {
+            log::warn!("PDF {} is encrypted: only reading the txt file", pdf_name);
+            return Some(ParseWarning::EncryptedPdf);
+        }
+        Err(err) => {
+            log::warn!("PDF {} not checked: {}", pdf_name, err);
+            return None;
+        }
+    };
+    if pdf_serial == txt_serial {
+        return None;
+    }
+
+    log::warn!(
+        "PDF {} is for serial {} rather than {}: using the txt serial",
+        pdf_name,
+        pdf_serial,
+        txt_serial
+    );
+    Some(ParseWarning::SerialMismatch {
+        txt: txt_serial.to_string(),
+        pdf: pdf_serial,
+    })
+}
+
+/// Sensor details which can be recovered from a Berlinger PDF report alone (e.g. when the
+/// txt file has been lost). The PDF only has a chart of the temperatures, so the logs (and
+/// the breach peak temperatures worked out from them) and the device name only come from
+/// the txt file. The rest match the txt file, apart from Q-tag breach start times:
+/// - serial: the identification number (without the Q-tag run number & report timestamp)
+/// - model: from the report title
+/// - activation_timestamp: the activation date (Fridge-tags) or run start (Q-tags)
+/// - last_connected_timestamp: the report creation (Fridge-tags) or run stop (Q-tags)
+/// - stop_timestamp: the run stop (Q-tags only)
+/// - log_interval: the logging interval (not shown for Fridge-tags without logging)
+/// - configs: the alarm limits (Fridge-tag limits are duplicated as consecutive configs)
+/// - breaches: the daily alarms (Fridge-tags) or alarm events (Q-tags). Q-tag reports only
+///   show when each alarm was triggered, so the breach start is taken as the config
+///   duration before that, which is only exact for consecutive breaches.
+#[derive(Debug, Clone)]
+pub struct PdfSensorData {
+    pub serial: String,
+    pub model: Option<BerlingerModel>,
+    pub activation_timestamp: Option<NaiveDateTime>,
+    pub last_connected_timestamp: Option<NaiveDateTime>,
+    pub stop_timestamp: Option<NaiveDateTime>,
+    pub log_interval: Option<Duration>,
+    pub configs: Option<Vec<TemperatureBreachConfig>>,
+    pub breaches: Option<Vec<TemperatureBreach>>,
+}
+
+/// Extracts the strings of text shown in a PDF report, in the order they're drawn, for
+/// parse_pdf_with and the readers which check PDF reports against txt files (e.g.
+/// read_sensors_from_dir_with_extractor). Implement this to read reports the default
+/// TextOperatorExtractor can't (e.g. with another PDF library).
+pub trait PdfExtractor {
+    fn extract_strings(&self, pdf: &[u8]) -> Result<Vec<String>, TemperatureSensorError>;
+}
+
+/// The default PdfExtractor (used by parse_pdf), which reads the strings of the PDF's text
+/// operators directly. This only works for uncompressed PDFs, as Berlinger writes them.
+/// Returns an EncryptedPdf error if the PDF is password protected.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextOperatorExtractor;
+
+impl PdfExtractor for TextOperatorExtractor {
+    fn extract_strings(&self, pdf: &[u8]) -> Result<Vec<String>, TemperatureSensorError> {
+        if is_encrypted_pdf(pdf) {
+            return Err(TemperatureSensorError::EncryptedPdf);
+        }
+        let pdf = String::from_utf8_lossy(pdf);
+        Ok(pdf_text_strings(&pdf)
+            .into_iter()
+            .map(|string| string.to_string())
+            .collect())
+    }
+}
+
+/// Returns the sensor details shown in a Berlinger PDF report (see PdfSensorData).
+/// Returns an EncryptedPdf error if the PDF is password protected, or an InvalidPdf error
+/// if there is no identification number in the PDF.
+pub fn parse_pdf(pdf: &[u8]) -> Result<PdfSensorData, TemperatureSensorError> {
+    parse_pdf_with(pdf, &TextOperatorExtractor)
+}
+
+/// As parse_pdf, extracting the text of the PDF with the specified extractor (returning
+/// any error from it).
+pub fn parse_pdf_with<E: PdfExtractor + ?Sized>(
+    pdf: &[u8],
+    extractor: &E,
+) -> Result<PdfSensorData, TemperatureSensorError> {
+    let extracted_strings = extractor.extract_strings(pdf)?;
+    let strings: Vec<&str> = extracted_strings.iter().map(String::as_str).collect();
+    let serial = serial_from_pdf_strings(&strings).ok_or_else(|| {
+        TemperatureSensorError::InvalidPdf("no identification number found".to_string())
+    })?;
+
+    // the report title names the model, with a (non-ASCII) registered trademark sign
+    let model = strings.iter().find_map(|string| {
+        let ascii_string: String = string.chars().filter(char::is_ascii).collect();
+        model_from_device_name(&ascii_string)
+    });
+
+    let mut pdf_sensor_data = match model {
+        Some(BerlingerModel::QtagCLm) => parse_qtag_pdf(&strings),
+        _ => parse_fridgetag_pdf(&strings),
+    };
+    pdf_sensor_data.serial = serial;
+    pdf_sensor_data.model = model;
+    if let Some(breaches) = &mut pdf_sensor_data.breaches {
+        breaches.sort_by(|a, b| {
+            (a.start_timestamp, &a.breach_type, a.end_timestamp).cmp(&(
+                b.start_timestamp,
+                &b.breach_type,
+                b.end_timestamp,
+            ))
+        });
+    }
+
+    Ok(pdf_sensor_data)
+}
+
+/// Returns the string following the (case insensitive) label in a PDF report.
+fn pdf_value_after<'a>(strings: &[&'a str], label: &str) -> Option<&'a str> {
+    let label_index = strings
+        .iter()
+        .position(|string| string.trim().eq_ignore_ascii_case(label))?;
+    strings.get(label_index + 1).copied()
+}
+
+/// Parses a PDF report temperature e.g. "+8.0°C" (the degree sign isn't valid UTF-8).
+fn pdf_temperature(text: &str) -> Option<Celsius> {
+    let number: String = text
+        .trim()
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || matches!(c, '+' | '-' | '.'))
+        .collect();
+    number.parse().ok().map(Celsius)
+}
+
+/// Parses a PDF report duration e.g. "30min", "10 min", "1h " or "2d 22h 26min".
+fn pdf_duration(text: &str) -> Option<Duration> {
+    let text: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    if text.is_empty() {
+        return None;
+    }
+
+    let mut duration = Duration::zero();
+    let mut rest = text.as_str();
+    while !rest.is_empty() {
+        let unit_start = rest.find(|c: char| !c.is_ascii_digit())?;
+        let value: i64 = rest[..unit_start].parse().ok()?;
+        rest = &rest[unit_start..];
+        let unit_end = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        duration += match &rest[..unit_end] {
+            "d" => Duration::days(value),
+            "h" => Duration::hours(value),
+            "min" => Duration::minutes(value),
+            _ => return None,
+        };
+        rest = &rest[unit_end..];
+    }
+
+    Some(duration)
+}
+
+/// Parses a PDF report alarm limit e.g. "Below -0.5°C for 1h " into whether it's a cold
+/// limit, and its temperature & duration.
+fn pdf_alarm_limit(text: &str) -> Option<(bool, Celsius, Duration)> {
+    let (limit, duration) = text.split_once(" for ")?;
+    let (direction, temperature) = limit.trim().split_once(' ')?;
+    let is_cold = match direction.to_lowercase().as_str() {
+        "below" => true,
+        "above" => false,
+        _ => return None,
+    };
+
+    Some((
+        is_cold,
+        pdf_temperature(temperature)?,
+        pdf_duration(duration)?,
+    ))
+}
+
+/// Returns the breach config for an alarm limit, with the same (unused) other end of the
+/// range as the txt file configs.
+fn limit_config(
+    breach_type: BreachType,
+    temperature: Celsius,
+    duration: Duration,
+) -> TemperatureBreachConfig {
+    let mut config = TemperatureBreachConfig {
+        breach_type,
+        maximum_temperature: Celsius(100.0),
+        minimum_temperature: Celsius(-273.0),
+        duration,
+    };
+    *config.limit_mut() = temperature;
+    config
+}
+
+fn parse_fridgetag_pdf(strings: &[&str]) -> PdfSensorData {
+    let pdf_timestamp = |label: &str| {
+        pdf_value_after(strings, label)
+            .and_then(|value| NaiveDateTime::parse_from_str(value.trim(), "%d.%m.%Y %H:%Mh").ok())
+    };
+    let report_timestamp = pdf_timestamp("Date and time of report creation:");
+
+    // duplicate cumulative breach configs as consecutive ones, as for the txt file
+    let mut configs: Vec<TemperatureBreachConfig> = Vec::new();
+    for label in ["Lower alarm limit:", "Upper alarm limit:"] {
+        if let Some((is_cold, temperature, duration)) =
+            pdf_value_after(strings, label).and_then(pdf_alarm_limit)
+        {
+            let breach_types = if is_cold {
+                [BreachType::ColdConsecutive, BreachType::ColdCumulative]
+            } else {
+                [BreachType::HotConsecutive, BreachType::HotCumulative]
+            };
+            for breach_type in breach_types {
+                configs.push(limit_config(breach_type, temperature, duration));
+            }
+        }
+    }
+
+    // each day of the history table starts with its row number and date ("Today" for the
+    // report date), so the rows can be found in order even across page breaks
+    let row_date = |text: &str| match text.trim() {
+        "Today" => report_timestamp.map(|timestamp| timestamp.date()),
+        date => NaiveDate::parse_from_str(date, "%d.%m.%Y").ok(),
+    };
+    let mut rows: Vec<(usize, NaiveDate)> = Vec::new();
+    for index in 0..strings.len().saturating_sub(1) {
+        if strings[index].trim() == (rows.len() + 1).to_string() {
+            if let Some(date) = row_date(strings[index + 1]) {
+                rows.push((index, date));
+            }
+        }
+    }
+
+    let mut breaches: Vec<TemperatureBreach> = Vec::new();
+    for (row_index, (start, breach_date)) in rows.iter().enumerate() {
+        let end = rows
+            .get(row_index + 1)
+            .map_or(strings.len(), |(next_start, _)| *next_start);
+        // after the date: events, average temperature, then the lower & upper alarm
+        // columns (status, min/max temperature, cumulative time, trigger time if any)
+        let mut column = start + 4;
+        for breach_type in [BreachType::ColdCumulative, BreachType::HotCumulative] {
+            let breach_duration = strings[..end]
+                .get(column + 2)
+                .and_then(|text| pdf_duration(text));
+            let trigger_time = strings[..end]
+                .get(column + 3)
+                .and_then(|text| NaiveTime::parse_from_str(text.trim(), "%H:%Mh").ok());
+            column += if trigger_time.is_some() { 4 } else { 3 };
+
+            let config_duration = configs
+                .iter()
+                .find(|config| config.breach_type == breach_type)
+                .map(|config| config.duration);
+            if let (Some(breach_duration), Some(trigger_time), Some(config_duration)) =
+                (breach_duration, trigger_time, config_duration)
+            {
+                if breach_duration > Duration::zero() && config_duration > Duration::zero() {
+                    breaches.push(fridgetag_breach(
+                        breach_type,
+                        *breach_date,
+                        NaiveDateTime::new(*breach_date, trigger_time),
+                        config_duration,
+                        breach_duration,
+                    ));
+                }
+            }
+        }
+    }
+
+    PdfSensorData {
+        serial: String::new(),
+        model: None,
+        activation_timestamp: pdf_timestamp("Activation date:"),
+        last_connected_timestamp: report_timestamp,
+        stop_timestamp: None,
+        log_interval: pdf_value_after(strings, "Logging interval:").and_then(pdf_duration),
+        configs: (!configs.is_empty()).then_some(configs),
+        breaches: Some(breaches), // both models record alarms
+    }
+}
+
+fn parse_qtag_pdf(strings: &[&str]) -> PdfSensorData {
+    let pdf_timestamp = |index: usize| {
+        let date = NaiveDate::parse_from_str(strings.get(index)?.trim(), "%d.%m.%Y").ok()?;
+        let time = NaiveTime::parse_from_str(strings.get(index + 1)?.trim(), "%H:%M").ok()?;
+        Some(NaiveDateTime::new(date, time))
+    };
+    let labelled_timestamp = |label: &str| {
+        let label_index = strings
+            .iter()
+            .position(|string| string.trim().eq_ignore_ascii_case(label))?;
+        pdf_timestamp(label_index + 1)
+    };
+
+    // each alarm in the table is its number & kind, its limit, then its status followed
+    // by the trigger date, time & duration of each breach (e.g. "ALARM", "08.11.2022",
+    // "22:20", "3h 39min")
+    let mut configs: Vec<TemperatureBreachConfig> = Vec::new();
+    let mut breaches: Vec<TemperatureBreach> = Vec::new();
+    for index in 0..strings.len().saturating_sub(1) {
+        let Some((_, kind)) = strings[index].split_once(": ") else {
+            continue;
+        };
+        let Some((is_cold, temperature, duration)) = pdf_alarm_limit(strings[index + 1]) else {
+            continue;
+        };
+        let breach_type = match (kind.trim(), is_cold) {
+            ("Single Event", true) => BreachType::ColdConsecutive,
+            ("Single Event", false) => BreachType::HotConsecutive,
+            ("Accumulated", true) => BreachType::ColdCumulative,
+            ("Accumulated", false) => BreachType::HotCumulative,
+            _ => continue,
+        };
+        configs.push(limit_config(breach_type.clone(), temperature, duration));
+
+        let mut breach_index = index + 2;
+        while strings.get(breach_index).map(|status| status.trim()) == Some("ALARM") {
+            if let (Some(trigger_timestamp), Some(breach_duration)) = (
+                pdf_timestamp(breach_index + 1),
+                strings
+                    .get(breach_index + 3)
+                    .and_then(|text| pdf_duration(text)),
+            ) {
+                let start_timestamp = trigger_timestamp - duration;
+                let breach = TemperatureBreach {
+                    breach_type: breach_type.clone(),
+                    start_timestamp,
+                    end_timestamp: start_timestamp + breach_duration,
+                    duration: breach_duration,
+                    acknowledged: false,
+                    peak_temperature: None,
+                    trigger_timestamp: Some(trigger_timestamp),
+                    annotation: None,
+                };
+                match breach.breach_type {
+                    // cumulative breaches are counted per day
+                    BreachType::HotCumulative | BreachType::ColdCumulative => {
+                        breaches.extend(split_breach_by_day(&breach))
+                    }
+                    _ => breaches.push(breach),
+                }
+            }
+            breach_index += 4;
+        }
+    }
+
+    let stop_timestamp = labelled_timestamp("Stop date and time");
+    PdfSensorData {
+        serial: String::new(),
+        model: None,
+        activation_timestamp: labelled_timestamp("Start date and time"),
+        last_connected_timestamp: stop_timestamp,
+        stop_timestamp,
+        log_interval: pdf_value_after(strings, "Logging Interval").and_then(pdf_duration),
+        configs: (!configs.is_empty()).then_some(configs),
+        breaches: Some(breaches), // both models record alarms
+    }
+}
+
+fn pdf_files_in_dir(dir: &Path) -> Vec<PathBuf> {
+    let mut pdf_file_paths: Vec<PathBuf> = Vec::new();
+
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let entry_path = entry.path();
+            if entry_path
+                .extension()
+                .is_some_and(|extension| extension == "pdf")
+            {
+                pdf_file_paths.push(entry_path);
+            }
+        }
+    }
+
+    pdf_file_paths
+}
+
+fn sensor_files_in_dir(dir: &Path) -> Vec<SensorFiles> {
+    let mut txt_file_paths: Vec<PathBuf> = Vec::new();
+    let mut pdf_file_paths: Vec<PathBuf> = Vec::new();
+    let mut file_list: Vec<SensorFiles> = Vec::new();
+
+    if let Ok(entries) = fs::read_dir(dir) {
+        // loop over files in the folder
+        for entry in entries.flatten() {
+            let entry_path = entry.path();
+            if let Some(extension) = entry_path.extension() {
+                if extension == "txt" {
+                    txt_file_paths.push(entry_path);
+                } else if extension == "pdf" {
+                    pdf_file_paths.push(entry_path);
+                }
+            }
+        }
+    }
+    txt_file_paths.sort();
+
+    for txt_file_path in txt_file_paths {
+        if !is_sensor_txt_file(&txt_file_path) {
+            // not a sensor txt file
+            continue;
+        }
+        if let Some(txt_file_name) = txt_file_path.to_str() {
+            let pdf_file_path = matching_pdf_file(&txt_file_path, &pdf_file_paths);
+            if pdf_file_path.is_none() {
+                // still read the txt file, as it has everything except the PDF-only details
+                log::warn!("No matching PDF file found for {}", txt_file_name);
+            }
+            file_list.push(SensorFiles {
+                txt_file_path: txt_file_name.to_string(),
+                pdf_file_path,
+            });
+        }
+    }
+
+    file_list
+}
+
+fn sensor_file_list() -> Vec<SensorFiles> {
+    let mut file_list: Vec<SensorFiles> = Vec::new();
+
+    for volume_root in sensor_volume_paths() {
+        // loop over volumes (sensor files are only expected in the volume root)
+        file_list.extend(sensor_files_in_dir(Path::new(&volume_root)));
+    }
+
+    file_list
+}
+
+fn sensor_serial_from_file_path(txt_file_path: &str) -> Option<String> {
     let mut valid_serial = false;
     let mut serial = "";
     let file_path = Path::new(&txt_file_path);
@@ -901,18 +2295,20 @@ fn sensor_serial_from_file_path(txt_file_path: &str) -> Option<String> {
 
 /// Returns all the serials found from currently mounted USB drives up to 8GB capacity
 /// (-> any USB drive containing sensor files if you don't have a physical sensor).
-/// For Berlinger sensors, it expects to find a serial_xxxxx.txt file in the root folder
-/// together with a matching PDF file (USB drives can have multiple pairs of files).
+/// For Berlinger sensors, it expects to find a serial_xxxxx.txt file in the root folder,
+/// usually together with a matching PDF file (USB drives can have multiple pairs of files).
+/// A txt file without a PDF is still read, and if there are several PDF files for the
+/// same serial then the most recently modified one is used.
 pub fn read_sensor_serials() -> Option<Vec<String>> {
     let mut serial_list: Vec<String> = Vec::new();
 
-    for txt_file_path in sensor_file_list() {
-        if let Some(serial) = sensor_serial_from_file_path(&txt_file_path) {
+    for sensor_files in sensor_file_list() {
+        if let Some(serial) = sensor_serial_from_file_path(&sensor_files.txt_file_path) {
             serial_list.push(serial)
         }
     }
 
-    if serial_list.len() > 0 {
+    if !serial_list.is_empty() {
         Some(serial_list)
     } else {
         None
@@ -921,20 +2317,2238 @@ pub fn read_sensor_serials() -> Option<Vec<String>> {
 
 /// Returns all sensors found from currently mounted USB drives up to 8GB capacity
 /// (-> any USB drive containing sensor files if you don't have a physical sensor).
-/// For Berlinger sensors, it expects to find a serial_xxxxx.txt file in the root folder
-/// together with a matching PDF file (USB drives can have multiple pairs of files).
+/// For Berlinger sensors, it expects to find a serial_xxxxx.txt file in the root folder,
+/// usually together with a matching PDF file (USB drives can have multiple pairs of files).
+/// A txt file without a PDF is still read, and if there are several PDF files for the
+/// same serial then the most recently modified one is used. Files which can't be read are
+/// logged and skipped (see read_sensors_from_usb_detailed to report them).
+/// Each sensor's source is the drive it was read from. The same serial can be returned
+/// more than once if several drives have files for it (e.g. a backup drive), and the
+/// source tells them apart.
 pub fn read_sensors_from_usb() -> Option<Vec<Sensor>> {
-    let mut sensors: Vec<Sensor> = Vec::new();
+    read_sensors_from_usb_with_extractor(&TextOperatorExtractor)
+}
+
+/// As read_sensors_from_usb, reading the PDF reports which are checked against the txt
+/// files with the specified extractor (see parse_pdf_with).
+pub fn read_sensors_from_usb_with_extractor<E: PdfExtractor + ?Sized>(
+    extractor: &E,
+) -> Option<Vec<Sensor>> {
+    let volume_paths: Vec<PathBuf> = sensor_volume_paths()
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+
+    read_sensors_from_paths_with_extractor(&volume_paths, extractor).ok()
+}
+
+/// As read_sensors_from_usb, but returning the result of reading each sensor file found
+/// (in the same order), so that the files which couldn't be read can be reported along
+/// with why. Each error comes with the path of the txt file.
+pub fn read_sensors_from_usb_detailed() -> Vec<Result<Sensor, (PathBuf, TemperatureSensorError)>> {
+    let volume_paths: Vec<PathBuf> = sensor_volume_paths()
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+
+    read_sensors_from_paths_detailed(&volume_paths)
+}
+
+/// As read_sensors_from_usb, but reading from the specified drive roots (e.g. the mount
+/// paths handed over by a host app on platforms where the drives can't be listed here).
+/// Paths which aren't folders are logged and skipped. Returns a NoSensorsFound error if
+/// there are no sensor files in any of them.
+pub fn read_sensors_from_paths(paths: &[PathBuf]) -> Result<Vec<Sensor>, TemperatureSensorError> {
+    read_sensors_from_paths_with_extractor(paths, &TextOperatorExtractor)
+}
+
+/// As read_sensors_from_paths, reading the PDF reports which are checked against the txt
+/// files with the specified extractor (see parse_pdf_with).
+pub fn read_sensors_from_paths_with_extractor<E: PdfExtractor + ?Sized>(
+    paths: &[PathBuf],
+    extractor: &E,
+) -> Result<Vec<Sensor>, TemperatureSensorError> {
+    let sensors = successful_sensors(read_sensor_results_from_paths(paths, extractor));
+
+    if !sensors.is_empty() {
+        Ok(sensors)
+    } else {
+        Err(TemperatureSensorError::NoSensorsFound)
+    }
+}
+
+/// As read_sensors_from_usb_detailed, but reading from the specified drive roots (see
+/// read_sensors_from_paths). Paths which aren't folders are logged and skipped.
+pub fn read_sensors_from_paths_detailed(
+    paths: &[PathBuf],
+) -> Vec<Result<Sensor, (PathBuf, TemperatureSensorError)>> {
+    read_sensor_results_from_paths(paths, &TextOperatorExtractor)
+}
+
+/// As read_sensors_from_paths_detailed, reading the PDF reports with the specified extractor.
+fn read_sensor_results_from_paths<E: PdfExtractor + ?Sized>(
+    paths: &[PathBuf],
+    extractor: &E,
+) -> Vec<Result<Sensor, (PathBuf, TemperatureSensorError)>> {
+    paths
+        .iter()
+        .filter(|path| {
+            let is_dir = path.is_dir();
+            if !is_dir {
+                log::warn!("Not a folder, so skipped: {}", path.display());
+            }
+            is_dir
+        })
+        .flat_map(|volume_root| read_sensor_results_in_dir(volume_root, extractor))
+        .collect()
+}
+
+/// Returns the sensors read from the sensor files in the folder, and in all its
+/// subfolders if recursive (e.g. when files are kept in per-date folders), with the
+/// folder each was found in as its source. A txt file is only paired with a PDF in the
+/// same folder. Symlinked folders are followed, but each folder is only read once so
+/// symlink loops are skipped. Files which can't be read are logged and skipped.
+pub fn read_sensors_from_dir(dir: &str, recursive: bool) -> Vec<Sensor> {
+    read_sensors_from_dir_with_extractor(dir, recursive, &TextOperatorExtractor)
+}
+
+/// As read_sensors_from_dir, reading the PDF reports which are checked against the txt
+/// files with the specified extractor (see parse_pdf_with).
+pub fn read_sensors_from_dir_with_extractor<E: PdfExtractor + ?Sized>(
+    dir: &str,
+    recursive: bool,
+    extractor: &E,
+) -> Vec<Sensor> {
+    let dirs = if recursive {
+        dirs_below(Path::new(dir))
+    } else {
+        vec![PathBuf::from(dir)]
+    };
+
+    dirs.iter()
+        .flat_map(|dir| read_sensors_in_dir(dir, extractor))
+        .collect()
+}
+
+/// Returns the folder followed by all its subfolders (depth first, in name order).
+fn dirs_below(dir: &Path) -> Vec<PathBuf> {
+    let mut visited: HashSet<PathBuf> = HashSet::new();
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        // symlinks resolve to the folder they point to, so a loop finds a visited folder
+        let Ok(canonical_dir) = fs::canonicalize(&dir) else {
+            continue;
+        };
+        if !visited.insert(canonical_dir) {
+            log::warn!("Skipping {}: folder already read", dir.display());
+            continue;
+        }
+
+        if let Ok(entries) = fs::read_dir(&dir) {
+            let mut subdirs: Vec<PathBuf> = entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|entry_path| entry_path.is_dir())
+                .collect();
+            subdirs.sort();
+            pending.extend(subdirs.into_iter().rev());
+        }
+        dirs.push(dir);
+    }
+
+    dirs
+}
+
+/// Returns the sensors read from the sensor files in the folder (not its subfolders),
+/// with the folder as their source.
+fn read_sensors_in_dir<E: PdfExtractor + ?Sized>(dir: &Path, extractor: &E) -> Vec<Sensor> {
+    successful_sensors(read_sensor_results_in_dir(dir, extractor))
+}
+
+/// Returns the sensors which were read, logging the sensor files which couldn't be.
+fn successful_sensors(
+    results: Vec<Result<Sensor, (PathBuf, TemperatureSensorError)>>,
+) -> Vec<Sensor> {
+    results
+        .into_iter()
+        .filter_map(|result| match result {
+            Ok(sensor) => Some(sensor),
+            Err((txt_file_path, err)) => {
+                log::error!("{}: {}", txt_file_path.display(), err);
+                None
+            }
+        })
+        .collect()
+}
+
+/// As read_sensors_in_dir, returning the result of reading each sensor file in the
+/// folder, with the path of the txt file for each error.
+fn read_sensor_results_in_dir<E: PdfExtractor + ?Sized>(
+    dir: &Path,
+    extractor: &E,
+) -> Vec<Result<Sensor, (PathBuf, TemperatureSensorError)>> {
+    let mut results = Vec::new();
+
+    for sensor_files in sensor_files_in_dir(dir) {
+        if let Some(pdf_file_path) = &sensor_files.pdf_file_path {
+            log::info!(
+                "Reading {} (PDF: {})",
+                sensor_files.txt_file_path,
+                pdf_file_path
+            );
+        }
+        match read_sensor_from_file(&sensor_files.txt_file_path) {
+            Ok(mut sensor) => {
+                if let Some(pdf_file_path) = &sensor_files.pdf_file_path {
+                    // logs any mismatch or encryption (the txt data is kept either way)
+                    check_pdf_file(Path::new(pdf_file_path), &sensor.serial, extractor);
+                }
+                sensor.source = dir.to_str().map(|path| path.to_string());
+                results.push(Ok(sensor))
+            }
+            Err(err) => results.push(Err((PathBuf::from(&sensor_files.txt_file_path), err))),
+        }
+    }
 
-    for txt_file_path in sensor_file_list() {
-        if let Some(sensor) = read_sensor_from_file(&txt_file_path) {
-            sensors.push(sensor.clone())
+    results
+}
+
+#[cfg(feature = "zip")]
+/// Returns all sensors found in a zip archive (e.g. a txt/PDF pair emailed from the field),
+/// unpacking the entries in memory rather than writing them to disk. Each sensor txt file
+/// is paired with a PDF in the same way as for USB drives. Sensor files which can't be read
+/// are logged and skipped, and a NoSensorsFound error is returned if none could be read.
+pub fn read_sensors_from_zip<R: io::Read + io::Seek>(
+    reader: R,
+) -> Result<Vec<Sensor>, TemperatureSensorError> {
+    read_sensors_from_zip_with_extractor(reader, &TextOperatorExtractor)
+}
+
+#[cfg(feature = "zip")]
+/// As read_sensors_from_zip, reading the PDF reports which are checked against the txt
+/// files with the specified extractor (see parse_pdf_with).
+pub fn read_sensors_from_zip_with_extractor<R: io::Read + io::Seek, E: PdfExtractor + ?Sized>(
+    reader: R,
+    extractor: &E,
+) -> Result<Vec<Sensor>, TemperatureSensorError> {
+    let archive_error =
+        |error: zip::result::ZipError| TemperatureSensorError::InvalidArchive(error.to_string());
+    let mut archive = zip::ZipArchive::new(reader).map_err(archive_error)?;
+    let mut txt_files: Vec<(String, Vec<u8>)> = Vec::new();
+    let mut pdf_files: Vec<(String, Vec<u8>)> = Vec::new();
+
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(archive_error)?;
+        if !entry.is_file() {
+            continue;
+        }
+        let entry_path = PathBuf::from(entry.name());
+        let (Some(stem), Some(extension)) = (
+            entry_path.file_stem().and_then(|stem| stem.to_str()),
+            entry_path
+                .extension()
+                .and_then(|extension| extension.to_str()),
+        ) else {
+            continue;
+        };
+        if !matches!(extension, "txt" | "pdf") {
+            continue;
+        }
+        let stem = stem.to_string();
+        let mut contents = Vec::new();
+        entry.read_to_end(&mut contents).map_err(|error| {
+            TemperatureSensorError::InvalidArchive(format!("{}: {}", entry.name(), error))
+        })?;
+        if extension == "pdf" {
+            pdf_files.push((stem, contents));
+        } else if strip_bom(&contents).starts_with(b"Device:") {
+            txt_files.push((stem, contents));
+        }
+    }
+    txt_files.sort();
+
+    let mut sensors: Vec<Sensor> = Vec::new();
+    for (txt_stem, contents) in txt_files {
+        let serial = txt_stem.split('_').next().unwrap_or(&txt_stem);
+        let pdf_file = pdf_files
+            .iter()
+            .find(|(pdf_stem, _)| *pdf_stem == txt_stem)
+            .or_else(|| {
+                pdf_files
+                    .iter()
+                    .find(|(pdf_stem, _)| is_pdf_for_serial(pdf_stem, serial))
+            });
+        match pdf_file {
+            Some((pdf_stem, _)) => log::info!("Reading {}.txt (PDF: {}.pdf)", txt_stem, pdf_stem),
+            None => log::warn!("No matching PDF file found for {}.txt", txt_stem),
+        }
+        let sensor = match read_sensor_from_reader(contents.as_slice(), &ParseOptions::default()) {
+            Ok(sensor) => sensor,
+            Err(err) => {
+                log::error!("{}.txt: {}", txt_stem, err);
+                continue;
+            }
+        };
+        if let Some((pdf_stem, pdf_contents)) = pdf_file {
+            // logs any mismatch or encryption (the txt data is kept either way)
+            check_pdf(
+                &format!("{}.pdf", pdf_stem),
+                pdf_contents,
+                &sensor.serial,
+                extractor,
+            );
         }
+        sensors.push(sensor);
     }
 
-    if sensors.len() > 0 {
-        Some(sensors)
+    if !sensors.is_empty() {
+        Ok(sensors)
     } else {
-        None
+        Err(TemperatureSensorError::NoSensorsFound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::detect_consecutive_breaches;
+    use std::fs::File;
+
+    const FRIDGETAG_2_TXT: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/data/FridgeTag 2/130400191542_202302140605.txt"
+    );
+    const FRIDGETAG_2_PDF: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/data/FridgeTag 2/130400191542_202302140605.pdf"
+    );
+
+    /// Returns the sensor without the file it was read from and when, to compare it with
+    /// the same sensor read another way.
+    fn without_origin(sensor: Sensor) -> Sensor {
+        Sensor {
+            source_path: None,
+            parsed_at: None,
+            ..sensor
+        }
+    }
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("temperature_sensor_{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_txt_without_pdf() {
+        let dir = test_dir("txt_without_pdf");
+        fs::copy(FRIDGETAG_2_TXT, dir.join("130400191542_202302140605.txt")).unwrap();
+
+        let file_list = sensor_files_in_dir(&dir);
+        assert_eq!(file_list.len(), 1);
+        assert!(file_list[0].pdf_file_path.is_none());
+
+        let sensor = read_sensor_from_file(&file_list[0].txt_file_path).unwrap();
+        assert_eq!(sensor.serial, "130400191542");
+        assert!(sensor.logs.is_some());
+    }
+
+    #[test]
+    fn test_duplicate_serials_across_drives() {
+        // the same export on a live drive and a backup drive
+        let live_dir = test_dir("duplicate_serials_live");
+        let backup_dir = test_dir("duplicate_serials_backup");
+        for dir in [&live_dir, &backup_dir] {
+            fs::copy(FRIDGETAG_2_TXT, dir.join("130400191542_202302140605.txt")).unwrap();
+            fs::copy(FRIDGETAG_2_PDF, dir.join("130400191542_202302140605.pdf")).unwrap();
+        }
+
+        let sensors: Vec<Sensor> = [&live_dir, &backup_dir]
+            .iter()
+            .flat_map(|dir| read_sensors_from_dir(dir.to_str().unwrap(), false))
+            .collect();
+        assert_eq!(sensors.len(), 2);
+        assert!(sensors.iter().all(|sensor| sensor.serial == "130400191542"));
+        assert_eq!(
+            sensors[0].source.as_deref(),
+            Some(live_dir.to_str().unwrap())
+        );
+        assert_eq!(
+            sensors[1].source.as_deref(),
+            Some(backup_dir.to_str().unwrap())
+        );
+
+        // a file read directly has no source
+        assert!(read_sensor_from_file(FRIDGETAG_2_TXT)
+            .unwrap()
+            .source
+            .is_none());
+    }
+
+    #[test]
+    fn test_read_sensors_from_paths() {
+        let fridgetag_root =
+            PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/data/FridgeTag 2"));
+        let qtag_root = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/data/QTag CLm doc LR"));
+        let missing_root = test_dir("paths_missing").join("not mounted");
+
+        let sensors =
+            read_sensors_from_paths(&[fridgetag_root.clone(), missing_root, qtag_root.clone()])
+                .unwrap();
+        let mut serials: Vec<&str> = sensors
+            .iter()
+            .map(|sensor| sensor.serial.as_str())
+            .collect();
+        serials.dedup();
+        assert_eq!(serials, vec!["130400191542", "130400191544", "RCPJ00010"]);
+        assert!(sensors[..2]
+            .iter()
+            .all(|sensor| sensor.source.as_deref() == fridgetag_root.to_str()));
+        assert!(sensors[2..]
+            .iter()
+            .all(|sensor| sensor.source.as_deref() == qtag_root.to_str()));
+
+        assert_eq!(
+            read_sensors_from_paths(&[test_dir("paths_empty")]).unwrap_err(),
+            TemperatureSensorError::NoSensorsFound
+        );
+        assert_eq!(
+            read_sensors_from_paths(&[]).unwrap_err(),
+            TemperatureSensorError::NoSensorsFound
+        );
+    }
+
+    #[test]
+    fn test_read_sensors_from_paths_detailed() {
+        // one good txt/PDF pair and one with a corrupt txt file
+        let dir = test_dir("paths_detailed");
+        fs::copy(FRIDGETAG_2_TXT, dir.join("130400191542_202302140605.txt")).unwrap();
+        fs::copy(FRIDGETAG_2_PDF, dir.join("130400191542_202302140605.pdf")).unwrap();
+        let corrupt_txt_file_path = dir.join("130500100001_202206081014.txt");
+        fs::copy(
+            concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/data/test/broken_temperature.txt"
+            ),
+            &corrupt_txt_file_path,
+        )
+        .unwrap();
+        fs::copy(FRIDGETAG_2_PDF, dir.join("130500100001_202206081014.pdf")).unwrap();
+
+        let results = read_sensors_from_paths_detailed(std::slice::from_ref(&dir));
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_ref().unwrap().serial, "130400191542");
+        let (txt_file_path, err) = results[1].as_ref().unwrap_err();
+        assert_eq!(txt_file_path, &corrupt_txt_file_path);
+        assert!(matches!(err, TemperatureSensorError::ParseError { .. }));
+
+        // only the good one without the details
+        let sensors = read_sensors_from_paths(&[dir]).unwrap();
+        assert_eq!(sensors.len(), 1);
+        assert_eq!(sensors[0].serial, "130400191542");
+    }
+
+    #[test]
+    fn test_read_sensors_from_dir_recursive() {
+        // per-date folders, with the PDF of the nested txt file in its parent folder
+        let dir = test_dir("recursive");
+        let first_dir = dir.join("2023-02-14");
+        let nested_dir = dir.join("2023-04-20").join("fridge 2");
+        fs::create_dir_all(&first_dir).unwrap();
+        fs::create_dir_all(&nested_dir).unwrap();
+        fs::copy(
+            FRIDGETAG_2_TXT,
+            first_dir.join("130400191542_202302140605.txt"),
+        )
+        .unwrap();
+        fs::copy(
+            FRIDGETAG_2_PDF,
+            first_dir.join("130400191542_202302140605.pdf"),
+        )
+        .unwrap();
+        fs::copy(
+            concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/data/FridgeTag 2/130400191544_202304201514.txt"
+            ),
+            nested_dir.join("130400191544_202304201514.txt"),
+        )
+        .unwrap();
+        fs::copy(
+            concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/data/FridgeTag 2/130400191544_202304201514.pdf"
+            ),
+            dir.join("2023-04-20").join("130400191544_202304201514.pdf"),
+        )
+        .unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&dir, nested_dir.join("loop")).unwrap();
+
+        let dir_path = dir.to_str().unwrap();
+        assert!(read_sensors_from_dir(dir_path, false).is_empty());
+
+        let sensors = read_sensors_from_dir(dir_path, true);
+        let serials_and_sources: Vec<(&str, &str)> = sensors
+            .iter()
+            .map(|sensor| (sensor.serial.as_str(), sensor.source.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            serials_and_sources,
+            vec![
+                ("130400191542", first_dir.to_str().unwrap()),
+                ("130400191544", nested_dir.to_str().unwrap()),
+            ]
+        );
+
+        // PDFs are only paired within the same folder
+        let nested_files = sensor_files_in_dir(&nested_dir);
+        assert_eq!(nested_files.len(), 1);
+        assert!(nested_files[0].pdf_file_path.is_none());
+        assert!(sensor_files_in_dir(&first_dir)[0].pdf_file_path.is_some());
+    }
+
+    #[test]
+    fn test_multiple_pdfs() {
+        let dir = test_dir("multiple_pdfs");
+        fs::copy(FRIDGETAG_2_TXT, dir.join("130400191542_202302140605.txt")).unwrap();
+        fs::copy(FRIDGETAG_2_PDF, dir.join("130400191542_202301010000.pdf")).unwrap();
+        fs::copy(FRIDGETAG_2_PDF, dir.join("130400191542_202212010000.pdf")).unwrap();
+
+        // make the second PDF the newest one
+        let now = SystemTime::now();
+        File::options()
+            .write(true)
+            .open(dir.join("130400191542_202301010000.pdf"))
+            .unwrap()
+            .set_modified(now - std::time::Duration::from_secs(3600))
+            .unwrap();
+        File::options()
+            .write(true)
+            .open(dir.join("130400191542_202212010000.pdf"))
+            .unwrap()
+            .set_modified(now)
+            .unwrap();
+
+        let file_list = sensor_files_in_dir(&dir);
+        assert_eq!(file_list.len(), 1);
+        let pdf_file_path = file_list[0].pdf_file_path.clone().unwrap();
+        assert!(pdf_file_path.ends_with("130400191542_202212010000.pdf"));
+
+        // an exact name match is preferred over a newer PDF
+        fs::copy(FRIDGETAG_2_PDF, dir.join("130400191542_202302140605.pdf")).unwrap();
+        let file_list = sensor_files_in_dir(&dir);
+        let pdf_file_path = file_list[0].pdf_file_path.clone().unwrap();
+        assert!(pdf_file_path.ends_with("130400191542_202302140605.pdf"));
+    }
+
+    fn config_limits(
+        configs: &Option<Vec<TemperatureBreachConfig>>,
+    ) -> Vec<(BreachType, Celsius, Celsius, Duration)> {
+        configs
+            .iter()
+            .flatten()
+            .map(|config| {
+                (
+                    config.breach_type.clone(),
+                    config.maximum_temperature,
+                    config.minimum_temperature,
+                    config.duration,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_pdf() {
+        // the PDF reports match their txt files, apart from the logs
+        for (txt_file_path, model) in [
+            (
+                "/data/FridgeTag 2/130400191542_202302140605.txt",
+                BerlingerModel::FridgeTag2,
+            ),
+            (
+                "/data/FridgeTag 2/130400191544_202304201514.txt",
+                BerlingerModel::FridgeTag2,
+            ),
+            (
+                "/data/FridgeTag 2L/130500109088_202206081014.txt",
+                BerlingerModel::FridgeTag2L,
+            ),
+            (
+                "/data/FridgeTag UL/100800001215_202304201410.txt",
+                BerlingerModel::FridgeTagUL,
+            ),
+        ] {
+            let txt_file_path = format!("{}{}", env!("CARGO_MANIFEST_DIR"), txt_file_path);
+            let sensor = read_sensor_from_file(&txt_file_path).unwrap();
+            let pdf = fs::read(txt_file_path.replace(".txt", ".pdf")).unwrap();
+            let pdf_sensor_data = parse_pdf(&pdf).unwrap();
+
+            assert_eq!(pdf_sensor_data.serial, sensor.serial);
+            assert_eq!(pdf_sensor_data.model, Some(model));
+            assert_eq!(
+                pdf_sensor_data.activation_timestamp,
+                sensor.activation_timestamp
+            );
+            assert_eq!(
+                pdf_sensor_data.last_connected_timestamp,
+                sensor.last_connected_timestamp
+            );
+            assert_eq!(pdf_sensor_data.stop_timestamp, sensor.stop_timestamp);
+            assert_eq!(pdf_sensor_data.log_interval, sensor.log_interval);
+            assert_eq!(
+                config_limits(&pdf_sensor_data.configs),
+                config_limits(&sensor.configs)
+            );
+            let breach_times = |breaches: Option<Vec<TemperatureBreach>>| {
+                breaches
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|breach| {
+                        (
+                            breach.breach_type,
+                            breach.start_timestamp,
+                            breach.end_timestamp,
+                            breach.duration,
+                            breach.trigger_timestamp,
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(
+                breach_times(pdf_sensor_data.breaches),
+                breach_times(sensor.breaches),
+                "{}",
+                txt_file_path
+            );
+        }
+
+        // Q-tag cumulative breach start times are only estimated from the trigger time
+        let qtag_txt_file_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/QTag CLm doc LR/RCPJ00010_02_202211090128.txt"
+        );
+        let sensor = read_sensor_from_file(qtag_txt_file_path).unwrap();
+        let pdf = fs::read(qtag_txt_file_path.replace(".txt", ".pdf")).unwrap();
+        let pdf_sensor_data = parse_pdf(&pdf).unwrap();
+        assert_eq!(pdf_sensor_data.serial, "RCPJ00010");
+        assert_eq!(pdf_sensor_data.model, Some(BerlingerModel::QtagCLm));
+        assert_eq!(
+            pdf_sensor_data.activation_timestamp,
+            sensor.activation_timestamp
+        );
+        assert_eq!(pdf_sensor_data.stop_timestamp, sensor.stop_timestamp);
+        assert_eq!(pdf_sensor_data.log_interval, Some(Duration::minutes(10)));
+        assert_eq!(
+            config_limits(&pdf_sensor_data.configs),
+            config_limits(&sensor.configs)
+        );
+        let consecutive_breaches = |breaches: Option<Vec<TemperatureBreach>>| {
+            breaches
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|breach| {
+                    matches!(
+                        breach.breach_type,
+                        BreachType::ColdConsecutive | BreachType::HotConsecutive
+                    )
+                })
+                .map(|breach| (breach.start_timestamp, breach.end_timestamp))
+                .collect::<Vec<_>>()
+        };
+        let pdf_breaches = pdf_sensor_data.breaches.clone();
+        assert_eq!(consecutive_breaches(pdf_breaches.clone()).len(), 4);
+        assert_eq!(
+            consecutive_breaches(pdf_breaches),
+            consecutive_breaches(sensor.breaches)
+        );
+
+        assert_eq!(
+            parse_pdf(b"%PDF-1.4 not a sensor report").unwrap_err(),
+            TemperatureSensorError::InvalidPdf("no identification number found".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_pdf_with_extractor() {
+        struct MockExtractor(Vec<&'static str>);
+        impl PdfExtractor for MockExtractor {
+            fn extract_strings(&self, _pdf: &[u8]) -> Result<Vec<String>, TemperatureSensorError> {
+                if self.0.is_empty() {
+                    return Err(TemperatureSensorError::InvalidPdf("unreadable".to_string()));
+                }
+                Ok(self.0.iter().map(|string| string.to_string()).collect())
+            }
+        }
+
+        // the PDF contents are left to the extractor
+        let extractor = MockExtractor(vec![
+            "Lower alarm limit:",
+            "Below -0.5\u{b0}C for 1h ",
+            "Upper alarm limit:",
+            "Above +8.0\u{b0}C for 10h ",
+            "Activation date:",
+            "24.03.2022 02:22h",
+            "Date and time of report creation:",
+            "14.02.2023 06:05h",
+            "Identification number:",
+            "130400199999",
+            "PDF document of the Fridge-tag\u{ae} 2",
+        ]);
+        let pdf_sensor_data = parse_pdf_with(b"not a PDF", &extractor).unwrap();
+        assert_eq!(pdf_sensor_data.serial, "130400199999");
+        assert_eq!(pdf_sensor_data.model, Some(BerlingerModel::FridgeTag2));
+        assert_eq!(
+            pdf_sensor_data.last_connected_timestamp,
+            NaiveDateTime::parse_from_str("2023-02-14 06:05", "%Y-%m-%d %H:%M").ok()
+        );
+        assert_eq!(pdf_sensor_data.configs.unwrap().len(), 4);
+
+        assert_eq!(
+            parse_pdf_with(b"not a PDF", &MockExtractor(vec![])).unwrap_err(),
+            TemperatureSensorError::InvalidPdf("unreadable".to_string())
+        );
+
+        // the default extractor is the one parse_pdf uses
+        let fridgetag_pdf = fs::read(FRIDGETAG_2_PDF).unwrap();
+        assert_eq!(
+            format!(
+                "{:?}",
+                parse_pdf_with(&fridgetag_pdf, &TextOperatorExtractor).unwrap()
+            ),
+            format!("{:?}", parse_pdf(&fridgetag_pdf).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_pdf_serial_mismatch() {
+        let qtag_pdf = fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/QTag CLm doc LR/RCPJ00010_02_202211090128.pdf"
+        ))
+        .unwrap();
+        assert_eq!(
+            pdf_serial(&qtag_pdf, &TextOperatorExtractor),
+            Ok(Some("RCPJ00010".to_string()))
+        );
+        assert_eq!(
+            pdf_serial(&fs::read(FRIDGETAG_2_PDF).unwrap(), &TextOperatorExtractor),
+            Ok(Some("130400191542".to_string()))
+        );
+
+        // the PDF of another Fridge-tag copied next to the txt file under a matching name
+        let dir = test_dir("pdf_serial_mismatch");
+        let txt_file_path = dir.join("130400191542_202302140605.txt");
+        fs::copy(FRIDGETAG_2_TXT, &txt_file_path).unwrap();
+        fs::copy(
+            concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/data/FridgeTag 2/130400191544_202304201514.pdf"
+            ),
+            dir.join("130400191542_202302140605.pdf"),
+        )
+        .unwrap();
+
+        let parsed_sensor = read_sensor_from_file_verbose(txt_file_path.to_str().unwrap()).unwrap();
+        assert_eq!(parsed_sensor.sensor.serial, "130400191542");
+        assert!(parsed_sensor
+            .warnings
+            .contains(&ParseWarning::SerialMismatch {
+                txt: "130400191542".to_string(),
+                pdf: "130400191544".to_string(),
+            }));
+
+        // no warning once the right PDF is in place
+        fs::copy(FRIDGETAG_2_PDF, dir.join("130400191542_202302140605.pdf")).unwrap();
+        let parsed_sensor = read_sensor_from_file_verbose(txt_file_path.to_str().unwrap()).unwrap();
+        assert!(!parsed_sensor
+            .warnings
+            .iter()
+            .any(|warning| matches!(warning, ParseWarning::SerialMismatch { .. })));
+    }
+
+    /// Returns the canned strings for every PDF, counting the PDFs read.
+    struct CountingExtractor {
+        strings: Vec<&'static str>,
+        count: std::cell::Cell<usize>,
+    }
+
+    impl CountingExtractor {
+        fn new(strings: Vec<&'static str>) -> Self {
+            CountingExtractor {
+                strings,
+                count: std::cell::Cell::new(0),
+            }
+        }
+    }
+
+    impl PdfExtractor for CountingExtractor {
+        fn extract_strings(&self, _pdf: &[u8]) -> Result<Vec<String>, TemperatureSensorError> {
+            self.count.set(self.count.get() + 1);
+            if self.strings.is_empty() {
+                return Err(TemperatureSensorError::EncryptedPdf);
+            }
+            Ok(self
+                .strings
+                .iter()
+                .map(|string| string.to_string())
+                .collect())
+        }
+    }
+
+    #[test]
+    fn test_read_sensors_with_extractor() {
+        let dir = test_dir("read_sensors_with_extractor");
+        let txt_file_path = dir.join("130400191542_202302140605.txt");
+        fs::copy(FRIDGETAG_2_TXT, &txt_file_path).unwrap();
+        fs::copy(FRIDGETAG_2_PDF, dir.join("130400191542_202302140605.pdf")).unwrap();
+        let txt_file_path = txt_file_path.to_str().unwrap();
+
+        // the PDF is checked with the extractor's text rather than its own
+        let extractor = CountingExtractor::new(vec!["Identification number:", "130400199999"]);
+        let parsed_sensor = read_sensor_from_file_verbose_with_extractor(
+            txt_file_path,
+            &Default::default(),
+            &extractor,
+        )
+        .unwrap();
+        assert_eq!(extractor.count.get(), 1);
+        assert!(parsed_sensor
+            .warnings
+            .contains(&ParseWarning::SerialMismatch {
+                txt: "130400191542".to_string(),
+                pdf: "130400199999".to_string(),
+            }));
+        let extractor = CountingExtractor::new(vec![]);
+        let parsed_sensor = read_sensor_from_file_verbose_with_extractor(
+            txt_file_path,
+            &Default::default(),
+            &extractor,
+        )
+        .unwrap();
+        assert!(parsed_sensor.warnings.contains(&ParseWarning::EncryptedPdf));
+
+        // the txt data is kept either way
+        let sensors =
+            read_sensors_from_dir_with_extractor(dir.to_str().unwrap(), false, &extractor);
+        assert_eq!(sensors.len(), 1);
+        assert_eq!(extractor.count.get(), 2);
+        let sensors = read_sensors_from_paths_with_extractor(&[dir], &extractor).unwrap();
+        assert_eq!(sensors[0].serial, "130400191542");
+        assert_eq!(extractor.count.get(), 3);
+    }
+
+    #[test]
+    fn test_encrypted_pdf() {
+        let encrypted_pdf_path = concat!(env!("CARGO_MANIFEST_DIR"), "/data/test/encrypted.pdf");
+        let encrypted_pdf = fs::read(encrypted_pdf_path).unwrap();
+        assert!(is_encrypted_pdf(&encrypted_pdf));
+        assert!(!is_encrypted_pdf(&fs::read(FRIDGETAG_2_PDF).unwrap()));
+        assert_eq!(
+            parse_pdf(&encrypted_pdf).unwrap_err(),
+            TemperatureSensorError::EncryptedPdf
+        );
+
+        // the txt data is still read when its PDF is encrypted
+        let dir = test_dir("encrypted_pdf");
+        let txt_file_path = dir.join("130400191542_202302140605.txt");
+        fs::copy(FRIDGETAG_2_TXT, &txt_file_path).unwrap();
+        fs::copy(
+            encrypted_pdf_path,
+            dir.join("130400191542_202302140605.pdf"),
+        )
+        .unwrap();
+
+        let expected_sensor = read_sensor_from_file(FRIDGETAG_2_TXT).unwrap();
+        let parsed_sensor = read_sensor_from_file_verbose(txt_file_path.to_str().unwrap()).unwrap();
+        assert!(parsed_sensor.warnings.contains(&ParseWarning::EncryptedPdf));
+        assert_eq!(parsed_sensor.sensor.serial, expected_sensor.serial);
+        assert_eq!(
+            parsed_sensor.sensor.content_hash(),
+            expected_sensor.content_hash()
+        );
+    }
+
+    #[test]
+    fn test_log_table_columns() {
+        let standard_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/test/logs_standard_columns.txt"
+        );
+        let optional_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/test/logs_optional_columns.txt"
+        );
+
+        let read_json = |file_path: &str| {
+            read_sensor_to_json(
+                io::BufReader::new(File::open(file_path).unwrap()),
+                None,
+                false,
+            )
+            .unwrap()
+            .0
+        };
+        let standard_json = read_json(standard_path);
+        let optional_json = read_json(optional_path);
+        assert_eq!(standard_json["Data"]["Temperature"][2], "8.6");
+        assert_eq!(standard_json["Data"], optional_json["Data"]);
+
+        let breach_flags: Vec<bool> = optional_json["Data"]["Breach"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|flag| flag.as_bool().unwrap())
+            .collect();
+        assert_eq!(breach_flags, vec![false, false, true, true, false]);
+
+        let standard_sensor = read_sensor_from_file(standard_path).unwrap();
+        let optional_sensor = read_sensor_from_file(optional_path).unwrap();
+        let standard_logs = standard_sensor.logs.unwrap();
+        let optional_logs = optional_sensor.logs.unwrap();
+        assert_eq!(standard_logs.len(), 7); // 5 data logs + daily min & max
+        assert_eq!(standard_logs.len(), optional_logs.len());
+        for (standard_log, optional_log) in standard_logs.iter().zip(optional_logs.iter()) {
+            assert_eq!(standard_log.timestamp, optional_log.timestamp);
+            assert_eq!(standard_log.temperature, optional_log.temperature);
+        }
+    }
+
+    fn assert_parse_error(fixture: &str, expected_line: usize, expected_field: &str) {
+        let file_path = format!("{}/data/test/{}", env!("CARGO_MANIFEST_DIR"), fixture);
+        match read_sensor_from_file(&file_path) {
+            Err(TemperatureSensorError::ParseError {
+                line,
+                content,
+                field,
+            }) => {
+                assert_eq!(line, expected_line);
+                assert_eq!(field, expected_field);
+                let file_contents = fs::read_to_string(&file_path).unwrap();
+                assert_eq!(file_contents.lines().nth(line - 1), Some(content.as_str()));
+            }
+            other => panic!("expected ParseError for {}, got {:?}", fixture, other),
+        }
+    }
+
+    #[test]
+    fn test_parse_error_line() {
+        assert_parse_error("broken_temperature.txt", 32, "Temperature");
+        assert_parse_error("broken_date.txt", 18, "Date");
+        assert_parse_error("broken_alarm_type.txt", 10, "Type");
+    }
+
+    #[test]
+    fn test_lenient_truncated_file() {
+        let file_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/test/truncated_mid_log.txt"
+        );
+        let lenient = ParseOptions {
+            lenient: true,
+            ..ParseOptions::default()
+        };
+
+        assert!(matches!(
+            read_sensor_from_file(file_path),
+            Err(TemperatureSensorError::ParseError { line: 31, .. })
+        ));
+        let parsed_sensor =
+            read_sensor_from_file_verbose_with_options(file_path, &lenient).unwrap();
+        assert!(parsed_sensor
+            .warnings
+            .contains(&ParseWarning::Truncated { at_line: 31 }));
+        let sensor = parsed_sensor.sensor;
+        assert_eq!(sensor.serial, "QTAG00012");
+        assert_eq!(
+            sensor.logs.unwrap().last().unwrap().timestamp,
+            NaiveDateTime::parse_from_str("2023-05-01 09:20", "%Y-%m-%d %H:%M").unwrap()
+        );
+
+        // complete files have no warning
+        let parsed_sensor = read_sensor_from_file_verbose_with_options(
+            concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/data/test/qtag_low_battery.txt"
+            ),
+            &lenient,
+        )
+        .unwrap();
+        assert!(!parsed_sensor
+            .warnings
+            .iter()
+            .any(|warning| matches!(warning, ParseWarning::Truncated { .. })));
+
+        // only the last line can be dropped, and the header must still be there
+        let bad_row_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/test/truncated_data_row.txt"
+        );
+        assert!(read_sensor_from_file_with_options(bad_row_path, &lenient).is_err());
+        let garbled_header = "Devi\u{fffd}e: Q-tag CLm doc LR\n";
+        assert!(read_sensor_from_reader(garbled_header.as_bytes(), &lenient).is_err());
+        let cut_header = "Devic";
+        assert!(read_sensor_from_reader(cut_header.as_bytes(), &lenient).is_err());
+    }
+
+    #[test]
+    fn test_txt_parse_error_line() {
+        let file_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/test/truncated_data_row.txt"
+        );
+        let error = read_sensor_from_file(file_path).unwrap_err();
+        assert_eq!(
+            error,
+            TemperatureSensorError::ParseError {
+                line: 32,
+                content: "\t2023-05-01 09:10".to_string(),
+                field: "temperature".to_string(),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "Invalid temperature at line 32: 2023-05-01 09:10"
+        );
+
+        let file_path = concat!(env!("CARGO_MANIFEST_DIR"), "/data/test/garbled_bytes.txt");
+        match read_sensor_from_file(file_path) {
+            Err(TemperatureSensorError::ParseError {
+                line,
+                content,
+                field,
+            }) => {
+                assert_eq!(line, 33);
+                assert_eq!(field, "text");
+                assert!(content.starts_with("\t2023-05-01 09:15\t9."));
+            }
+            other => panic!("expected ParseError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_qtag_five_configs() {
+        let sensor = read_sensor_from_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/test/qtag_five_configs.txt"
+        ))
+        .unwrap();
+        let configs = sensor.configs.unwrap();
+        assert_eq!(configs.len(), 5);
+
+        // (breach type, max temperature, min temperature, duration in minutes)
+        let expected_configs = [
+            (BreachType::HotConsecutive, 8.0, -273.0, 30),
+            (BreachType::HotCumulative, 8.5, -273.0, 60),
+            (BreachType::ColdConsecutive, 100.0, 2.0, 45),
+            (BreachType::ColdCumulative, 100.0, 1.5, 120),
+            (BreachType::HotConsecutive, 25.0, -273.0, 10),
+        ];
+        for (config, (breach_type, maximum_temperature, minimum_temperature, duration)) in
+            configs.iter().zip(expected_configs)
+        {
+            assert_eq!(config.breach_type, breach_type);
+            assert_eq!(config.maximum_temperature, Celsius(maximum_temperature));
+            assert_eq!(config.minimum_temperature, Celsius(minimum_temperature));
+            assert_eq!(config.duration, Duration::minutes(duration));
+        }
+    }
+
+    #[test]
+    fn test_detect_sensor_model() {
+        let fixtures = [
+            (
+                "/data/FridgeTag 2/130400191542_202302140605.txt",
+                BerlingerModel::FridgeTag2,
+            ),
+            (
+                "/data/FridgeTag 2L/130500109088_202206081014.txt",
+                BerlingerModel::FridgeTag2L,
+            ),
+            (
+                "/data/FridgeTag UL/100800001215_202304201410.txt",
+                BerlingerModel::FridgeTagUL,
+            ),
+            (
+                "/data/QTag CLm doc LR/RCPJ00010_02_202211090128.txt",
+                BerlingerModel::QtagCLm,
+            ),
+        ];
+        for (file_path, model) in fixtures {
+            let txt =
+                fs::read_to_string(format!("{}{}", env!("CARGO_MANIFEST_DIR"), file_path)).unwrap();
+            assert_eq!(detect_sensor_model(&txt), Some(model), "{}", file_path);
+        }
+
+        assert_eq!(
+            detect_sensor_model("Device: Q-tag Unknown\nVers: 0.5"),
+            None
+        );
+        assert_eq!(detect_sensor_model("Vers: 0.5\nFw Vers: 3.4p0o"), None);
+        assert_eq!(detect_sensor_model(""), None);
+    }
+
+    #[test]
+    fn test_parsed_sensor_type() {
+        for (file_path, model) in [
+            (FRIDGETAG_2_TXT, BerlingerModel::FridgeTag2),
+            (
+                concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/data/FridgeTag UL/100800001215_202304201410.txt"
+                ),
+                BerlingerModel::FridgeTagUL,
+            ),
+            (
+                concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/data/QTag CLm doc LR/RCPJ00010_02_202211090128.txt"
+                ),
+                BerlingerModel::QtagCLm,
+            ),
+        ] {
+            let sensor = read_sensor_from_file(file_path).unwrap();
+            assert_eq!(
+                sensor.sensor_type,
+                SensorType::Berlinger(Some(model)),
+                "{}",
+                file_path
+            );
+        }
+
+        // unknown devices are still read, without a model
+        let txt = write_sensor_to_txt(&crate::sample_sensor());
+        let sensor = read_sensor_from_reader(txt.as_bytes(), &ParseOptions::default()).unwrap();
+        assert_eq!(sensor.sensor_type, SensorType::Berlinger(None));
+    }
+
+    #[test]
+    fn test_model_capabilities() {
+        assert_eq!(
+            BerlingerModel::FridgeTag2.capabilities(),
+            ModelCapabilities {
+                has_logs: false,
+                records_consecutive: false,
+                records_cumulative: true,
+                max_configs: 2,
+            }
+        );
+        assert_eq!(
+            BerlingerModel::FridgeTag2L.capabilities(),
+            ModelCapabilities {
+                has_logs: true,
+                records_consecutive: false,
+                records_cumulative: true,
+                max_configs: 2,
+            }
+        );
+        assert_eq!(
+            BerlingerModel::QtagCLm.capabilities(),
+            ModelCapabilities {
+                has_logs: true,
+                records_consecutive: true,
+                records_cumulative: true,
+                max_configs: 5,
+            }
+        );
+
+        // consistent with the example files
+        for file_path in [
+            "/data/FridgeTag 2/130400191542_202302140605.txt",
+            "/data/FridgeTag 2L/130500109088_202206081014.txt",
+            "/data/FridgeTag UL/100800001215_202304201410.txt",
+            "/data/QTag CLm doc LR/RCPJ00010_02_202211090128.txt",
+        ] {
+            let file_path = format!("{}{}", env!("CARGO_MANIFEST_DIR"), file_path);
+            let txt = fs::read_to_string(&file_path).unwrap();
+            let capabilities = detect_sensor_model(&txt).unwrap().capabilities();
+            let sensor = read_sensor_from_file(&file_path).unwrap();
+            assert_eq!(
+                txt.contains("\nData:"),
+                capabilities.has_logs,
+                "{}",
+                file_path
+            );
+            // Fridge-tag alarms are read as both a consecutive & a cumulative config
+            assert!(txt.matches("T AL:").count() <= capabilities.max_configs);
+            if !capabilities.records_consecutive {
+                assert!(sensor.breaches.iter().flatten().all(|breach| matches!(
+                    breach.breach_type,
+                    BreachType::HotCumulative | BreachType::ColdCumulative
+                )));
+            }
+        }
+        assert_eq!(BerlingerModel::ALL.len(), 4);
+    }
+
+    #[test]
+    fn test_round_temperatures() {
+        let file_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/test/four_decimal_temperatures.txt"
+        );
+        let options = ParseOptions {
+            round_temperatures_to: Some(1),
+            ..Default::default()
+        };
+        let sensor = read_sensor_from_file_with_options(file_path, &options).unwrap();
+
+        let temperatures: Vec<f64> = sensor
+            .logs
+            .unwrap()
+            .iter()
+            .map(|log| log.temperature.0)
+            .collect();
+        // includes the Hist min/max logs (ties round to even: 8.625 -> 8.6)
+        assert_eq!(temperatures, vec![4.1, 4.1, 5.1, 8.6, 9.2, 9.2, 6.4]);
+
+        let configs = sensor.configs.unwrap();
+        assert_eq!(configs[0].minimum_temperature, Celsius(2.2)); // 2.25
+        assert_eq!(configs[2].maximum_temperature, Celsius(8.8)); // 8.75
+
+        // no rounding by default
+        let sensor = read_sensor_from_file(file_path).unwrap();
+        assert_eq!(sensor.logs.unwrap()[0].temperature, Celsius(4.1234));
+    }
+
+    #[cfg(feature = "flate2")]
+    #[test]
+    fn test_gzip_txt_file() {
+        let plain_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/FridgeTag 2L/130500109088_202206081014.txt"
+        );
+        let gzip_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/test/fridgetag_2l_gzip.txt.gz"
+        );
+        assert!(fs::read(gzip_path).unwrap().starts_with(&[0x1f, 0x8b]));
+
+        let plain_sensor = without_origin(read_sensor_from_file(plain_path).unwrap());
+        let gzip_sensor = without_origin(read_sensor_from_file(gzip_path).unwrap());
+        assert_eq!(format!("{:?}", gzip_sensor), format!("{:?}", plain_sensor));
+
+        // corrupt gzip data is reported rather than parsed as text
+        let mut corrupt = fs::read(gzip_path).unwrap();
+        corrupt.truncate(20);
+        assert!(matches!(
+            read_sensor_from_reader(corrupt.as_slice(), &ParseOptions::default()),
+            Err(TemperatureSensorError::ParseError { line: 0, .. })
+        ));
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn test_read_sensors_from_zip() {
+        let zip_file = File::open(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/test/fridgetag_2_pair.zip"
+        ))
+        .unwrap();
+        let sensors = read_sensors_from_zip(zip_file).unwrap();
+        assert_eq!(sensors.len(), 1);
+
+        let expected = read_sensor_from_file(FRIDGETAG_2_TXT).unwrap();
+        assert_eq!(sensors[0].serial, expected.serial);
+        assert_eq!(
+            sensors[0].breaches.as_ref().map(Vec::len),
+            expected.breaches.map(|b| b.len())
+        );
+        assert_eq!(
+            sensors[0].logs.as_ref().map(Vec::len),
+            expected.logs.map(|l| l.len())
+        );
+
+        // the paired PDF is read with the extractor
+        let zip_file = File::open(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/test/fridgetag_2_pair.zip"
+        ))
+        .unwrap();
+        let extractor = CountingExtractor::new(vec!["Identification number:", "130400191542"]);
+        let sensors = read_sensors_from_zip_with_extractor(zip_file, &extractor).unwrap();
+        assert_eq!(sensors.len(), 1);
+        assert_eq!(extractor.count.get(), 1);
+
+        // a corrupt sensor file is skipped rather than failing the whole archive
+        let zip_file = File::open(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/test/fridgetag_2_corrupt_entry.zip"
+        ))
+        .unwrap();
+        let sensors = read_sensors_from_zip(zip_file).unwrap();
+        assert_eq!(sensors.len(), 1);
+        assert_eq!(sensors[0].serial, expected.serial);
+
+        // but an archive without any readable sensor files has no sensors
+        let mut empty_zip = io::Cursor::new(Vec::new());
+        zip::ZipWriter::new(&mut empty_zip).finish().unwrap();
+        assert_eq!(
+            read_sensors_from_zip(empty_zip).unwrap_err(),
+            TemperatureSensorError::NoSensorsFound
+        );
+
+        let not_a_zip = io::Cursor::new(b"Device: Q-tag Fridge-tag 2".to_vec());
+        assert!(matches!(
+            read_sensors_from_zip(not_a_zip),
+            Err(TemperatureSensorError::InvalidArchive(_))
+        ));
+    }
+
+    #[test]
+    fn test_last_connected_fallback() {
+        let sensor = read_sensor_from_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/test/no_report_timestamp.txt"
+        ))
+        .unwrap();
+        assert_eq!(
+            sensor.last_connected_timestamp,
+            NaiveDateTime::parse_from_str("2023-05-01 09:20", "%Y-%m-%d %H:%M").ok()
+        );
+
+        // the report timestamp takes precedence when present
+        let sensor = read_sensor_from_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/test/logs_standard_columns.txt"
+        ))
+        .unwrap();
+        assert_eq!(
+            sensor.last_connected_timestamp,
+            NaiveDateTime::parse_from_str("2023-05-01 10:00", "%Y-%m-%d %H:%M").ok()
+        );
+    }
+
+    #[test]
+    fn test_breach_peak_temperature() {
+        let sensor = read_sensor_from_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/QTag CLm doc LR/RCPJ00010_03_202304201359.txt"
+        ))
+        .unwrap();
+        let breaches = sensor.breaches.unwrap();
+        let hot_breach = breaches
+            .iter()
+            .find(|breach| breach.breach_type == BreachType::HotConsecutive)
+            .unwrap();
+        // the alarm max (11.4 at 16:17), not the 12.7 start reading before the breach window
+        assert_eq!(hot_breach.peak_temperature, Some(Celsius(11.4)));
+
+        // no logs -> no peak
+        let mut sensor = read_sensor_from_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/QTag CLm doc LR/RCPJ00010_03_202304201359.txt"
+        ))
+        .unwrap();
+        sensor.logs = None;
+        sensor.update_peak_temperatures();
+        assert!(sensor
+            .breaches
+            .unwrap()
+            .iter()
+            .all(|breach| breach.peak_temperature.is_none()));
+    }
+
+    #[test]
+    fn test_empty_file() {
+        for fixture in ["empty.txt", "blank_lines.txt"] {
+            let file_path = format!("{}/data/test/{}", env!("CARGO_MANIFEST_DIR"), fixture);
+            let error = read_sensor_from_file(&file_path).unwrap_err();
+            assert_eq!(
+                error,
+                TemperatureSensorError::ParseError {
+                    line: 0,
+                    content: "file is empty or contains no sensor header".to_string(),
+                    field: "Device".to_string(),
+                },
+                "{}",
+                fixture
+            );
+            assert_eq!(
+                error.to_string(),
+                "Invalid Device: file is empty or contains no sensor header"
+            );
+        }
+    }
+
+    #[test]
+    fn test_bom_and_line_endings() {
+        let read_fixture = |fixture: &str| {
+            let file_path = format!("{}/data/test/{}", env!("CARGO_MANIFEST_DIR"), fixture);
+            format!(
+                "{:?}",
+                without_origin(read_sensor_from_file(&file_path).unwrap())
+            )
+        };
+        let reference = read_fixture("logs_standard_columns.txt");
+        assert_eq!(read_fixture("logs_bom.txt"), reference);
+        assert_eq!(read_fixture("logs_crlf.txt"), reference);
+
+        // old-style CR line endings
+        let contents = fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/test/logs_standard_columns.txt"
+        ))
+        .unwrap();
+        let cr_contents: Vec<u8> = contents
+            .iter()
+            .map(|byte| if *byte == b'\n' { b'\r' } else { *byte })
+            .collect();
+        let sensor = read_sensor_from_reader(cr_contents.as_slice(), &ParseOptions::default());
+        assert_eq!(format!("{:?}", sensor.unwrap()), reference);
+    }
+
+    #[test]
+    fn test_override_configs() {
+        let file_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/test/logs_standard_columns.txt"
+        );
+        let hot_config = |maximum_temperature: f64| TemperatureBreachConfig {
+            breach_type: BreachType::HotConsecutive,
+            maximum_temperature: Celsius(maximum_temperature),
+            minimum_temperature: Celsius(-273.0),
+            duration: Duration::minutes(5),
+        };
+        let read_with_configs = |configs: Vec<TemperatureBreachConfig>| {
+            let options = ParseOptions {
+                override_configs: Some(configs),
+                ..Default::default()
+            };
+            read_sensor_from_file_with_options(file_path, &options).unwrap()
+        };
+
+        // the recorded alarms have no breaches
+        assert!(read_sensor_from_file(file_path)
+            .unwrap()
+            .breaches
+            .unwrap()
+            .is_empty());
+
+        // 8.6 at 09:10 and 9.2 at 09:15 are over 8.0 for 5 minutes
+        let sensor = read_with_configs(vec![hot_config(8.0)]);
+        assert_eq!(sensor.configs.unwrap().len(), 1);
+        let breaches = sensor.breaches.unwrap();
+        assert_eq!(breaches.len(), 1);
+        assert_eq!(
+            breaches[0].start_timestamp,
+            NaiveDateTime::parse_from_str("2023-05-01 09:10", "%Y-%m-%d %H:%M").unwrap()
+        );
+        assert_eq!(breaches[0].duration, Duration::minutes(5));
+        assert_eq!(breaches[0].peak_temperature, Some(Celsius(9.2)));
+
+        // only 9.2 is over 9.0, which isn't long enough for a breach
+        let sensor = read_with_configs(vec![hot_config(9.0)]);
+        assert!(sensor.breaches.unwrap().is_empty());
+
+        // the 8.0 breach only has three breaching logs (9.2 is logged again as the max)
+        let options = ParseOptions {
+            override_configs: Some(vec![hot_config(8.0)]),
+            min_breach_samples: 4,
+            ..Default::default()
+        };
+        let sensor = read_sensor_from_file_with_options(file_path, &options).unwrap();
+        assert!(sensor.breaches.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_fridgetag_ul_cold_breach() {
+        let sensor = read_sensor_from_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/test/fridgetag_ul_cold.txt"
+        ))
+        .unwrap();
+        let timestamp = |time: &str| {
+            NaiveDateTime::parse_from_str(&format!("2023-04-18 {}", time), "%Y-%m-%d %H:%M")
+                .unwrap()
+        };
+
+        let logs = sensor.logs.unwrap();
+        assert!(logs
+            .iter()
+            .any(|log| log.timestamp == timestamp("09:35") && log.temperature == Celsius(-89.5)));
+
+        let configs = sensor.configs.unwrap();
+        let cold_config = configs
+            .iter()
+            .find(|config| config.breach_type == BreachType::ColdConsecutive)
+            .unwrap();
+        assert_eq!(cold_config.minimum_temperature, Celsius(-85.0));
+        let hot_config = configs
+            .iter()
+            .find(|config| config.breach_type == BreachType::HotConsecutive)
+            .unwrap();
+        assert_eq!(hot_config.maximum_temperature, Celsius(-60.0));
+
+        // recorded cold breach (FridgeTags record the daily cumulative time)
+        let breaches = sensor.breaches.unwrap();
+        assert_eq!(breaches.len(), 1);
+        assert_eq!(breaches[0].breach_type, BreachType::ColdCumulative);
+        assert_eq!(breaches[0].duration, Duration::minutes(75));
+        assert_eq!(breaches[0].start_timestamp, timestamp("09:00"));
+        assert_eq!(breaches[0].end_timestamp, timestamp("10:15"));
+        assert_eq!(breaches[0].peak_temperature, Some(Celsius(-89.5)));
+
+        // and detected as a consecutive breach from the logs (-78.0 isn't above -60)
+        let detected = detect_consecutive_breaches(&logs, &configs);
+        assert_eq!(detected.len(), 1);
+        assert_eq!(detected[0].breach_type, BreachType::ColdConsecutive);
+        assert_eq!(detected[0].start_timestamp, timestamp("09:00"));
+        assert_eq!(detected[0].end_timestamp, timestamp("10:15"));
+    }
+
+    #[test]
+    fn test_write_sensor_to_txt() {
+        let sensor = crate::sample_sensor();
+        let txt = write_sensor_to_txt(&sensor);
+        assert_eq!(detect_sensor_model(&txt), None); // sample sensor isn't a real model
+
+        let read_back = read_sensor_from_reader(txt.as_bytes(), &ParseOptions::default()).unwrap();
+        assert_eq!(format!("{:?}", read_back), format!("{:?}", sensor));
+
+        // and written again identically
+        assert_eq!(write_sensor_to_txt(&read_back), txt);
+
+        // read from file == write then read (apart from where and when it was read), for a
+        // real Q-tag file
+        let sensor = without_origin(
+            read_sensor_from_file(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/data/QTag CLm doc LR/RCPJ00010_03_202304201359.txt"
+            ))
+            .unwrap(),
+        );
+        let txt = write_sensor_to_txt(&sensor);
+        let read_back = read_sensor_from_reader(txt.as_bytes(), &ParseOptions::default()).unwrap();
+        assert_eq!(format!("{:?}", read_back), format!("{:?}", sensor));
+    }
+
+    #[test]
+    fn test_active_logs() {
+        let timestamp =
+            |timestamp: &str| NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M").unwrap();
+
+        let mut sensor = read_sensor_from_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/test/pre_activation_logs.txt"
+        ))
+        .unwrap();
+        assert_eq!(
+            sensor.activation_timestamp,
+            Some(timestamp("2023-05-01 09:00"))
+        );
+        assert_eq!(sensor.stop_timestamp, None);
+        // the 3 data rows before activation are left out
+        let active_logs = sensor.active_logs();
+        assert_eq!(active_logs.len(), sensor.log_count() - 3);
+        assert!(active_logs
+            .iter()
+            .all(|log| log.timestamp >= timestamp("2023-05-01 09:00")));
+
+        sensor.stop_timestamp = Some(timestamp("2023-05-01 09:10"));
+        assert!(sensor.active_logs().iter().all(|log| {
+            log.timestamp >= timestamp("2023-05-01 09:00")
+                && log.timestamp <= timestamp("2023-05-01 09:10")
+        }));
+        sensor.activation_timestamp = None;
+        sensor.stop_timestamp = None;
+        assert_eq!(sensor.active_logs().len(), sensor.log_count());
+
+        let sensor = read_sensor_from_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/QTag CLm doc LR/RCPJ00010_03_202304201359.txt"
+        ))
+        .unwrap();
+        assert_eq!(
+            sensor.activation_timestamp,
+            Some(timestamp("2023-04-17 15:23"))
+        );
+        assert_eq!(sensor.stop_timestamp, Some(timestamp("2023-04-20 13:59")));
+        assert_eq!(sensor.active_logs().len(), sensor.log_count());
+    }
+
+    #[test]
+    fn test_battery_low() {
+        let sensor = read_sensor_from_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/test/qtag_low_battery.txt"
+        ))
+        .unwrap();
+        assert_eq!(sensor.battery_low, Some(true));
+        assert_eq!(sensor.summary().battery_low, Some(true));
+        let read_back = read_sensor_from_reader(
+            write_sensor_to_txt(&sensor).as_bytes(),
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(read_back.battery_low, Some(true));
+
+        // blank battery timestamps on a Q-tag, and none reported by Fridge-tags
+        let sensor = read_sensor_from_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/QTag CLm doc LR/RCPJ00010_02_202211090128.txt"
+        ))
+        .unwrap();
+        assert_eq!(sensor.battery_low, Some(false));
+        let sensor = read_sensor_from_file(FRIDGETAG_2_TXT).unwrap();
+        assert_eq!(sensor.battery_low, None);
+        assert_eq!(sensor.summary().battery_low, None);
+    }
+
+    #[test]
+    fn test_timezone() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/data/test/qtag_timezone.txt");
+        let india = FixedOffset::east_opt(5 * 3600 + 30 * 60).unwrap();
+        let day = |day| NaiveDate::from_ymd_opt(2023, 5, day).unwrap();
+        let breach_times = |sensor: &Sensor| {
+            sensor
+                .breaches
+                .iter()
+                .flatten()
+                .map(|breach| {
+                    (
+                        breach.start_timestamp.format("%H:%M").to_string(),
+                        breach.end_timestamp.format("%H:%M").to_string(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // the 17:00-20:00 UTC breach is 22:30-01:30 in India, so is split at midnight
+        let sensor = read_sensor_from_file(path).unwrap();
+        assert_eq!(sensor.timezone, Some(india));
+        assert_eq!(
+            breach_times(&sensor),
+            vec![
+                ("22:30".to_string(), "00:00".to_string()),
+                ("00:00".to_string(), "01:30".to_string())
+            ]
+        );
+        let breaches_by_day = sensor.breaches_by_day();
+        assert_eq!(
+            breaches_by_day.keys().copied().collect::<Vec<_>>(),
+            vec![day(1), day(2)]
+        );
+        let read_back = read_sensor_from_reader(
+            write_sensor_to_txt(&sensor).as_bytes(),
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(read_back.timezone, Some(india));
+        assert_eq!(breach_times(&read_back), breach_times(&sensor));
+
+        // the zone in the file overrides the caller's default
+        let utc_options = ParseOptions {
+            timezone: FixedOffset::east_opt(0),
+            ..Default::default()
+        };
+        let sensor = read_sensor_from_file_with_options(path, &utc_options).unwrap();
+        assert_eq!(sensor.timezone, Some(india));
+        assert_eq!(breach_times(&sensor).len(), 2);
+
+        // without a zone in the file, the timestamps are already local time, so are left as
+        // recorded, with the caller's default (if any) as the zone they're in
+        let txt = fs::read_to_string(path)
+            .unwrap()
+            .replace("Time Zone: +05:30\n", "");
+        let sensor = read_sensor_from_reader(txt.as_bytes(), &ParseOptions::default()).unwrap();
+        assert_eq!(sensor.timezone, None);
+        assert_eq!(
+            breach_times(&sensor),
+            vec![("17:00".to_string(), "20:00".to_string())]
+        );
+        assert_eq!(
+            sensor.breaches_by_day().keys().copied().collect::<Vec<_>>(),
+            vec![day(1)]
+        );
+        let india_options = ParseOptions {
+            timezone: Some(india),
+            ..Default::default()
+        };
+        let sensor = read_sensor_from_reader(txt.as_bytes(), &india_options).unwrap();
+        assert_eq!(sensor.timezone, Some(india));
+        assert_eq!(
+            breach_times(&sensor),
+            vec![("17:00".to_string(), "20:00".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_fridgetag_timezone_option() {
+        // Fridge-tag dates and times are device-local, so the caller's zone doesn't move them
+        let india = FixedOffset::east_opt(5 * 3600 + 30 * 60).unwrap();
+        let options = ParseOptions {
+            timezone: Some(india),
+            ..Default::default()
+        };
+        let local = read_sensor_from_file(FRIDGETAG_2_TXT).unwrap();
+        let sensor = read_sensor_from_file_with_options(FRIDGETAG_2_TXT, &options).unwrap();
+        assert_eq!(local.timezone, None);
+        assert_eq!(sensor.timezone, Some(india));
+        assert_eq!(format!("{:?}", sensor.logs), format!("{:?}", local.logs));
+        assert_eq!(
+            format!("{:?}", sensor.breaches),
+            format!("{:?}", local.breaches)
+        );
+
+        // breaches over a whole day (from the daily summary) still run midnight to midnight
+        let whole_day_breaches: Vec<&TemperatureBreach> = sensor
+            .breaches
+            .iter()
+            .flatten()
+            .filter(|breach| breach.duration == Duration::days(1))
+            .collect();
+        assert!(!whole_day_breaches.is_empty());
+        for breach in whole_day_breaches {
+            assert_eq!(breach.start_timestamp.time(), NaiveTime::MIN);
+            assert_eq!(
+                breach.end_timestamp,
+                breach.start_timestamp + Duration::days(1)
+            );
+        }
+    }
+
+    #[test]
+    fn test_to_utc() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/data/test/qtag_timezone.txt");
+        let timestamp =
+            |timestamp: &str| NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M").unwrap();
+
+        let sensor = read_sensor_from_file(path).unwrap();
+        assert_eq!(
+            sensor.activation_timestamp,
+            Some(timestamp("2023-05-01 21:30"))
+        );
+        let sensor = sensor.to_utc();
+        assert_eq!(sensor.timezone, None);
+        assert_eq!(
+            sensor.activation_timestamp,
+            Some(timestamp("2023-05-01 16:00"))
+        );
+        assert_eq!(sensor.stop_timestamp, Some(timestamp("2023-05-01 21:00")));
+        assert_eq!(
+            sensor.logs.as_ref().unwrap()[0].timestamp,
+            timestamp("2023-05-01 16:00")
+        );
+        let breach_times: Vec<(NaiveDateTime, NaiveDateTime, Option<NaiveDateTime>)> = sensor
+            .breaches
+            .iter()
+            .flatten()
+            .map(|breach| {
+                (
+                    breach.start_timestamp,
+                    breach.end_timestamp,
+                    breach.trigger_timestamp,
+                )
+            })
+            .collect();
+        assert_eq!(
+            breach_times,
+            vec![
+                (
+                    timestamp("2023-05-01 17:00"),
+                    timestamp("2023-05-01 18:30"),
+                    Some(timestamp("2023-05-01 18:00"))
+                ),
+                (
+                    timestamp("2023-05-01 18:30"),
+                    timestamp("2023-05-01 20:00"),
+                    Some(timestamp("2023-05-01 18:00"))
+                )
+            ]
+        );
+
+        // unchanged without a timezone
+        let utc_sensor = sensor.clone().to_utc();
+        assert_eq!(format!("{:?}", utc_sensor), format!("{:?}", sensor));
+    }
+
+    #[test]
+    fn test_no_breaches() {
+        let txt = "Device: Q-tag CLm doc LR
+Conf:
+ Serial: QTAG00011
+ Logging Interval: 10
+ Alarm:
+  1:
+   Type: 2, T AL: 8.0, t AL: 30
+Res:
+ TS Start: 2023-05-01 09:00
+ TS Stop: 2023-05-01 10:00
+ Alarm:
+Data:
+	yyyy-MM-dd hh:mm	T
+	2023-05-01 09:00	4.1
+	2023-05-01 09:10	5.0
+";
+        // a clean trip on a model which records alarms
+        let sensor = read_sensor_from_reader(txt.as_bytes(), &ParseOptions::default()).unwrap();
+        assert!(sensor.configs.is_some());
+        assert!(sensor.breaches.unwrap().is_empty());
+
+        // an unknown device, which mightn't record alarms at all
+        let unknown_txt = txt.replace("Q-tag CLm doc LR", "Acme Logger 3000");
+        let sensor =
+            read_sensor_from_reader(unknown_txt.as_bytes(), &ParseOptions::default()).unwrap();
+        assert!(sensor.breaches.is_none());
+    }
+
+    #[test]
+    fn test_inverted_breaches() {
+        // a consecutive breach ending before it starts, and a cumulative one with a
+        // negative duration
+        let txt = "Device: Q-tag CLm doc LR
+Conf:
+ Serial: QTAG00007
+ Logging Interval: 10
+ Alarm:
+  1:
+   Type: 2, T AL: 8.0, t AL: 30
+  2:
+   Type: 3, T AL: 2.0, t AL: 60
+Res:
+ TS Start: 2023-05-01 09:00
+ TS Stop: 2023-05-01 12:00
+ Alarm:
+  1:
+   TS S: 2023-05-01 10:00, t A: 30, TS A: 2023-05-01 10:30, TS E: 2023-05-01 09:30
+  2:
+   TS S: 2023-05-01 11:00, t A: -30, TS A: 2023-05-01 11:00
+";
+        let sensor = read_sensor_from_reader(txt.as_bytes(), &ParseOptions::default()).unwrap();
+        let breaches = sensor.breaches.unwrap();
+        assert_eq!(breaches.len(), 2);
+        assert!(breaches
+            .iter()
+            .all(|breach| breach.is_valid() && breach.end_timestamp == breach.start_timestamp));
+
+        let inverted_breach = TemperatureBreach {
+            end_timestamp: breaches[0].start_timestamp - Duration::minutes(1),
+            ..breaches[0].clone()
+        };
+        assert!(!inverted_breach.is_valid());
+
+        // e.g. built by the caller, so normalize clamps it without panicking
+        let mut inverted_sensor = Sensor {
+            breaches: Some(vec![inverted_breach]),
+            ..sensor
+        };
+        inverted_sensor.normalize();
+        let breach = &inverted_sensor.breaches.unwrap()[0];
+        assert_eq!(breach.end_timestamp, breach.start_timestamp);
+    }
+
+    #[test]
+    fn test_temperature_scale() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/data/test/qtag_tenths.txt");
+        let temperatures = |sensor: &Sensor| {
+            sensor
+                .logs
+                .iter()
+                .flatten()
+                .map(|log| log.temperature.0)
+                .collect::<Vec<f64>>()
+        };
+        let limits = |sensor: &Sensor| {
+            sensor
+                .configs
+                .iter()
+                .flatten()
+                .map(|config| (config.maximum_temperature.0, config.minimum_temperature.0))
+                .collect::<Vec<(f64, f64)>>()
+        };
+
+        // detected from the Temp Scale field, with only the limit of each config scaled
+        // (the min & max temperatures are logs too)
+        let sensor = read_sensor_from_file(path).unwrap();
+        assert_eq!(temperatures(&sensor), vec![4.1, 8.8, 8.8, 6.3, -0.5, -0.5]);
+        assert_eq!(limits(&sensor), vec![(8.0, -273.0), (100.0, 2.0)]);
+
+        // set by the parse option when the file doesn't say
+        let contents = fs::read_to_string(path).unwrap();
+        let unmarked = contents.replace(" Temp Scale: 10\n", "");
+        let options = ParseOptions {
+            temperature_scale: Some(10.0),
+            ..ParseOptions::default()
+        };
+        let sensor = read_sensor_from_reader(unmarked.as_bytes(), &options).unwrap();
+        assert_eq!(temperatures(&sensor), vec![4.1, 8.8, 8.8, 6.3, -0.5, -0.5]);
+        let sensor =
+            read_sensor_from_reader(unmarked.as_bytes(), &ParseOptions::default()).unwrap();
+        assert_eq!(
+            temperatures(&sensor),
+            vec![41.0, 88.0, 88.0, 63.0, -5.0, -5.0]
+        );
+
+        // the parse option takes precedence over the file
+        let options = ParseOptions {
+            temperature_scale: Some(1.0),
+            ..ParseOptions::default()
+        };
+        let sensor = read_sensor_from_file_with_options(path, &options).unwrap();
+        assert_eq!(
+            temperatures(&sensor),
+            vec![41.0, 88.0, 88.0, 63.0, -5.0, -5.0]
+        );
+
+        // a scale which isn't positive is rejected as an option, and ignored in the file
+        for temperature_scale in [0.0, -10.0, f64::NAN] {
+            let options = ParseOptions {
+                temperature_scale: Some(temperature_scale),
+                ..ParseOptions::default()
+            };
+            assert!(matches!(
+                read_sensor_from_file_with_options(path, &options),
+                Err(TemperatureSensorError::InvalidOption(_))
+            ));
+        }
+        let zero_scale = contents.replace(" Temp Scale: 10\n", " Temp Scale: 0\n");
+        let sensor =
+            read_sensor_from_reader(zero_scale.as_bytes(), &ParseOptions::default()).unwrap();
+        assert_eq!(
+            temperatures(&sensor),
+            vec![41.0, 88.0, 88.0, 63.0, -5.0, -5.0]
+        );
+    }
+
+    #[test]
+    fn test_log_interval_unit() {
+        let minutes_path = concat!(env!("CARGO_MANIFEST_DIR"), "/data/test/qtag_timezone.txt");
+        let seconds_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/test/qtag_interval_seconds.txt"
+        );
+
+        // "60" and "3600 s" are the same interval
+        let in_minutes = read_sensor_from_file(minutes_path).unwrap();
+        let in_seconds = read_sensor_from_file(seconds_path).unwrap();
+        assert_eq!(in_minutes.log_interval, Some(Duration::minutes(60)));
+        assert_eq!(in_seconds.log_interval, in_minutes.log_interval);
+
+        // a unit stated in the file takes precedence over the parse option
+        let options = ParseOptions {
+            log_interval_unit: IntervalUnit::Seconds,
+            ..ParseOptions::default()
+        };
+        let sensor = read_sensor_from_file_with_options(seconds_path, &options).unwrap();
+        assert_eq!(sensor.log_interval, Some(Duration::minutes(60)));
+
+        // which only applies to intervals without one
+        let contents = fs::read_to_string(seconds_path).unwrap();
+        let unmarked = contents.replace("3600 s", "3600");
+        let sensor = read_sensor_from_reader(unmarked.as_bytes(), &options).unwrap();
+        assert_eq!(sensor.log_interval, Some(Duration::minutes(60)));
+        let sensor =
+            read_sensor_from_reader(unmarked.as_bytes(), &ParseOptions::default()).unwrap();
+        assert_eq!(sensor.log_interval, Some(Duration::minutes(3600)));
+
+        // sub-minute intervals are written in seconds
+        let contents = contents.replace("3600 s", "30 s");
+        let sensor = read_sensor_from_reader(contents.as_bytes(), &options).unwrap();
+        assert_eq!(sensor.log_interval, Some(Duration::seconds(30)));
+        assert!(write_sensor_to_txt(&sensor).contains(" Logging Interval: 30 s\n"));
+
+        // an unknown unit is a parse error
+        let contents = fs::read_to_string(minutes_path)
+            .unwrap()
+            .replace("Logging Interval: 60", "Logging Interval: 1 h");
+        assert!(matches!(
+            read_sensor_from_reader(contents.as_bytes(), &ParseOptions::default()),
+            Err(TemperatureSensorError::ParseError { line: 8, .. })
+        ));
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let sensor = read_sensor_from_file(FRIDGETAG_2_TXT).unwrap();
+        let hash = sensor.content_hash();
+        assert_eq!(
+            read_sensor_from_file(FRIDGETAG_2_TXT)
+                .unwrap()
+                .content_hash(),
+            hash
+        );
+
+        // independent of the order of the logs & breaches
+        let mut reordered = sensor.clone();
+        reordered.logs.as_mut().unwrap().reverse();
+        reordered.breaches.as_mut().unwrap().reverse();
+        assert_eq!(reordered.content_hash(), hash);
+
+        let mut changed = sensor.clone();
+        changed.logs.as_mut().unwrap()[0].temperature.0 += 0.1;
+        assert_ne!(changed.content_hash(), hash);
+        let mut changed = sensor.clone();
+        changed.breaches.as_mut().unwrap()[0].acknowledged = true;
+        assert_ne!(changed.content_hash(), hash);
+        let mut changed = sensor;
+        changed.serial.push('0');
+        assert_ne!(changed.content_hash(), hash);
+    }
+
+    #[test]
+    fn test_decimal_comma() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/test/qtag_decimal_comma.txt"
+        );
+        let temperatures = |sensor: &Sensor| {
+            sensor
+                .logs
+                .iter()
+                .flatten()
+                .map(|log| log.temperature.0)
+                .collect::<Vec<f64>>()
+        };
+        let limits = |sensor: &Sensor| {
+            sensor
+                .configs
+                .iter()
+                .flatten()
+                .map(|config| (config.maximum_temperature.0, config.minimum_temperature.0))
+                .collect::<Vec<(f64, f64)>>()
+        };
+
+        // detected from the file
+        let sensor = read_sensor_from_file(path).unwrap();
+        assert_eq!(temperatures(&sensor), vec![4.1, 8.8, 8.8, 6.3, -0.5, -0.5]);
+        assert_eq!(limits(&sensor), vec![(8.5, -273.0), (100.0, 2.5)]);
+
+        // set by the parse option
+        let options = ParseOptions {
+            decimal_comma: Some(true),
+            ..ParseOptions::default()
+        };
+        let sensor = read_sensor_from_file_with_options(path, &options).unwrap();
+        assert_eq!(temperatures(&sensor), vec![4.1, 8.8, 8.8, 6.3, -0.5, -0.5]);
+
+        // decimal points are unaffected, and comma decimals rejected if switched off
+        let points_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/test/qtag_low_battery.txt"
+        );
+        assert_eq!(
+            temperatures(&read_sensor_from_file_with_options(points_path, &options).unwrap()),
+            temperatures(&read_sensor_from_file(points_path).unwrap())
+        );
+        let options = ParseOptions {
+            decimal_comma: Some(false),
+            ..ParseOptions::default()
+        };
+        assert!(matches!(
+            read_sensor_from_file_with_options(path, &options),
+            Err(TemperatureSensorError::ParseError { field, .. }) if field == "T AL"
+        ));
+    }
+
+    #[test]
+    fn test_skip_self_test() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/data/test/self_test_spike.txt");
+        let timestamp = |text: &str| NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap();
+        // hot for 20 minutes, which the 25 minute startup spike would breach
+        let hot_config = TemperatureBreachConfig {
+            breach_type: BreachType::HotConsecutive,
+            maximum_temperature: Celsius(8.0),
+            minimum_temperature: Celsius(-273.0),
+            duration: Duration::minutes(20),
+        };
+        let options = ParseOptions {
+            override_configs: Some(vec![hot_config]),
+            ..ParseOptions::default()
+        };
+        assert!(options.skip_self_test);
+
+        let sensor = read_sensor_from_file_with_options(path, &options).unwrap();
+        let self_test_logs = sensor.self_test_logs();
+        assert_eq!(self_test_logs.len(), 6);
+        assert!(self_test_logs
+            .iter()
+            .all(|log| log.timestamp < timestamp("2023-05-01 09:00")));
+        // the logs are kept, but there's no breach for the spike
+        assert_eq!(
+            sensor.log_count(),
+            read_sensor_from_file(path).unwrap().log_count()
+        );
+        assert_eq!(sensor.breaches.unwrap().len(), 0);
+
+        let options = ParseOptions {
+            skip_self_test: false,
+            ..options
+        };
+        let sensor = read_sensor_from_file_with_options(path, &options).unwrap();
+        let breaches = sensor.breaches.unwrap();
+        assert_eq!(breaches.len(), 1);
+        assert_eq!(breaches[0].start_timestamp, timestamp("2023-05-01 08:30"));
+        assert_eq!(breaches[0].end_timestamp, timestamp("2023-05-01 08:55"));
+
+        // recorded breaches within the self-test are dropped too
+        let mut sensor = Sensor {
+            breaches: Some(breaches),
+            ..sensor
+        };
+        sensor.drop_self_test_breaches();
+        assert!(sensor.breaches.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_exclude_inactive_logs() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/test/pre_activation_logs.txt"
+        );
+        let original = read_sensor_from_file(path).unwrap();
+        assert!(original.excluded_logs.is_none());
+        let options = ParseOptions {
+            exclude_inactive_logs: true,
+            ..Default::default()
+        };
+        let sensor = read_sensor_from_file_with_options(path, &options).unwrap();
+
+        // the active and excluded logs partition the original logs
+        let active_logs = original.active_logs();
+        let excluded_logs = sensor.excluded_logs.clone().unwrap();
+        assert_eq!(sensor.log_count(), active_logs.len());
+        assert_eq!(
+            sensor.log_count() + excluded_logs.len(),
+            original.log_count()
+        );
+        let activation = sensor.activation_timestamp.unwrap();
+        assert!(excluded_logs.iter().all(|log| log.timestamp < activation));
+        assert!(sensor
+            .logs
+            .iter()
+            .flatten()
+            .zip(active_logs)
+            .all(|(log, active)| log.timestamp == active.timestamp
+                && log.temperature == active.temperature));
+
+        // excluding again doesn't lose the logs already excluded
+        let mut sensor = sensor;
+        sensor.exclude_inactive_logs();
+        assert_eq!(sensor.excluded_logs.unwrap().len(), excluded_logs.len());
+    }
+
+    #[test]
+    fn test_sub_second_timestamps() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/test/sub_second_timestamps.txt"
+        );
+        let temperatures = |sensor: &Sensor| {
+            sensor
+                .logs
+                .iter()
+                .flatten()
+                .map(|log| log.temperature.0)
+                .collect::<Vec<f64>>()
+        };
+        let start = NaiveDateTime::parse_from_str("2023-05-01 09:00", "%Y-%m-%d %H:%M").unwrap();
+
+        // the logs are ordered by their fractional timestamps
+        let sensor = read_sensor_from_file(path).unwrap();
+        assert_eq!(temperatures(&sensor), vec![4.1, 4.3, 4.4, 4.5, 4.6]);
+        assert_eq!(
+            sensor.logs.as_ref().unwrap()[0].timestamp,
+            start + Duration::milliseconds(250)
+        );
+        assert_eq!(
+            sensor.activation_timestamp,
+            Some(start + Duration::milliseconds(125))
+        );
+        assert_eq!(
+            sensor.stop_timestamp,
+            Some(start + Duration::milliseconds(2500))
+        );
+
+        // truncated timestamps keep the file order when they share a second
+        let options = ParseOptions {
+            truncate_to_seconds: true,
+            ..ParseOptions::default()
+        };
+        let sensor = read_sensor_from_file_with_options(path, &options).unwrap();
+        assert_eq!(temperatures(&sensor), vec![4.3, 4.1, 4.5, 4.4, 4.6]);
+        assert_eq!(sensor.logs.as_ref().unwrap()[0].timestamp, start);
+        assert_eq!(sensor.stop_timestamp, Some(start + Duration::seconds(2)));
+    }
+
+    #[test]
+    fn test_drive_diagnostic() {
+        let dir = test_dir("drive_diagnostic");
+        let path = dir.to_str().unwrap();
+
+        let diagnostic = drive_diagnostic(path, Some(4 * 1024 * 1024 * 1024));
+        assert!(diagnostic.within_size_limit);
+        assert!(diagnostic.sensor_files.is_empty());
+
+        fs::copy(FRIDGETAG_2_TXT, dir.join("130400191542_202302140605.txt")).unwrap();
+        fs::copy(FRIDGETAG_2_PDF, dir.join("130400191542_202302140605.pdf")).unwrap();
+        fs::write(dir.join("notes.txt"), "not a sensor file").unwrap();
+        let diagnostic = drive_diagnostic(path, Some(16 * 1024 * 1024 * 1024));
+        assert_eq!(diagnostic.path, path);
+        assert!(!diagnostic.within_size_limit);
+        assert_eq!(diagnostic.sensor_files.len(), 2);
+        assert!(diagnostic.sensor_files[0].ends_with("130400191542_202302140605.txt"));
+        assert!(diagnostic.sensor_files[1].ends_with("130400191542_202302140605.pdf"));
+
+        assert!(drive_diagnostic(path, None).within_size_limit);
+    }
+
+    #[test]
+    fn test_breach_trigger_timestamp() {
+        let timestamp =
+            |timestamp: &str| NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M").unwrap();
+
+        // Fridge-tag breaches start the config duration before the trigger time, but
+        // not before midnight
+        let sensor = read_sensor_from_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/FridgeTag 2L/130500109088_202206081014.txt"
+        ))
+        .unwrap();
+        let breaches = sensor.breaches.unwrap();
+        let breach = breaches
+            .iter()
+            .find(|breach| breach.start_timestamp.date() == timestamp("2022-06-07 00:00").date())
+            .unwrap();
+        assert_eq!(
+            breach.trigger_timestamp,
+            Some(timestamp("2022-06-07 14:55"))
+        );
+        assert_eq!(breach.start_timestamp, timestamp("2022-06-07 13:55"));
+        for breach in &breaches {
+            let trigger = breach.trigger_timestamp.unwrap();
+            let midnight = NaiveDateTime::new(trigger.date(), NaiveTime::MIN);
+            assert_eq!(
+                breach.start_timestamp,
+                (trigger - Duration::minutes(60)).max(midnight)
+            );
+        }
+
+        let sensor = read_sensor_from_file(FRIDGETAG_2_TXT).unwrap();
+        let breach = &sensor.breaches.unwrap()[0];
+        let trigger = breach.trigger_timestamp.unwrap();
+        assert_eq!(trigger.time(), NaiveTime::MIN);
+        assert_eq!(breach.start_timestamp, trigger); // clamped to midnight
+
+        // Q-tags record the trigger time too
+        let sensor = read_sensor_from_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/QTag CLm doc LR/RCPJ00010_03_202304201359.txt"
+        ))
+        .unwrap();
+        let breach = sensor
+            .breaches
+            .unwrap()
+            .into_iter()
+            .find(|breach| breach.start_timestamp == timestamp("2023-04-17 15:33"))
+            .unwrap();
+        assert_eq!(
+            breach.trigger_timestamp,
+            Some(timestamp("2023-04-17 16:03"))
+        );
     }
 }
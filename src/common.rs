@@ -1,4 +1,141 @@
-use chrono::{Duration, NaiveDateTime};
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use chrono::{Duration, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use core::cmp::Reverse;
+use core::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "serde")]
+/// Serializes durations as a whole number of seconds.
+mod duration_seconds {
+    use chrono::Duration;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(duration.num_seconds())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        Ok(Duration::seconds(i64::deserialize(deserializer)?))
+    }
+}
+
+#[cfg(feature = "serde")]
+/// Serializes optional durations as a whole number of seconds.
+mod optional_duration_seconds {
+    use chrono::Duration;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match duration {
+            Some(duration) => serializer.serialize_some(&duration.num_seconds()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Ok(Option::<i64>::deserialize(deserializer)?.map(Duration::seconds))
+    }
+}
+
+#[cfg(feature = "serde")]
+/// Serializes optional UTC offsets as a whole number of seconds east of UTC.
+mod optional_offset_seconds {
+    use chrono::FixedOffset;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        offset: &Option<FixedOffset>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match offset {
+            Some(offset) => serializer.serialize_some(&offset.local_minus_utc()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<FixedOffset>, D::Error> {
+        Option::<i32>::deserialize(deserializer)?
+            .map(|seconds| {
+                FixedOffset::east_opt(seconds)
+                    .ok_or_else(|| D::Error::custom("UTC offset out of range"))
+            })
+            .transpose()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Define the errors returned when reading sensor data.
+pub enum TemperatureSensorError {
+    NoSensorsFound,
+    SensorNotFound(String), // serial
+    FileNotFound(String),   // file path
+    FileNotCreated(String), // file path
+    InvalidArchive(String), // why the archive couldn't be read
+    InvalidPdf(String),     // why the PDF report couldn't be read
+    EncryptedPdf,           // the PDF report is encrypted (password protected), so can't be read
+    InvalidOption(String),  // which parse option is invalid, and why
+    ParseError {
+        line: usize,     // 1-based line number in the sensor file (0 if not a particular line)
+        content: String, // raw content of the line (or what went wrong, if not a particular line)
+        field: String,   // name of the field which failed to parse (e.g. Device for no header)
+    },
+}
+
+impl fmt::Display for TemperatureSensorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TemperatureSensorError::NoSensorsFound => write!(f, "No sensors found"),
+            TemperatureSensorError::SensorNotFound(serial) => {
+                write!(f, "Sensor not found: {}", serial)
+            }
+            TemperatureSensorError::FileNotFound(file_path) => {
+                write!(f, "Sensor file not found: {}", file_path)
+            }
+            TemperatureSensorError::FileNotCreated(file_path) => {
+                write!(f, "Sensor file not created: {}", file_path)
+            }
+            TemperatureSensorError::InvalidArchive(reason) => {
+                write!(f, "Invalid sensor archive: {}", reason)
+            }
+            TemperatureSensorError::InvalidPdf(reason) => {
+                write!(f, "Invalid sensor PDF: {}", reason)
+            }
+            TemperatureSensorError::EncryptedPdf => write!(f, "Sensor PDF is encrypted"),
+            TemperatureSensorError::InvalidOption(reason) => {
+                write!(f, "Invalid parse option: {}", reason)
+            }
+            TemperatureSensorError::ParseError {
+                line,
+                content,
+                field,
+            } => {
+                write!(f, "Invalid {}", field)?;
+                if *line > 0 {
+                    write!(f, " at line {}", line)?;
+                }
+                if !content.trim().is_empty() {
+                    write!(f, ": {}", content.trim())?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl core::error::Error for TemperatureSensorError {}
 
 #[derive(Debug, Clone)]
 /// Define the types encountered when parsing a sensor txt file (e.g. Berlinger).
@@ -11,7 +148,8 @@ pub enum SensorFieldType {
     Timestamp(NaiveDateTime),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 /// Define the four types of breach.
 pub enum BreachType {
     HotConsecutive,
@@ -20,47 +158,2387 @@ pub enum BreachType {
     ColdCumulative,
 }
 
-#[derive(Debug, Clone)]
-/// Define the sensor types supported.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Berlinger device model, as named on the "Device:" line at the top of the txt file.
+pub enum BerlingerModel {
+    FridgeTag2,
+    FridgeTag2L,
+    FridgeTagUL,
+    QtagCLm,
+}
+
+/// Define what a Berlinger model records (see BerlingerModel::capabilities).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModelCapabilities {
+    pub has_logs: bool, // a log every logging interval, rather than only the daily min/max
+    pub records_consecutive: bool, // consecutive (single event) breaches
+    pub records_cumulative: bool, // cumulative (accumulated) breaches
+    pub max_configs: usize, // most alarm configs the sensor can be set up with
+}
+
+impl BerlingerModel {
+    /// All the supported models.
+    pub const ALL: [BerlingerModel; 4] = [
+        BerlingerModel::FridgeTag2,
+        BerlingerModel::FridgeTag2L,
+        BerlingerModel::FridgeTagUL,
+        BerlingerModel::QtagCLm,
+    ];
+
+    /// Returns what the model records, e.g. to decide which columns to show before
+    /// parsing a file (see berlinger::detect_sensor_model). Fridge-tags have a cold & a
+    /// hot alarm, each counting the total time out of range per day, and only the 2 L &
+    /// Ultra Low log the temperature over the day.
+    pub fn capabilities(&self) -> ModelCapabilities {
+        match self {
+            BerlingerModel::FridgeTag2 => ModelCapabilities {
+                has_logs: false,
+                records_consecutive: false,
+                records_cumulative: true,
+                max_configs: 2,
+            },
+            BerlingerModel::FridgeTag2L | BerlingerModel::FridgeTagUL => ModelCapabilities {
+                has_logs: true,
+                records_consecutive: false,
+                records_cumulative: true,
+                max_configs: 2,
+            },
+            BerlingerModel::QtagCLm => ModelCapabilities {
+                has_logs: true,
+                records_consecutive: true,
+                records_cumulative: true,
+                max_configs: 5,
+            },
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Define the sensor types supported, with the model where known.
 pub enum SensorType {
-    Berlinger, // only Berlinger so far
+    Berlinger(Option<BerlingerModel>), // None if the device isn't a supported model
+}
+
+impl Default for SensorType {
+    fn default() -> Self {
+        SensorType::Berlinger(None)
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+/// A temperature in degrees Celsius, so thresholds in other units can't be mixed in by accident.
+pub struct Celsius(pub f64);
+
+impl Celsius {
+    pub fn to_fahrenheit(self) -> f64 {
+        self.0 * 9.0 / 5.0 + 32.0
+    }
+
+    #[cfg(feature = "std")]
+    /// Rounds to the specified number of decimal places, with ties going to the even
+    /// digit (banker's rounding) so that rounding doesn't bias the readings upwards.
+    pub fn round_to(self, decimal_places: u8) -> Celsius {
+        let scale = 10f64.powi(decimal_places as i32);
+        Celsius((self.0 * scale).round_ties_even() / scale)
+    }
+}
+
+impl From<f64> for Celsius {
+    fn from(degrees: f64) -> Self {
+        Celsius(degrees)
+    }
 }
 
+impl fmt::Display for Celsius {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}°C", self.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Define the units the logging interval can be stated in.
+pub enum IntervalUnit {
+    Seconds,
+    #[default]
+    Minutes, // as in the sensor files
+}
+
+impl IntervalUnit {
+    pub fn duration(self, amount: i64) -> Duration {
+        match self {
+            IntervalUnit::Seconds => Duration::seconds(amount),
+            IntervalUnit::Minutes => Duration::minutes(amount),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Options applied when parsing a sensor file (the default leaves the data as recorded,
+/// apart from skipping the self-test, see skip_self_test).
+pub struct ParseOptions {
+    pub round_temperatures_to: Option<u8>, // decimal places for log temperatures and config thresholds
+    pub truncate_to_seconds: bool,         // drop any fractions of a second from the timestamps
+    pub exclude_inactive_logs: bool,       // move logs outside the trip to excluded_logs
+    /// Replaces the configs recorded on the sensor (e.g. if it was deployed with the wrong
+    /// thresholds). The recorded breaches are then discarded in favour of consecutive
+    /// breaches recomputed from the logs (see detect_consecutive_breaches).
+    pub override_configs: Option<Vec<TemperatureBreachConfig>>,
+    /// Minimum number of breaching logs for a breach recomputed with override_configs (see
+    /// detect_consecutive_breaches_with_min_samples). Defaults to 1, i.e. only the config
+    /// duration is checked.
+    pub min_breach_samples: usize,
+    /// Turns cumulative breaches into consecutive ones when the logs show they were
+    /// continuous (see Sensor::reclassify_continuous_cumulative_breaches).
+    pub reclassify_continuous_cumulative: bool,
+    /// Raw temperature units per degree (e.g. 10 for firmware which records 8.8°C as 88),
+    /// for files which don't say so themselves (see berlinger::read_sensor_from_file).
+    /// Reading fails with an InvalidOption error unless it's positive.
+    pub temperature_scale: Option<f64>,
+    /// Device timezone for files which don't record the zone themselves (see
+    /// Sensor::timezone). Their timestamps are already local, so are left as recorded.
+    pub timezone: Option<FixedOffset>,
+    /// Whether temperatures use a decimal comma (e.g. 8,8 in some European exports), or
+    /// None to detect this from the file.
+    pub decimal_comma: Option<bool>,
+    /// Reads a file whose last line can't be parsed (e.g. cut off when the drive was pulled
+    /// out) up to that line, with a Truncated warning, rather than failing.
+    pub lenient: bool,
+    /// Moves breach starts and ends to the logs within them (see
+    /// Sensor::snap_breach_endpoints_to_logs), to line up with the plotted logs.
+    pub snap_breach_endpoints_to_logs: bool,
+    /// Leaves the logs recorded during the self-test before the sensor was activated (see
+    /// Sensor::self_test_logs) out of breach detection, and drops any breaches within it.
+    /// On by default.
+    pub skip_self_test: bool,
+    /// Unit of a logging interval stated without one (e.g. "Logging Interval: 300" from
+    /// firmware counting in seconds). A unit stated in the file (e.g. "300 s" or
+    /// "5 min") takes precedence.
+    pub log_interval_unit: IntervalUnit,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            round_temperatures_to: None,
+            truncate_to_seconds: false,
+            exclude_inactive_logs: false,
+            override_configs: None,
+            min_breach_samples: 1,
+            reclassify_continuous_cumulative: false,
+            temperature_scale: None,
+            timezone: None,
+            decimal_comma: None,
+            lenient: false,
+            snap_breach_endpoints_to_logs: false,
+            skip_self_test: true,
+            log_interval_unit: IntervalUnit::Minutes,
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone)]
 /// Define the structure used to capture a temperature log.
 pub struct TemperatureLog {
-    pub temperature: f64,
+    pub temperature: Celsius,
     pub timestamp: NaiveDateTime,
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone)]
 /// Define the structure used to capture a breach config.
 pub struct TemperatureBreachConfig {
     pub breach_type: BreachType,
-    pub maximum_temperature: f64, // breach if temperature > maximum_temperature
-    pub minimum_temperature: f64, // breach if temperature < minimum_temperature
-    pub duration: Duration,
+    pub maximum_temperature: Celsius, // hot configs breach if temperature > maximum_temperature
+    pub minimum_temperature: Celsius, // cold configs breach if temperature < minimum_temperature
+    #[cfg_attr(feature = "serde", serde(with = "duration_seconds"))]
+    pub duration: Duration, // alarm delay (see alarm_delay), not the length of any breach
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone)]
 /// Define the structure used to capture a temperature breach.
 pub struct TemperatureBreach {
     pub breach_type: BreachType,
     pub start_timestamp: NaiveDateTime,
     pub end_timestamp: NaiveDateTime,
+    #[cfg_attr(feature = "serde", serde(with = "duration_seconds"))]
     pub duration: Duration, // equals (end_timestamp - start_timestamp) for consecutive breaches, but more for cumulative ones
     pub acknowledged: bool,
+    pub peak_temperature: Option<Celsius>, // max log temperature for hot breaches, min for cold ones
+    pub trigger_timestamp: Option<NaiveDateTime>, // when the sensor raised the alarm, if recorded
+    pub annotation: Option<String>, // note entered by staff (see Sensor::annotate_breach), never parsed
 }
 
-#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default)]
 /// Define the structure used to capture sensor details (incomplete).
+/// The default is an empty Berlinger sensor: blank serial and name, and everything else None.
+/// Sensors returned by the parser are normalized (see Sensor::normalize), so logs are
+/// sorted by timestamp and breaches by start timestamp then breach type. Their breaches
+/// are an empty list if the sensor didn't record any (e.g. a trip without alarms), and
+/// None only if the model can't record breaches.
 pub struct Sensor {
     pub sensor_type: SensorType,
     pub serial: String,
     pub name: String,
     pub last_connected_timestamp: Option<NaiveDateTime>,
+    pub activation_timestamp: Option<NaiveDateTime>, // start of the trip, if recorded
+    pub stop_timestamp: Option<NaiveDateTime>,       // end of the trip, if recorded
+    #[cfg_attr(feature = "serde", serde(with = "optional_duration_seconds"))]
     pub log_interval: Option<Duration>,
     pub breaches: Option<Vec<TemperatureBreach>>,
     pub configs: Option<Vec<TemperatureBreachConfig>>,
     pub logs: Option<Vec<TemperatureLog>>,
+    pub excluded_logs: Option<Vec<TemperatureLog>>, // logs outside the trip, if filtered out
+    pub battery_low: Option<bool>, // None if the sensor doesn't report its battery status
+    /// Device timezone, if known, which the timestamps are in (see Sensor::to_utc). Files
+    /// recording the zone have UTC timestamps, which are converted to this zone when parsed
+    /// so that days (e.g. for cumulative breaches) run from local midnight. Files without a
+    /// zone (e.g. Fridge-tags) have local timestamps, which are left as recorded, with
+    /// ParseOptions::timezone (if any) as their zone.
+    #[cfg_attr(feature = "serde", serde(with = "optional_offset_seconds"))]
+    pub timezone: Option<FixedOffset>,
+    /// Folder a scan found the sensor file in (e.g. the drive root), or None if the sensor
+    /// wasn't found by a scan. Unlike source_path, this is where to look, not the file read.
+    pub source: Option<String>,
+    /// Sensor file the sensor was read from, or None if it wasn't read from a file. Unlike
+    /// source, this is set however the file was found.
+    pub source_path: Option<String>,
+    /// Local time the sensor file was read, if read from a file. This isn't sensor data, so
+    /// it's left out of content_hash (reading the same file twice gives the same hash).
+    pub parsed_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Define the recoverable oddities found when parsing a sensor file, which don't stop
+/// the sensor being read but may be worth showing the user.
+pub enum ParseWarning {
+    MissingLogInterval,
+    MissingPdf,            // no matching PDF file next to the txt file
+    EncryptedPdf,          // the matching PDF file is encrypted, so only the txt file was read
+    UnknownDevice(String), // device name not recognised as a supported model
+    SerialMismatch { txt: String, pdf: String }, // the txt serial is used
+    Truncated { at_line: usize }, // the file was only read up to this (unparseable) last line
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseWarning::MissingLogInterval => write!(f, "Logging interval is missing"),
+            ParseWarning::MissingPdf => write!(f, "No matching PDF file found"),
+            ParseWarning::EncryptedPdf => write!(f, "Matching PDF file is encrypted"),
+            ParseWarning::UnknownDevice(name) => write!(f, "Unknown device: {}", name),
+            ParseWarning::SerialMismatch { txt, pdf } => write!(
+                f,
+                "PDF serial {} doesn't match txt serial {} (using {})",
+                pdf, txt, txt
+            ),
+            ParseWarning::Truncated { at_line } => {
+                write!(f, "File is truncated (read up to line {})", at_line)
+            }
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+/// Define how serious a breach is (see TemperatureBreach::severity).
+pub enum Severity {
+    Minor,
+    Major,
+    Critical,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Define the kind of product a breach config is meant for (see
+/// TemperatureBreachConfig::matches_profile): fridge products (e.g. most vaccines) are kept
+/// between 2°C and 8°C, and frozen ones below -15°C.
+pub enum ProductProfile {
+    Fridge,
+    Freezer,
+}
+
+#[derive(Debug, Clone)]
+/// Define the thresholds at which a breach becomes Major or Critical: how far its peak
+/// temperature goes beyond the config limit (in degrees), or how long it lasts.
+/// The defaults are 2 degrees or 4 hours for Major, and 5 degrees or 24 hours for Critical.
+pub struct SeverityThresholds {
+    pub major_excess: f64,
+    pub critical_excess: f64,
+    pub major_duration: Duration,
+    pub critical_duration: Duration,
+}
+
+impl Default for SeverityThresholds {
+    fn default() -> Self {
+        SeverityThresholds {
+            major_excess: 2.0,
+            critical_excess: 5.0,
+            major_duration: Duration::hours(4),
+            critical_duration: Duration::hours(24),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Define how to work out the temperature at each point of a resampling grid (see
+/// resample_logs).
+pub enum ResampleMethod {
+    Nearest,      // the nearest log, if within half the interval (else no point)
+    Linear,       // interpolated between the logs either side, however far apart
+    CarryForward, // the last log at or before the point (last observation carried forward)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Define which logs to keep when several share a timestamp (see collapse_same_timestamp).
+pub enum ExtremePreference {
+    Min,
+    Max,
+    Both, // the min and the max
+}
+
+#[derive(Debug, Clone)]
+/// Define the structure used to return a sensor together with any parse warnings.
+pub struct ParsedSensor {
+    pub sensor: Sensor,
+    pub warnings: Vec<ParseWarning>,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+/// Define the compact summary of a sensor used for list views (see Sensor::summary),
+/// which doesn't include the logs themselves.
+pub struct SensorSummary {
+    pub serial: String,
+    pub name: String,
+    pub sensor_type: SensorType,
+    pub last_connected_timestamp: Option<NaiveDateTime>,
+    pub log_count: usize,
+    pub breach_count: usize,
+    pub unacknowledged_breach_count: usize,
+    pub min_temperature: Option<Celsius>, // lowest log temperature, if there are logs
+    pub max_temperature: Option<Celsius>, // highest log temperature, if there are logs
+    pub battery_low: Option<bool>,        // a low battery may mean missed logs
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default)]
+/// Define what changed between two reads of the same sensor (see sensor_delta), e.g. for
+/// incremental sync.
+pub struct SensorDelta {
+    pub new_logs: Vec<TemperatureLog>, // logs at timestamps the previous read didn't have
+    pub new_breaches: Vec<TemperatureBreach>, // breaches the previous read didn't have
+    pub newly_acknowledged: Vec<TemperatureBreach>, // breaches which were unacknowledged before
+}
+
+#[cfg(feature = "std")]
+/// Standard normal cumulative distribution function, using the Abramowitz & Stegun
+/// 7.1.26 approximation of erf (accurate to about 1.5e-7).
+fn normal_cdf(x: f64) -> f64 {
+    let z = x.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.3275911 * z);
+    let polynomial = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let erf = 1.0 - polynomial * (-z * z).exp();
+
+    if x >= 0.0 {
+        0.5 * (1.0 + erf)
+    } else {
+        0.5 * (1.0 - erf)
+    }
+}
+
+impl TemperatureLog {
+    /// Returns the configs whose limit this log's temperature is outside of (see
+    /// TemperatureBreachConfig::is_breaching), in the order given. Usually there's at most
+    /// one, but overlapping configs can both be breached.
+    pub fn breaching_configs<'a>(
+        &self,
+        configs: &'a [TemperatureBreachConfig],
+    ) -> Vec<&'a TemperatureBreachConfig> {
+        configs
+            .iter()
+            .filter(|config| config.is_breaching(self.temperature))
+            .collect()
+    }
+}
+
+impl Default for TemperatureBreachConfig {
+    /// A hot consecutive config which is never breached, as its thresholds are the extremes
+    /// supported (-273°C to 100°C), with a zero duration.
+    fn default() -> Self {
+        TemperatureBreachConfig {
+            breach_type: BreachType::HotConsecutive,
+            maximum_temperature: Celsius(100.0),
+            minimum_temperature: Celsius(-273.0),
+            duration: Duration::zero(),
+        }
+    }
+}
+
+impl TemperatureBreachConfig {
+    /// Returns the config's limit: the minimum temperature for cold configs, or the maximum
+    /// temperature for hot configs (see is_breaching).
+    pub fn limit(&self) -> Celsius {
+        match self.breach_type {
+            BreachType::ColdConsecutive | BreachType::ColdCumulative => self.minimum_temperature,
+            BreachType::HotConsecutive | BreachType::HotCumulative => self.maximum_temperature,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    /// As limit, for changing it.
+    pub(crate) fn limit_mut(&mut self) -> &mut Celsius {
+        match self.breach_type {
+            BreachType::ColdConsecutive | BreachType::ColdCumulative => {
+                &mut self.minimum_temperature
+            }
+            BreachType::HotConsecutive | BreachType::HotCumulative => &mut self.maximum_temperature,
+        }
+    }
+
+    /// Returns whether the temperature is outside the config's limit: below the minimum
+    /// temperature for cold configs, or above the maximum temperature for hot configs (the
+    /// other limit is only there to fill the range, e.g. -273 for a hot config). A
+    /// temperature at the limit isn't a breach.
+    pub fn is_breaching(&self, temperature: Celsius) -> bool {
+        match self.breach_type {
+            BreachType::ColdConsecutive | BreachType::ColdCumulative => {
+                temperature < self.minimum_temperature
+            }
+            BreachType::HotConsecutive | BreachType::HotCumulative => {
+                temperature > self.maximum_temperature
+            }
+        }
+    }
+
+    /// Returns the product profile the config's limit is meant for, if any: Fridge for a
+    /// limit from -1°C to 10°C (e.g. hot above 8°C, or cold below 2°C or -0.5°C for
+    /// freezing), and Freezer for a limit of -10°C or below (e.g. hot above -15°C). This can
+    /// flag a sensor deployed with the wrong thresholds, e.g. a freezer sensor with fridge
+    /// ones.
+    pub fn matches_profile(&self) -> Option<ProductProfile> {
+        let limit = self.limit();
+        if limit >= Celsius(-1.0) && limit <= Celsius(10.0) {
+            Some(ProductProfile::Fridge)
+        } else if limit <= Celsius(-10.0) {
+            Some(ProductProfile::Freezer)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the alarm delay, i.e. how long the temperature has to be out of range
+    /// (consecutively or cumulatively, depending on the breach type) before the sensor
+    /// records a breach. This is the config's duration, not how long any breach lasted.
+    pub fn alarm_delay(&self) -> Duration {
+        self.duration
+    }
+}
+
+impl TemperatureBreach {
+    /// Returns a one-line description of the breach for alerts, e.g.
+    /// "Hot breach: 9.2°C for 6 min from 2023-05-23 13:04 to 13:10" (the temperature
+    /// is the peak temperature, and is left out if that isn't known). The duration is
+    /// rounded to the nearest minute (see duration_minutes).
+    pub fn summary(&self) -> String {
+        let description = match self.breach_type {
+            BreachType::HotConsecutive => "Hot breach",
+            BreachType::ColdConsecutive => "Cold breach",
+            BreachType::HotCumulative => "Hot cumulative breach",
+            BreachType::ColdCumulative => "Cold cumulative breach",
+        };
+        let peak_temperature = self
+            .peak_temperature
+            .map(|temperature| format!(": {}", temperature))
+            .unwrap_or_default();
+        let minutes = self.duration_minutes();
+        let duration = if minutes < 60 {
+            format!("{} min", minutes)
+        } else {
+            format!("{} h {} min", minutes / 60, minutes % 60)
+        };
+        let end_format = if self.end_timestamp.date() == self.start_timestamp.date() {
+            "%H:%M"
+        } else {
+            "%Y-%m-%d %H:%M"
+        };
+
+        format!(
+            "{}{} for {} from {} to {}",
+            description,
+            peak_temperature,
+            duration,
+            self.start_timestamp.format("%Y-%m-%d %H:%M"),
+            self.end_timestamp.format(end_format)
+        )
+    }
+
+    /// Returns the severity of the breach of the specified config, using the default
+    /// thresholds (see SeverityThresholds).
+    pub fn severity(&self, config: &TemperatureBreachConfig) -> Severity {
+        self.severity_with_thresholds(config, &SeverityThresholds::default())
+    }
+
+    /// Returns the severity of the breach of the specified config: the more serious of
+    /// the severities for how far the peak temperature goes beyond the config limit and
+    /// for how long the breach lasts. If the peak temperature isn't known (e.g. there are
+    /// no logs), the severity is based on the duration alone.
+    pub fn severity_with_thresholds(
+        &self,
+        config: &TemperatureBreachConfig,
+        thresholds: &SeverityThresholds,
+    ) -> Severity {
+        let duration_severity = if self.duration >= thresholds.critical_duration {
+            Severity::Critical
+        } else if self.duration >= thresholds.major_duration {
+            Severity::Major
+        } else {
+            Severity::Minor
+        };
+
+        let excess_severity = self.peak_temperature.map(|peak| {
+            let excess = match config.breach_type {
+                BreachType::HotConsecutive | BreachType::HotCumulative => peak.0 - config.limit().0,
+                BreachType::ColdConsecutive | BreachType::ColdCumulative => {
+                    config.limit().0 - peak.0
+                }
+            };
+            if excess >= thresholds.critical_excess {
+                Severity::Critical
+            } else if excess >= thresholds.major_excess {
+                Severity::Major
+            } else {
+                Severity::Minor
+            }
+        });
+
+        excess_severity.map_or(duration_severity, |excess_severity| {
+            excess_severity.max(duration_severity)
+        })
+    }
+
+    /// Returns the key which sorts breaches of the specified config worst first: by
+    /// severity (see severity), then duration (longest first), then start time.
+    pub fn sort_key(
+        &self,
+        config: &TemperatureBreachConfig,
+    ) -> (Reverse<Severity>, Reverse<Duration>, NaiveDateTime) {
+        (
+            Reverse(self.severity(config)),
+            Reverse(self.duration),
+            self.start_timestamp,
+        )
+    }
+
+    /// Returns the breach duration rounded to the nearest whole minute (half a minute
+    /// rounds up).
+    pub fn duration_minutes(&self) -> i64 {
+        self.duration_rounded(Duration::minutes(1)).num_minutes()
+    }
+
+    /// Returns the breach duration rounded to the nearest multiple of the specified unit
+    /// (half a unit rounds up), to the millisecond. A unit of zero or less leaves the
+    /// duration as it is.
+    pub fn duration_rounded(&self, to: Duration) -> Duration {
+        let unit = to.num_milliseconds();
+        if unit <= 0 {
+            return self.duration;
+        }
+        let milliseconds = self.duration.num_milliseconds();
+        Duration::milliseconds((milliseconds + unit / 2).div_euclid(unit) * unit)
+    }
+
+    /// Returns whether the breach lasted at least the config's alarm delay, so a breach
+    /// lasting exactly the alarm delay exceeds it.
+    pub fn exceeds_config(&self, config: &TemperatureBreachConfig) -> bool {
+        self.duration >= config.alarm_delay()
+    }
+
+    /// Returns whether the breach ends at or after its start.
+    pub fn is_valid(&self) -> bool {
+        self.end_timestamp >= self.start_timestamp
+    }
+
+    /// Ends a breach which ends before it starts at its start instead (logging a warning),
+    /// so that no inverted breach is returned.
+    pub(crate) fn clamp_end_to_start(&mut self) {
+        if !self.is_valid() {
+            log::warn!(
+                "{:?} breach from {} ends before it starts ({}): ending it at its start",
+                self.breach_type,
+                self.start_timestamp,
+                self.end_timestamp
+            );
+            self.end_timestamp = self.start_timestamp;
+        }
+    }
+
+    /// Returns the calendar days touched by the breach in the specified timezone (a breach
+    /// ending exactly at midnight doesn't touch the following day).
+    fn days(&self, timezone: Option<FixedOffset>) -> Vec<NaiveDate> {
+        let shift = local_time_shift(timezone);
+        let (start, end) = (self.start_timestamp + shift, self.end_timestamp + shift);
+        let mut last_day = end.date();
+        if end > start && end.time() == NaiveTime::MIN {
+            last_day = last_day.pred_opt().unwrap_or(last_day);
+        }
+
+        start
+            .date()
+            .iter_days()
+            .take_while(|day| *day <= last_day)
+            .collect()
+    }
+}
+
+impl Sensor {
+    /// Returns an empty placeholder sensor with the specified serial (otherwise as
+    /// Sensor::default, so with no logs, breaches or configs).
+    pub fn empty(serial: &str) -> Sensor {
+        Sensor {
+            serial: String::from(serial),
+            ..Sensor::default()
+        }
+    }
+
+    /// Returns whether the sensor has no data, i.e. no logs and no breaches (whether they
+    /// are None or empty lists).
+    pub fn is_empty(&self) -> bool {
+        self.log_count() == 0 && self.breach_count() == 0
+    }
+
+    /// Sets the peak temperature of each breach from the logs within the breach window
+    /// (the highest temperature for hot breaches, the lowest for cold ones), or to None
+    /// if there are no logs within the window.
+    pub fn update_peak_temperatures(&mut self) {
+        if let Some(breaches) = &mut self.breaches {
+            let logs = self.logs.as_deref().unwrap_or_default();
+            for breach in breaches {
+                let temperatures = logs
+                    .iter()
+                    .filter(|log| {
+                        log.timestamp >= breach.start_timestamp
+                            && log.timestamp <= breach.end_timestamp
+                    })
+                    .map(|log| log.temperature);
+                breach.peak_temperature = match breach.breach_type {
+                    BreachType::HotConsecutive | BreachType::HotCumulative => {
+                        temperatures.reduce(|peak, temperature| {
+                            if temperature > peak {
+                                temperature
+                            } else {
+                                peak
+                            }
+                        })
+                    }
+                    BreachType::ColdConsecutive | BreachType::ColdCumulative => temperatures
+                        .reduce(|peak, temperature| {
+                            if temperature < peak {
+                                temperature
+                            } else {
+                                peak
+                            }
+                        }),
+                };
+            }
+        }
+    }
+
+    pub fn log_count(&self) -> usize {
+        self.logs.as_ref().map_or(0, Vec::len)
+    }
+
+    pub fn breach_count(&self) -> usize {
+        self.breaches.as_ref().map_or(0, Vec::len)
+    }
+
+    /// Returns the number of breaches of each type, including a zero count for each type
+    /// without any breaches.
+    pub fn breach_counts(&self) -> BTreeMap<BreachType, usize> {
+        let mut counts: BTreeMap<BreachType, usize> = [
+            BreachType::HotConsecutive,
+            BreachType::ColdConsecutive,
+            BreachType::HotCumulative,
+            BreachType::ColdCumulative,
+        ]
+        .into_iter()
+        .map(|breach_type| (breach_type, 0))
+        .collect();
+        for breach in self.breaches.iter().flatten() {
+            *counts.entry(breach.breach_type.clone()).or_default() += 1;
+        }
+        counts
+    }
+
+    pub fn config_count(&self) -> usize {
+        self.configs.as_ref().map_or(0, Vec::len)
+    }
+
+    /// Returns the summary of the sensor (see SensorSummary).
+    pub fn summary(&self) -> SensorSummary {
+        let extremes = self.extreme_temperatures();
+
+        SensorSummary {
+            serial: self.serial.clone(),
+            name: self.name.clone(),
+            sensor_type: self.sensor_type.clone(),
+            last_connected_timestamp: self.last_connected_timestamp,
+            log_count: self.log_count(),
+            breach_count: self.breach_count(),
+            unacknowledged_breach_count: self.unacknowledged_breaches().len(),
+            min_temperature: extremes.map(|(lowest, _)| lowest),
+            max_temperature: extremes.map(|(_, highest)| highest),
+            battery_low: self.battery_low,
+        }
+    }
+
+    #[cfg(all(feature = "serde", feature = "std"))]
+    /// Returns the summary of the sensor (see Sensor::summary) as a JSON object, using the
+    /// same timestamp format as the serde serialization of the sensor.
+    pub fn to_summary_json(&self) -> String {
+        serde_json::to_string(&self.summary()).unwrap_or_default()
+    }
+
+    /// Returns the logs recorded during the trip, i.e. from the activation timestamp to the
+    /// stop timestamp inclusive (either end is open if the timestamp isn't recorded), so
+    /// that readings from before the sensor was activated aren't counted.
+    pub fn active_logs(&self) -> Vec<&TemperatureLog> {
+        self.logs
+            .iter()
+            .flatten()
+            .filter(|log| self.is_during_trip(log.timestamp))
+            .collect()
+    }
+
+    /// Moves the logs from outside the trip (see active_logs) to excluded_logs, so they
+    /// no longer affect the breaches and statistics but are still available. Any logs
+    /// already excluded are kept.
+    pub fn exclude_inactive_logs(&mut self) {
+        let Some(logs) = self.logs.take() else {
+            return;
+        };
+        let (active, excluded): (Vec<TemperatureLog>, Vec<TemperatureLog>) = logs
+            .into_iter()
+            .partition(|log| self.is_during_trip(log.timestamp));
+
+        self.logs = Some(active);
+        if !excluded.is_empty() {
+            let mut excluded_logs = self.excluded_logs.take().unwrap_or_default();
+            excluded_logs.extend(excluded);
+            excluded_logs.sort_by_key(|log| log.timestamp);
+            self.excluded_logs = Some(excluded_logs);
+        }
+    }
+
+    /// Returns the logs recorded before the sensor was activated, when Berlinger sensors run
+    /// a self-test which often logs a burst of out-of-range readings (e.g. while still at
+    /// room temperature). There are none if the activation timestamp isn't recorded.
+    pub fn self_test_logs(&self) -> Vec<&TemperatureLog> {
+        self.logs
+            .iter()
+            .flatten()
+            .filter(|log| self.is_self_test(log.timestamp))
+            .collect()
+    }
+
+    /// Removes the breaches which ended before the sensor was activated, i.e. which were
+    /// only during the self-test (see self_test_logs), so aren't real breaches.
+    pub fn drop_self_test_breaches(&mut self) {
+        let Some(activation) = self.activation_timestamp else {
+            return;
+        };
+        if let Some(breaches) = &mut self.breaches {
+            breaches.retain(|breach| breach.end_timestamp >= activation);
+        }
+    }
+
+    pub(crate) fn is_self_test(&self, timestamp: NaiveDateTime) -> bool {
+        self.activation_timestamp
+            .is_some_and(|activation| timestamp < activation)
+    }
+
+    fn is_during_trip(&self, timestamp: NaiveDateTime) -> bool {
+        self.activation_timestamp
+            .is_none_or(|activation| timestamp >= activation)
+            && self.stop_timestamp.is_none_or(|stop| timestamp <= stop)
+    }
+
+    /// Returns the fraction of the expected logs which were recorded during the trip (see
+    /// expected_log_count), e.g. 0.5 if half the logs are missing. The trip runs from the
+    /// activation to the stop timestamp, or the first/last log if either isn't recorded,
+    /// and logs sharing a timestamp (e.g. Q-tag min/max readings) are only counted once.
+    /// Returns None if there is no log interval or there are no logs during the trip.
+    pub fn log_completeness(&self) -> Option<f64> {
+        let interval = self.log_interval?;
+        let mut timestamps: Vec<NaiveDateTime> =
+            self.active_logs().iter().map(|log| log.timestamp).collect();
+        timestamps.sort();
+        timestamps.dedup();
+
+        let start = self.activation_timestamp.or(timestamps.first().copied())?;
+        let end = self.stop_timestamp.or(timestamps.last().copied())?;
+        let expected = expected_log_count(start, end, interval);
+        if timestamps.is_empty() || expected == 0 {
+            return None;
+        }
+
+        Some(timestamps.len() as f64 / expected as f64)
+    }
+
+    /// Returns the earliest and latest log timestamps, or None if there are no logs.
+    pub fn date_range(&self) -> Option<(NaiveDateTime, NaiveDateTime)> {
+        let mut timestamps = self.logs.iter().flatten().map(|log| log.timestamp);
+        let first = timestamps.next()?;
+        Some(
+            timestamps.fold((first, first), |(earliest, latest), timestamp| {
+                (earliest.min(timestamp), latest.max(timestamp))
+            }),
+        )
+    }
+
+    /// Returns the lowest and highest log temperatures, or None if there are no logs.
+    pub fn extreme_temperatures(&self) -> Option<(Celsius, Celsius)> {
+        let mut temperatures = self.logs.iter().flatten().map(|log| log.temperature);
+        let first = temperatures.next()?;
+        Some(
+            temperatures.fold((first, first), |(lowest, highest), temperature| {
+                (
+                    Celsius(lowest.0.min(temperature.0)),
+                    Celsius(highest.0.max(temperature.0)),
+                )
+            }),
+        )
+    }
+
+    /// Returns whichever of the lowest and highest log temperatures is furthest from 5°C
+    /// (the middle of the usual 2-8°C range), or None if there are no logs.
+    pub fn worst_temperature(&self) -> Option<Celsius> {
+        self.worst_temperature_from(Celsius(5.0))
+    }
+
+    /// As worst_temperature, but furthest from the specified ideal temperature (the
+    /// highest temperature if they are equally far).
+    pub fn worst_temperature_from(&self, ideal: Celsius) -> Option<Celsius> {
+        let (lowest, highest) = self.extreme_temperatures()?;
+        if ideal.0 - lowest.0 > highest.0 - ideal.0 {
+            Some(lowest)
+        } else {
+            Some(highest)
+        }
+    }
+
+    /// Sorts the logs ascending by timestamp and the breaches ascending by start
+    /// timestamp (ties broken by breach type, then end timestamp), so that the order
+    /// doesn't depend on how the sensor data was read or merged.
+    /// Breaches ending before they start are ended at their start (see
+    /// TemperatureBreach::is_valid).
+    pub fn normalize(&mut self) {
+        if let Some(logs) = &mut self.logs {
+            logs.sort_by_key(|log| log.timestamp);
+        }
+        if let Some(breaches) = &mut self.breaches {
+            for breach in breaches.iter_mut() {
+                breach.clamp_end_to_start();
+            }
+            breaches.sort_by(|a, b| {
+                (a.start_timestamp, &a.breach_type, a.end_timestamp).cmp(&(
+                    b.start_timestamp,
+                    &b.breach_type,
+                    b.end_timestamp,
+                ))
+            });
+        }
+    }
+
+    /// Returns a hash of the serial, logs and breaches (including acknowledgements), e.g. to
+    /// skip importing the same sensor file twice. The hash doesn't depend on the order of
+    /// the logs or breaches, and is stable across platforms and releases (it's FNV-1a).
+    pub fn content_hash(&self) -> u64 {
+        let mut log_records: Vec<Vec<u8>> = self
+            .logs
+            .iter()
+            .flatten()
+            .map(|log| {
+                [
+                    timestamp_bytes(log.timestamp).as_slice(),
+                    &log.temperature.0.to_le_bytes(),
+                ]
+                .concat()
+            })
+            .collect();
+        let mut breach_records: Vec<Vec<u8>> = self
+            .breaches
+            .iter()
+            .flatten()
+            .map(|breach| {
+                [
+                    &[breach.breach_type.clone() as u8, breach.acknowledged as u8],
+                    timestamp_bytes(breach.start_timestamp).as_slice(),
+                    &timestamp_bytes(breach.end_timestamp),
+                    &breach.duration.num_milliseconds().to_le_bytes(),
+                ]
+                .concat()
+            })
+            .collect();
+        // sorted so that the hash doesn't depend on the order
+        log_records.sort_unstable();
+        breach_records.sort_unstable();
+
+        let mut hash = fnv1a(FNV_OFFSET_BASIS, &(self.serial.len() as u64).to_le_bytes());
+        hash = fnv1a(hash, self.serial.as_bytes());
+        for records in [log_records, breach_records] {
+            hash = fnv1a(hash, &(records.len() as u64).to_le_bytes());
+            for record in records {
+                hash = fnv1a(hash, &record);
+            }
+        }
+        hash
+    }
+
+    /// Returns the logs within the breach window (from its start to its end, inclusive).
+    pub fn logs_during<'a>(&'a self, breach: &TemperatureBreach) -> Vec<&'a TemperatureLog> {
+        self.logs
+            .iter()
+            .flatten()
+            .filter(|log| {
+                log.timestamp >= breach.start_timestamp && log.timestamp <= breach.end_timestamp
+            })
+            .collect()
+    }
+
+    /// Returns the logs as parallel arrays of unix timestamps (in seconds, converted from
+    /// the sensor's timezone, or taking the timestamps as UTC if it has none) and
+    /// temperatures (in °C), in the same order as the logs, which are simpler to pass over
+    /// FFI than a list of logs. Both are empty if there are no logs.
+    pub fn logs_columnar(&self) -> (Vec<i64>, Vec<f64>) {
+        let utc_offset = self
+            .timezone
+            .map_or(0, |timezone| i64::from(timezone.local_minus_utc()));
+        self.logs
+            .iter()
+            .flatten()
+            .map(|log| {
+                (
+                    log.timestamp.and_utc().timestamp() - utc_offset,
+                    log.temperature.0,
+                )
+            })
+            .unzip()
+    }
+
+    /// Returns the breaches grouped by calendar day. A breach which spans more
+    /// than one day is listed under each day it touches.
+    pub fn breaches_by_day(&self) -> BTreeMap<NaiveDate, Vec<&TemperatureBreach>> {
+        let mut breaches_by_day: BTreeMap<NaiveDate, Vec<&TemperatureBreach>> = BTreeMap::new();
+
+        if let Some(breaches) = &self.breaches {
+            for breach in breaches {
+                for day in breach.days(None) {
+                    breaches_by_day.entry(day).or_default().push(breach);
+                }
+            }
+        }
+
+        breaches_by_day
+    }
+
+    /// Splits the sensor into one sensor per calendar day (in date order), each with a
+    /// copy of the sensor details and configs, and only that day's logs, excluded logs
+    /// and breaches. Breaches spanning midnight are clipped to each day they touch (see
+    /// split_breach_by_day). Days without any logs or breaches are left out.
+    pub fn split_by_day(mut self) -> Vec<(NaiveDate, Sensor)> {
+        type DayData = (
+            Vec<TemperatureLog>,
+            Vec<TemperatureLog>,
+            Vec<TemperatureBreach>,
+        );
+        let mut days: BTreeMap<NaiveDate, DayData> = BTreeMap::new();
+
+        let logs = self.logs.take();
+        let excluded_logs = self.excluded_logs.take();
+        let breaches = self.breaches.take();
+        for log in logs.iter().flatten() {
+            let (day_logs, _, _) = days.entry(log.timestamp.date()).or_default();
+            day_logs.push(log.clone());
+        }
+        for log in excluded_logs.iter().flatten() {
+            let (_, day_excluded_logs, _) = days.entry(log.timestamp.date()).or_default();
+            day_excluded_logs.push(log.clone());
+        }
+        for breach in breaches.iter().flatten() {
+            for day_breach in split_breach_by_day(breach) {
+                let (_, _, day_breaches) =
+                    days.entry(day_breach.start_timestamp.date()).or_default();
+                day_breaches.push(day_breach);
+            }
+        }
+
+        // days only have logs (or breaches) if the sensor recorded them
+        days.into_iter()
+            .map(|(day, (day_logs, day_excluded_logs, day_breaches))| {
+                let day_sensor = Sensor {
+                    logs: logs.as_ref().map(|_| day_logs),
+                    excluded_logs: excluded_logs.as_ref().map(|_| day_excluded_logs),
+                    breaches: breaches.as_ref().map(|_| day_breaches),
+                    ..self.clone()
+                };
+                (day, day_sensor)
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "std")]
+    /// Rounds the log temperatures and config thresholds to the specified number of
+    /// decimal places (see Celsius::round_to).
+    pub fn round_temperatures(&mut self, decimal_places: u8) {
+        if let Some(logs) = &mut self.logs {
+            for log in logs {
+                log.temperature = log.temperature.round_to(decimal_places);
+            }
+        }
+        if let Some(configs) = &mut self.configs {
+            for config in configs {
+                config.maximum_temperature = config.maximum_temperature.round_to(decimal_places);
+                config.minimum_temperature = config.minimum_temperature.round_to(decimal_places);
+            }
+        }
+    }
+
+    /// Drops any fractions of a second from the log, breach and sensor timestamps (e.g.
+    /// for systems which can't store them). Logs which then share a timestamp keep their
+    /// relative order when normalized.
+    pub fn truncate_timestamps_to_seconds(&mut self) {
+        let truncate = |timestamp: &mut NaiveDateTime| {
+            *timestamp = timestamp.with_nanosecond(0).unwrap_or(*timestamp);
+        };
+
+        for timestamp in [
+            &mut self.last_connected_timestamp,
+            &mut self.activation_timestamp,
+            &mut self.stop_timestamp,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            truncate(timestamp);
+        }
+        if let Some(logs) = &mut self.logs {
+            for log in logs {
+                truncate(&mut log.timestamp);
+            }
+        }
+        if let Some(breaches) = &mut self.breaches {
+            for breach in breaches {
+                truncate(&mut breach.start_timestamp);
+                truncate(&mut breach.end_timestamp);
+            }
+        }
+    }
+
+    /// Moves all the sensor, log and breach timestamps by the specified offset.
+    pub(crate) fn shift_timestamps(&mut self, offset: Duration) {
+        for timestamp in [
+            &mut self.last_connected_timestamp,
+            &mut self.activation_timestamp,
+            &mut self.stop_timestamp,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *timestamp += offset;
+        }
+        for log in self
+            .logs
+            .iter_mut()
+            .chain(&mut self.excluded_logs)
+            .flatten()
+        {
+            log.timestamp += offset;
+        }
+        for breach in self.breaches.iter_mut().flatten() {
+            breach.start_timestamp += offset;
+            breach.end_timestamp += offset;
+            if let Some(trigger_timestamp) = &mut breach.trigger_timestamp {
+                *trigger_timestamp += offset;
+            }
+        }
+    }
+
+    /// Returns the sensor with its timestamps converted from its timezone to UTC, and the
+    /// timezone cleared. A sensor without a timezone is returned unchanged (with a warning
+    /// logged), as its timestamps can't be converted.
+    pub fn to_utc(mut self) -> Sensor {
+        match self.timezone.take() {
+            Some(timezone) => {
+                self.shift_timestamps(-Duration::seconds(timezone.local_minus_utc().into()))
+            }
+            None => log::warn!(
+                "Sensor {} has no timezone, so its timestamps weren't converted to UTC",
+                self.serial
+            ),
+        }
+        self
+    }
+
+    /// Sets the breach starting at the specified timestamp as acknowledged, returning
+    /// false if there is no such breach.
+    pub fn acknowledge_breach(&mut self, start: NaiveDateTime) -> bool {
+        if let Some(breaches) = &mut self.breaches {
+            if let Some(breach) = breaches
+                .iter_mut()
+                .find(|breach| breach.start_timestamp == start)
+            {
+                breach.acknowledged = true;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Sets the note on the breach starting at the specified timestamp (e.g. "door left open
+    /// during delivery"), replacing any previous one, returning false if there is no such
+    /// breach.
+    pub fn annotate_breach(&mut self, start: NaiveDateTime, note: &str) -> bool {
+        if let Some(breaches) = &mut self.breaches {
+            if let Some(breach) = breaches
+                .iter_mut()
+                .find(|breach| breach.start_timestamp == start)
+            {
+                breach.annotation = Some(String::from(note));
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Sets all the breaches as acknowledged.
+    pub fn acknowledge_all(&mut self) {
+        if let Some(breaches) = &mut self.breaches {
+            for breach in breaches {
+                breach.acknowledged = true;
+            }
+        }
+    }
+
+    /// Returns the first config of the same type as the specified breach (i.e. the limit
+    /// it breached), or None if there is no such config.
+    pub fn config_for_breach(
+        &self,
+        breach: &TemperatureBreach,
+    ) -> Option<&TemperatureBreachConfig> {
+        self.configs
+            .iter()
+            .flatten()
+            .find(|config| config.breach_type == breach.breach_type)
+    }
+
+    /// Returns the breaches sorted worst first (see TemperatureBreach::sort_key), each
+    /// graded against the first of the specified configs of its type, or on its duration
+    /// alone if there is no config of its type.
+    pub fn breaches_sorted_by_severity(
+        &self,
+        configs: &[TemperatureBreachConfig],
+    ) -> Vec<&TemperatureBreach> {
+        let mut breaches: Vec<&TemperatureBreach> = self.breaches.iter().flatten().collect();
+        breaches.sort_by_cached_key(|breach| {
+            match configs
+                .iter()
+                .find(|config| config.breach_type == breach.breach_type)
+            {
+                Some(config) => breach.sort_key(config),
+                // the default limits are never breached, so only the duration counts
+                None => breach.sort_key(&TemperatureBreachConfig {
+                    breach_type: breach.breach_type.clone(),
+                    ..TemperatureBreachConfig::default()
+                }),
+            }
+        });
+        breaches
+    }
+
+    /// Returns the breaches which haven't been acknowledged yet.
+    pub fn unacknowledged_breaches(&self) -> Vec<&TemperatureBreach> {
+        self.breaches
+            .iter()
+            .flatten()
+            .filter(|breach| !breach.acknowledged)
+            .collect()
+    }
+
+    /// Returns whether any of the breaches haven't been acknowledged yet.
+    pub fn has_unacknowledged(&self) -> bool {
+        self.breaches
+            .iter()
+            .flatten()
+            .any(|breach| !breach.acknowledged)
+    }
+
+    /// Corrects the calculated start & end times of the cumulative breaches from the
+    /// temperature logs of the same day, using the default midnight tolerance of one
+    /// log interval (see correct_cumulative_breaches_with_tolerance).
+    pub fn correct_cumulative_breaches(&mut self) {
+        self.correct_cumulative_breaches_with_tolerance(1.0);
+    }
+
+    /// Corrects the calculated start & end times of the cumulative breaches by applying
+    /// rules (a), (b) & (c) from the crate docs to the logs of the breach day which breach
+    /// any config of the same type. The midnight tolerance used by rule (b) is a multiple
+    /// of the log interval (so 2.0 snaps breaching logs up to two intervals from midnight,
+    /// and 0 disables snapping). Rules (b) & (c) need the log interval, so only rule (a)
+    /// is applied if it's missing. Breach durations are left as recorded by the sensor, but
+    /// peak temperatures are updated for the new breach windows.
+    pub fn correct_cumulative_breaches_with_tolerance(&mut self, midnight_tolerance: f64) {
+        let logs = self.logs.as_deref().unwrap_or_default();
+        let configs = self.configs.as_deref().unwrap_or_default();
+        let log_interval = self.log_interval;
+
+        if let Some(breaches) = &mut self.breaches {
+            for breach in breaches.iter_mut().filter(|breach| {
+                matches!(
+                    breach.breach_type,
+                    BreachType::HotCumulative | BreachType::ColdCumulative
+                )
+            }) {
+                let day_start = NaiveDateTime::new(breach.start_timestamp.date(), NaiveTime::MIN);
+                let day_end = day_start + Duration::days(1);
+                let mut breaching_timestamps = logs
+                    .iter()
+                    .filter(|log| {
+                        log.timestamp >= day_start
+                            && log.timestamp < day_end
+                            && configs.iter().any(|config| {
+                                config.breach_type == breach.breach_type
+                                    && config.is_breaching(log.temperature)
+                            })
+                    })
+                    .map(|log| log.timestamp);
+                let Some(first) = breaching_timestamps.next() else {
+                    continue;
+                };
+                let last = breaching_timestamps.fold(first, NaiveDateTime::max);
+
+                // (a) expand to the first & last breaching logs
+                let mut start = breach.start_timestamp.min(first);
+                let mut end = breach.end_timestamp.max(last);
+
+                if let Some(interval) = log_interval {
+                    // (c) shrink for non-continuous breaches
+                    if first - start > interval {
+                        start = first;
+                    }
+                    if end - last > interval {
+                        end = last;
+                    }
+
+                    // (b) snap to midnight
+                    if midnight_tolerance > 0.0 {
+                        let tolerance = Duration::milliseconds(
+                            (interval.num_milliseconds() as f64 * midnight_tolerance) as i64,
+                        );
+                        if first - day_start <= tolerance {
+                            start = day_start;
+                        }
+                        if day_end - last <= tolerance {
+                            end = day_end;
+                        }
+                    }
+                }
+
+                breach.start_timestamp = start;
+                breach.end_timestamp = end;
+                debug_assert!(
+                    breach.is_valid(),
+                    "corrected breach is inverted: {:?}",
+                    breach
+                );
+            }
+        }
+
+        self.update_peak_temperatures();
+        self.normalize();
+    }
+
+    /// Changes the type of each cumulative breach to the matching consecutive type if the
+    /// logs show it was continuous, i.e. there is at least one log from its start to its
+    /// end (inclusive) and every one of them breaches a config of the same type, so there
+    /// is no in-range log in between. The duration of a reclassified breach is set to
+    /// end - start, as for any consecutive breach. Breaches without logs are left as they are.
+    pub fn reclassify_continuous_cumulative_breaches(&mut self) {
+        let logs = self.logs.as_deref().unwrap_or_default();
+        let configs = self.configs.as_deref().unwrap_or_default();
+
+        if let Some(breaches) = &mut self.breaches {
+            for breach in breaches.iter_mut() {
+                let consecutive_type = match breach.breach_type {
+                    BreachType::HotCumulative => BreachType::HotConsecutive,
+                    BreachType::ColdCumulative => BreachType::ColdConsecutive,
+                    _ => continue,
+                };
+                let mut breach_logs = logs
+                    .iter()
+                    .filter(|log| {
+                        log.timestamp >= breach.start_timestamp
+                            && log.timestamp <= breach.end_timestamp
+                    })
+                    .peekable();
+                let is_continuous = breach_logs.peek().is_some()
+                    && breach_logs.all(|log| {
+                        configs.iter().any(|config| {
+                            config.breach_type == breach.breach_type
+                                && config.is_breaching(log.temperature)
+                        })
+                    });
+
+                if is_continuous {
+                    breach.breach_type = consecutive_type;
+                    breach.duration = breach.end_timestamp - breach.start_timestamp;
+                }
+            }
+        }
+
+        self.normalize();
+    }
+
+    /// Moves the start of each breach to the first log within it, and its end to the last
+    /// log within it (inclusive), so that breaches which were worked out rather than logged
+    /// (e.g. after correction) line up with the logs. Breaches without any logs within them
+    /// are left as they are. The durations of consecutive breaches are updated to match, so
+    /// they can be up to a log interval shorter, whereas the durations of cumulative
+    /// breaches are left as recorded, so they can be longer than the snapped breach.
+    pub fn snap_breach_endpoints_to_logs(&mut self) {
+        let logs = self.logs.as_deref().unwrap_or_default();
+
+        if let Some(breaches) = &mut self.breaches {
+            for breach in breaches.iter_mut() {
+                let mut breach_timestamps =
+                    logs.iter().map(|log| log.timestamp).filter(|timestamp| {
+                        *timestamp >= breach.start_timestamp && *timestamp <= breach.end_timestamp
+                    });
+                let Some(first) = breach_timestamps.next() else {
+                    continue;
+                };
+                let last = breach_timestamps.next_back().unwrap_or(first);
+
+                breach.start_timestamp = first;
+                breach.end_timestamp = last;
+                debug_assert!(
+                    breach.is_valid(),
+                    "snapped breach is inverted: {:?}",
+                    breach
+                );
+                if matches!(
+                    breach.breach_type,
+                    BreachType::HotConsecutive | BreachType::ColdConsecutive
+                ) {
+                    breach.duration = last - first;
+                }
+            }
+        }
+
+        self.normalize();
+    }
+
+    /// Returns the summaries of the unacknowledged breaches (see TemperatureBreach::summary),
+    /// one per line, or an empty string if there are none.
+    pub fn alert_summary(&self) -> String {
+        self.unacknowledged_breaches()
+            .into_iter()
+            .map(TemperatureBreach::summary)
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Returns the total time per calendar day during which the temperature logs
+    /// were within range of all the breach configs (each log is taken to apply
+    /// until the next one).
+    fn in_range_time_by_day(&self) -> BTreeMap<NaiveDate, Duration> {
+        let mut in_range_time: BTreeMap<NaiveDate, Duration> = BTreeMap::new();
+
+        if let Some(logs) = &self.logs {
+            for log_pair in logs.windows(2) {
+                let in_range = match &self.configs {
+                    Some(configs) => !configs
+                        .iter()
+                        .any(|config| config.is_breaching(log_pair[0].temperature)),
+                    None => true,
+                };
+
+                if in_range {
+                    // split the interval between the logs at midnight
+                    let mut interval_start = log_pair[0].timestamp;
+                    while interval_start < log_pair[1].timestamp {
+                        let next_midnight = NaiveDateTime::new(
+                            interval_start.date() + Duration::days(1),
+                            NaiveTime::MIN,
+                        );
+                        let interval_end = next_midnight.min(log_pair[1].timestamp);
+                        *in_range_time
+                            .entry(interval_start.date())
+                            .or_insert_with(Duration::zero) += interval_end - interval_start;
+                        interval_start = interval_end;
+                    }
+                }
+            }
+        }
+
+        in_range_time
+    }
+
+    #[cfg(feature = "std")]
+    /// Returns the expected time (in seconds) that the true temperature was outside the
+    /// range `min` to `max`, given Gaussian measurement noise with standard deviation
+    /// `sigma` (in degrees).
+    ///
+    /// Each log is taken to apply until the next one, and each of those intervals is
+    /// weighted by the probability that the true temperature was out of range, assuming
+    /// it is normally distributed around the recorded temperature:
+    ///     P(out) = Φ((min - T) / sigma) + 1 - Φ((max - T) / sigma)
+    /// When sigma is zero this is just the time the recorded temperatures were out of range.
+    pub fn expected_time_outside(&self, min: f64, max: f64, sigma: f64) -> f64 {
+        let mut expected_time = 0.0;
+
+        if let Some(logs) = &self.logs {
+            for log_pair in logs.windows(2) {
+                let temperature = log_pair[0].temperature.0;
+                let probability_outside = if sigma > 0.0 {
+                    normal_cdf((min - temperature) / sigma)
+                        + (1.0 - normal_cdf((max - temperature) / sigma))
+                } else if temperature < min || temperature > max {
+                    1.0
+                } else {
+                    0.0
+                };
+                let interval = (log_pair[1].timestamp - log_pair[0].timestamp).num_milliseconds();
+
+                expected_time += probability_outside * interval as f64 / 1000.0;
+            }
+        }
+
+        expected_time
+    }
+
+    #[cfg(feature = "std")]
+    /// Returns the mean kinetic temperature (MKT) of the logs in °C, or None if there are
+    /// no logs or the activation energy isn't a positive number. The activation energy ΔH
+    /// is in kJ/mol (e.g. 83.144 in the USP & WHO guidance, though some products use
+    /// other values) and the temperatures T are in kelvin:
+    ///     MKT = (ΔH / R) / -ln((e^(-ΔH / (R·T1)) + ... + e^(-ΔH / (R·Tn))) / n)
+    /// where R is the gas constant, 8.314462618e-3 kJ/(mol·K). The logs are taken to be
+    /// evenly spaced, so each one counts equally.
+    pub fn mean_kinetic_temperature(&self, activation_energy_kj_per_mol: f64) -> Option<Celsius> {
+        const GAS_CONSTANT_KJ_PER_MOL_K: f64 = 8.314462618e-3;
+        const ZERO_CELSIUS_IN_KELVIN: f64 = 273.15;
+
+        if !activation_energy_kj_per_mol.is_finite() || activation_energy_kj_per_mol <= 0.0 {
+            return None;
+        }
+        let logs = self.logs.as_deref().filter(|logs| !logs.is_empty())?;
+        let energy_over_r = activation_energy_kj_per_mol / GAS_CONSTANT_KJ_PER_MOL_K;
+        let mean_exponential = logs
+            .iter()
+            .map(|log| (-energy_over_r / (log.temperature.0 + ZERO_CELSIUS_IN_KELVIN)).exp())
+            .sum::<f64>()
+            / logs.len() as f64;
+
+        Some(Celsius(
+            energy_over_r / -mean_exponential.ln() - ZERO_CELSIUS_IN_KELVIN,
+        ))
+    }
+
+    /// Returns the time-weighted mean log temperature within the (optional) start and end
+    /// of the window, i.e. the area under the temperature curve (interpolating linearly
+    /// between logs, as for the trapezoidal rule) divided by the time it covers, so that
+    /// closely-spaced logs don't count for more. If the window only has a single log
+    /// (or several at the same time), their mean is returned, and None if it has no logs.
+    pub fn time_weighted_mean(
+        &self,
+        start: Option<NaiveDateTime>,
+        end: Option<NaiveDateTime>,
+    ) -> Option<Celsius> {
+        let logs = self.logs.as_deref().unwrap_or_default();
+        let in_window = |timestamp: NaiveDateTime| {
+            start.is_none_or(|start| timestamp >= start) && end.is_none_or(|end| timestamp <= end)
+        };
+        let interpolate = |a: &TemperatureLog, b: &TemperatureLog, timestamp: NaiveDateTime| {
+            let span = (b.timestamp - a.timestamp).num_milliseconds() as f64;
+            let fraction = (timestamp - a.timestamp).num_milliseconds() as f64 / span;
+            a.temperature.0 + (b.temperature.0 - a.temperature.0) * fraction
+        };
+
+        let mut area = 0.0; // in degree-milliseconds
+        let mut total_time = 0.0;
+        for log_pair in logs.windows(2) {
+            let (a, b) = (&log_pair[0], &log_pair[1]);
+            let clipped_start = start.map_or(a.timestamp, |start| start.max(a.timestamp));
+            let clipped_end = end.map_or(b.timestamp, |end| end.min(b.timestamp));
+            if clipped_end <= clipped_start {
+                continue;
+            }
+            let time = (clipped_end - clipped_start).num_milliseconds() as f64;
+            area +=
+                time * (interpolate(a, b, clipped_start) + interpolate(a, b, clipped_end)) / 2.0;
+            total_time += time;
+        }
+        if total_time > 0.0 {
+            return Some(Celsius(area / total_time));
+        }
+
+        let temperatures: Vec<f64> = logs
+            .iter()
+            .filter(|log| in_window(log.timestamp))
+            .map(|log| log.temperature.0)
+            .collect();
+        (!temperatures.is_empty())
+            .then(|| Celsius(temperatures.iter().sum::<f64>() / temperatures.len() as f64))
+    }
+
+    /// Returns the total time spent in breaches of the specified type, within the
+    /// (optional) start and end of the window. Breaches partly outside the window only
+    /// count the part inside it (in proportion to their span, as cumulative breach
+    /// durations can be less than end - start), and overlapping breaches of the same type
+    /// are only counted once: each group of overlapping breaches counts at most its span.
+    pub fn time_in_breach(
+        &self,
+        breach_type: BreachType,
+        start: Option<NaiveDateTime>,
+        end: Option<NaiveDateTime>,
+    ) -> Duration {
+        self.breach_time(|breach| breach.breach_type == breach_type, start, end)
+    }
+
+    /// Returns the percentage of the monitored period (from the first to the last log,
+    /// within the optional start and end of the window) which wasn't in breach, counting
+    /// overlapping breaches of any type only once (see time_in_breach). Returns None if
+    /// there is no monitored time in the window.
+    pub fn compliance_percentage(
+        &self,
+        start: Option<NaiveDateTime>,
+        end: Option<NaiveDateTime>,
+    ) -> Option<f64> {
+        let (first_log, last_log) = self.date_range()?;
+        let monitored_start = start.map_or(first_log, |start| start.max(first_log));
+        let monitored_end = end.map_or(last_log, |end| end.min(last_log));
+        if monitored_end <= monitored_start {
+            return None;
+        }
+
+        let monitored_time = (monitored_end - monitored_start).num_milliseconds() as f64;
+        let breach_time = self
+            .breach_time(|_| true, Some(monitored_start), Some(monitored_end))
+            .num_milliseconds() as f64;
+        Some(100.0 * (monitored_time - breach_time) / monitored_time)
+    }
+
+    /// Returns the total time spent in the breaches accepted by the filter, as for
+    /// time_in_breach.
+    fn breach_time(
+        &self,
+        filter: impl Fn(&TemperatureBreach) -> bool,
+        start: Option<NaiveDateTime>,
+        end: Option<NaiveDateTime>,
+    ) -> Duration {
+        let mut clipped_breaches: Vec<(NaiveDateTime, NaiveDateTime, Duration)> = Vec::new();
+
+        for breach in self.breaches.iter().flatten() {
+            if !filter(breach) {
+                continue;
+            }
+            let clipped_start = start.map_or(breach.start_timestamp, |start| {
+                start.max(breach.start_timestamp)
+            });
+            let clipped_end = end.map_or(breach.end_timestamp, |end| end.min(breach.end_timestamp));
+            if clipped_end <= clipped_start {
+                continue;
+            }
+            let span = (breach.end_timestamp - breach.start_timestamp).num_milliseconds();
+            let clipped_duration = if span == 0 {
+                breach.duration
+            } else {
+                Duration::milliseconds(
+                    breach.duration.num_milliseconds()
+                        * (clipped_end - clipped_start).num_milliseconds()
+                        / span,
+                )
+            };
+            clipped_breaches.push((clipped_start, clipped_end, clipped_duration));
+        }
+        clipped_breaches.sort_by_key(|(clipped_start, _, _)| *clipped_start);
+
+        let mut total_time = Duration::zero();
+        let mut group: Option<(NaiveDateTime, NaiveDateTime, Duration)> = None;
+
+        for (clipped_start, clipped_end, clipped_duration) in clipped_breaches {
+            group = match group {
+                Some((group_start, group_end, group_duration)) if clipped_start < group_end => {
+                    Some((
+                        group_start,
+                        group_end.max(clipped_end),
+                        group_duration + clipped_duration,
+                    ))
+                }
+                _ => {
+                    if let Some((group_start, group_end, group_duration)) = group {
+                        total_time += group_duration.min(group_end - group_start);
+                    }
+                    Some((clipped_start, clipped_end, clipped_duration))
+                }
+            };
+        }
+        if let Some((group_start, group_end, group_duration)) = group {
+            total_time += group_duration.min(group_end - group_start);
+        }
+
+        total_time
+    }
+
+    /// Returns the calendar day with the largest total in-range time and no breaches
+    /// (the earliest such day if there is a tie), or None if every day has a breach.
+    pub fn longest_breach_free_day(&self) -> Option<NaiveDate> {
+        let breaches_by_day = self.breaches_by_day();
+        let mut best_day: Option<(NaiveDate, Duration)> = None;
+
+        for (day, in_range_time) in self.in_range_time_by_day() {
+            if breaches_by_day.contains_key(&day) || in_range_time <= Duration::zero() {
+                continue;
+            }
+            match best_day {
+                Some((_, best_time)) if best_time >= in_range_time => {} // keep earliest on a tie
+                _ => best_day = Some((day, in_range_time)),
+            }
+        }
+
+        best_day.map(|(day, _)| day)
+    }
+}
+
+/// Returns the consecutive breaches found in the logs for each of the consecutive breach
+/// configs (cumulative configs are ignored). A breach is a run of logs breaching the
+/// config which lasts at least the config duration, starting at the first breaching log
+/// and ending at the last one. The logs are expected to be sorted by timestamp.
+pub fn detect_consecutive_breaches(
+    logs: &[TemperatureLog],
+    configs: &[TemperatureBreachConfig],
+) -> Vec<TemperatureBreach> {
+    detect_consecutive_breaches_with_min_samples(logs, configs, 1)
+}
+
+/// As detect_consecutive_breaches, but a breach also needs at least `min_samples` logs
+/// (so that two breaching logs far apart don't count on their own, for sites which require
+/// that). A `min_samples` of 1 (or 0) only checks the duration.
+pub fn detect_consecutive_breaches_with_min_samples(
+    logs: &[TemperatureLog],
+    configs: &[TemperatureBreachConfig],
+    min_samples: usize,
+) -> Vec<TemperatureBreach> {
+    let mut breaches: Vec<TemperatureBreach> = Vec::new();
+
+    for config in configs {
+        if !matches!(
+            config.breach_type,
+            BreachType::HotConsecutive | BreachType::ColdConsecutive
+        ) {
+            continue;
+        }
+
+        let mut add_breach = |run: &[TemperatureLog]| {
+            if let (Some(first), Some(last)) = (run.first(), run.last()) {
+                let duration = last.timestamp - first.timestamp;
+                if duration >= config.duration && run.len() >= min_samples {
+                    breaches.push(TemperatureBreach {
+                        breach_type: config.breach_type.clone(),
+                        start_timestamp: first.timestamp,
+                        end_timestamp: last.timestamp,
+                        duration,
+                        acknowledged: false,
+                        peak_temperature: None,
+                        trigger_timestamp: None,
+                        annotation: None,
+                    });
+                }
+            }
+        };
+
+        let mut run_start: Option<usize> = None;
+        for (index, log) in logs.iter().enumerate() {
+            match (config.is_breaching(log.temperature), run_start) {
+                (true, None) => run_start = Some(index),
+                (false, Some(start)) => {
+                    add_breach(&logs[start..index]);
+                    run_start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(start) = run_start {
+            add_breach(&logs[start..]);
+        }
+    }
+
+    breaches
+}
+
+/// Returns the (first, last) log of each contiguous run of logs breaching the config,
+/// whatever its duration (unlike detect_consecutive_breaches), e.g. for shading breach
+/// regions on a chart. A single breaching log is a span on its own. The logs are expected
+/// to be sorted by timestamp.
+pub fn breaching_spans<'a>(
+    logs: &'a [TemperatureLog],
+    config: &TemperatureBreachConfig,
+) -> impl Iterator<Item = (&'a TemperatureLog, &'a TemperatureLog)> {
+    let config = config.clone();
+    let mut index = 0;
+
+    core::iter::from_fn(move || {
+        let first = index
+            + logs[index..]
+                .iter()
+                .position(|log| config.is_breaching(log.temperature))?;
+        let last = first
+            + logs[first..]
+                .iter()
+                .take_while(|log| config.is_breaching(log.temperature))
+                .count()
+            - 1;
+        index = last + 1;
+        Some((&logs[first], &logs[last]))
+    })
+}
+
+/// Returns the (first, last) log timestamps of each contiguous run of logs which came
+/// within `margin` degrees of the config's limit without breaching it (e.g. 7.5°C for a hot
+/// config breaching above 8°C with a margin of 1), as an early warning of a breach. The
+/// logs are expected to be sorted by timestamp.
+pub fn near_miss_spans(
+    logs: &[TemperatureLog],
+    config: &TemperatureBreachConfig,
+    margin: f64,
+) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+    let limit = config.limit().0;
+    let is_near_miss = |log: &TemperatureLog| {
+        let temperature = log.temperature.0;
+        match config.breach_type {
+            BreachType::ColdConsecutive | BreachType::ColdCumulative => {
+                temperature >= limit && temperature <= limit + margin
+            }
+            BreachType::HotConsecutive | BreachType::HotCumulative => {
+                temperature <= limit && temperature >= limit - margin
+            }
+        }
+    };
+
+    let mut spans = Vec::new();
+    let mut first = 0;
+    while let Some(offset) = logs[first..].iter().position(is_near_miss) {
+        first += offset;
+        let last = first
+            + logs[first..]
+                .iter()
+                .take_while(|log| is_near_miss(log))
+                .count()
+            - 1;
+        spans.push((logs[first].timestamp, logs[last].timestamp));
+        first = last + 1;
+    }
+
+    spans
+}
+
+/// Collapses each run of consecutive logs with the same timestamp (e.g. the min and max
+/// recorded for the same minute by Fridge-tags without logging) into the lowest and/or
+/// highest temperature log of the run, so they don't skew downsampling or interval
+/// calculations. With ExtremePreference::Both the min is kept before the max, and a run
+/// of identical temperatures collapses to a single log.
+pub fn collapse_same_timestamp(
+    logs: Vec<TemperatureLog>,
+    keep: ExtremePreference,
+) -> Vec<TemperatureLog> {
+    let mut collapsed: Vec<TemperatureLog> = Vec::with_capacity(logs.len());
+    let mut logs = logs.into_iter().peekable();
+
+    while let Some(first) = logs.next() {
+        let mut min = first.clone();
+        let mut max = first;
+        while let Some(log) = logs.next_if(|log| log.timestamp == min.timestamp) {
+            if log.temperature < min.temperature {
+                min = log;
+            } else if log.temperature > max.temperature {
+                max = log;
+            }
+        }
+
+        match keep {
+            ExtremePreference::Min => collapsed.push(min),
+            ExtremePreference::Max => collapsed.push(max),
+            ExtremePreference::Both => {
+                let same_temperature = min.temperature == max.temperature;
+                collapsed.push(min);
+                if !same_temperature {
+                    collapsed.push(max);
+                }
+            }
+        }
+    }
+
+    collapsed
+}
+
+/// Returns the periods when the sensor wasn't recording, as (last log before the gap,
+/// first log after it) timestamp pairs, where consecutive logs are further apart than
+/// `expected_interval + tolerance`. The logs are expected to be sorted by timestamp.
+pub fn recording_gaps(
+    logs: &[TemperatureLog],
+    expected_interval: Duration,
+    tolerance: Duration,
+) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+    logs.windows(2)
+        .filter(|log_pair| {
+            log_pair[1].timestamp - log_pair[0].timestamp > expected_interval + tolerance
+        })
+        .map(|log_pair| (log_pair[0].timestamp, log_pair[1].timestamp))
+        .collect()
+}
+
+/// Returns the periods when the sensor may be stuck, as (first log, last log) timestamp
+/// pairs of each run of at least `min_run` consecutive logs with exactly the same
+/// (bit-identical) temperature. A working sensor's readings vary a little even in a stable
+/// fridge, so a long flat line usually means a fault. The logs are expected to be sorted
+/// by timestamp.
+pub fn detect_stuck_sensor(
+    logs: &[TemperatureLog],
+    min_run: usize,
+) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+    let mut stuck_spans = Vec::new();
+    let mut first = 0;
+
+    while first < logs.len() {
+        let bits = logs[first].temperature.0.to_bits();
+        let run_length = logs[first..]
+            .iter()
+            .take_while(|log| log.temperature.0.to_bits() == bits)
+            .count();
+        let last = first + run_length - 1;
+        if run_length >= min_run {
+            stuck_spans.push((logs[first].timestamp, logs[last].timestamp));
+        }
+        first = last + 1;
+    }
+
+    stuck_spans
+}
+
+/// Returns the number of logs expected from `start` to `end` inclusive when logging every
+/// `interval` from `start`, or 0 if the end is before the start or the interval isn't
+/// positive.
+pub fn expected_log_count(start: NaiveDateTime, end: NaiveDateTime, interval: Duration) -> u64 {
+    if end < start || interval <= Duration::zero() {
+        return 0;
+    }
+
+    let trip_ms = (end - start).num_milliseconds();
+    let interval_ms = interval.num_milliseconds().max(1);
+    (trip_ms / interval_ms) as u64 + 1
+}
+
+/// Returns at most `max_points` logs representative of the specified logs (e.g. for
+/// plotting), by splitting them into max_points / 2 buckets and keeping the lowest and
+/// highest temperature log in each, so breach extremes are always retained. The logs are
+/// returned unchanged if there are already no more than `max_points`. A max_points of 1
+/// only keeps the first log (there's no room for both extremes), and 0 keeps none.
+pub fn downsample_logs(logs: &[TemperatureLog], max_points: usize) -> Vec<TemperatureLog> {
+    if logs.len() <= max_points {
+        return logs.to_vec();
+    }
+    if max_points < 2 {
+        return logs.iter().take(max_points).cloned().collect();
+    }
+
+    let bucket_count = max_points / 2;
+    let mut downsampled: Vec<TemperatureLog> = Vec::with_capacity(bucket_count * 2);
+
+    for bucket_index in 0..bucket_count {
+        let bucket_start = bucket_index * logs.len() / bucket_count;
+        let bucket_end = (bucket_index + 1) * logs.len() / bucket_count;
+        let bucket = &logs[bucket_start..bucket_end];
+
+        let mut min_index = 0;
+        let mut max_index = 0;
+        for (index, log) in bucket.iter().enumerate() {
+            if log.temperature < bucket[min_index].temperature {
+                min_index = index;
+            }
+            if log.temperature > bucket[max_index].temperature {
+                max_index = index;
+            }
+        }
+
+        // keep the bucket extremes in time order
+        downsampled.push(bucket[min_index.min(max_index)].clone());
+        if min_index != max_index {
+            downsampled.push(bucket[min_index.max(max_index)].clone());
+        }
+    }
+
+    downsampled
+}
+
+/// Returns the logs resampled to a regular grid every `interval` (e.g. to chart several
+/// sensors together), using the specified method. The grid is aligned to multiples of the
+/// interval from midnight of the first log's day, so grids of sensors read at different
+/// times line up, and runs from the first to the last log. Returns no logs if the
+/// interval isn't positive. The logs are expected to be sorted by timestamp.
+pub fn resample_logs(
+    logs: &[TemperatureLog],
+    interval: Duration,
+    method: ResampleMethod,
+) -> Vec<TemperatureLog> {
+    let (Some(first), Some(last)) = (logs.first(), logs.last()) else {
+        return Vec::new();
+    };
+    let interval_ms = interval.num_milliseconds();
+    if interval_ms <= 0 {
+        return Vec::new();
+    }
+
+    // the first grid point at or after the first log
+    let day_start = NaiveDateTime::new(first.timestamp.date(), NaiveTime::MIN);
+    let first_offset_ms = (first.timestamp - day_start).num_milliseconds();
+    let mut timestamp = day_start
+        + Duration::milliseconds((first_offset_ms + interval_ms - 1) / interval_ms * interval_ms);
+
+    let mut resampled: Vec<TemperatureLog> = Vec::new();
+    let mut next_index = 0; // index of the first log after the grid point
+    while timestamp <= last.timestamp {
+        while next_index < logs.len() && logs[next_index].timestamp <= timestamp {
+            next_index += 1;
+        }
+        let before = &logs[next_index - 1];
+        let after = logs.get(next_index);
+
+        let temperature = match method {
+            ResampleMethod::CarryForward => Some(before.temperature),
+            ResampleMethod::Linear => match after {
+                Some(after) if before.timestamp < timestamp => {
+                    let fraction = (timestamp - before.timestamp).num_milliseconds() as f64
+                        / (after.timestamp - before.timestamp).num_milliseconds() as f64;
+                    Some(Celsius(
+                        before.temperature.0
+                            + (after.temperature.0 - before.temperature.0) * fraction,
+                    ))
+                }
+                _ => Some(before.temperature),
+            },
+            ResampleMethod::Nearest => {
+                // the earlier log wins a tie
+                let nearest = after
+                    .filter(|after| after.timestamp - timestamp < timestamp - before.timestamp)
+                    .unwrap_or(before);
+                ((nearest.timestamp - timestamp).abs() * 2 <= interval)
+                    .then_some(nearest.temperature)
+            }
+        };
+
+        if let Some(temperature) = temperature {
+            resampled.push(TemperatureLog {
+                temperature,
+                timestamp,
+            });
+        }
+        timestamp += interval;
+    }
+
+    resampled
+}
+
+/// Returns true if the time windows of the two breaches overlap (regardless of breach
+/// type). Breaches which only touch end-to-start don't overlap, but a zero-length breach
+/// overlaps one covering (or starting at) the same time.
+pub fn breaches_overlap(a: &TemperatureBreach, b: &TemperatureBreach) -> bool {
+    (a.start_timestamp < b.end_timestamp && b.start_timestamp < a.end_timestamp)
+        || a.start_timestamp == b.start_timestamp
+}
+
+/// Returns the breaches in `new` which aren't already in `existing`, where a breach of
+/// the same type with an overlapping time window counts as already existing (e.g. when
+/// the same sensor is read again).
+pub fn dedupe_breaches(
+    existing: &[TemperatureBreach],
+    new: &[TemperatureBreach],
+) -> Vec<TemperatureBreach> {
+    new.iter()
+        .filter(|new_breach| {
+            !existing.iter().any(|existing_breach| {
+                existing_breach.breach_type == new_breach.breach_type
+                    && breaches_overlap(existing_breach, new_breach)
+            })
+        })
+        .cloned()
+        .collect()
+}
+
+/// Returns what's new in the current read of a sensor compared to a previous read of it:
+/// the logs at timestamps which weren't logged before, the breaches which weren't recorded
+/// before (with the same type and an overlapping time window, as for dedupe_breaches), and
+/// the breaches which were recorded before but have since been acknowledged. All are in
+/// the order of the current read.
+pub fn sensor_delta(previous: &Sensor, current: &Sensor) -> SensorDelta {
+    let previous_timestamps: BTreeSet<NaiveDateTime> = previous
+        .logs
+        .iter()
+        .flatten()
+        .map(|log| log.timestamp)
+        .collect();
+    let new_logs = current
+        .logs
+        .iter()
+        .flatten()
+        .filter(|log| !previous_timestamps.contains(&log.timestamp))
+        .cloned()
+        .collect();
+
+    let previous_breaches = previous.breaches.as_deref().unwrap_or_default();
+    let current_breaches = current.breaches.as_deref().unwrap_or_default();
+    let new_breaches = dedupe_breaches(previous_breaches, current_breaches);
+    let newly_acknowledged = current_breaches
+        .iter()
+        .filter(|current_breach| {
+            current_breach.acknowledged
+                && previous_breaches.iter().any(|previous_breach| {
+                    !previous_breach.acknowledged
+                        && previous_breach.breach_type == current_breach.breach_type
+                        && breaches_overlap(previous_breach, current_breach)
+                })
+        })
+        .cloned()
+        .collect();
+
+    SensorDelta {
+        new_logs,
+        new_breaches,
+        newly_acknowledged,
+    }
+}
+
+/// Returns the breaches with those of the same type merged where the gap between them is
+/// at most the tolerance (or they overlap), e.g. two hot breaches separated by a single
+/// in-range log. A merged breach spans both, with their durations summed (less any time
+/// they overlap), the worse peak temperature, the first trigger timestamp, both
+/// annotations (if different), and is only acknowledged if both were. The breaches are
+/// returned sorted by start timestamp, then breach type.
+pub fn merge_adjacent_breaches(
+    mut breaches: Vec<TemperatureBreach>,
+    gap_tolerance: Duration,
+) -> Vec<TemperatureBreach> {
+    breaches.sort_by(|a, b| {
+        (&a.breach_type, a.start_timestamp).cmp(&(&b.breach_type, b.start_timestamp))
+    });
+
+    let mut merged_breaches: Vec<TemperatureBreach> = Vec::new();
+    for breach in breaches {
+        match merged_breaches.last_mut() {
+            Some(merged)
+                if merged.breach_type == breach.breach_type
+                    && breach.start_timestamp - merged.end_timestamp <= gap_tolerance =>
+            {
+                let overlap = (merged.end_timestamp.min(breach.end_timestamp)
+                    - breach.start_timestamp)
+                    .max(Duration::zero());
+                merged.duration = merged.duration + breach.duration - overlap;
+                merged.end_timestamp = merged.end_timestamp.max(breach.end_timestamp);
+                merged.acknowledged &= breach.acknowledged;
+                merged.peak_temperature = match (merged.peak_temperature, breach.peak_temperature) {
+                    (Some(a), Some(b)) => Some(match merged.breach_type {
+                        BreachType::HotConsecutive | BreachType::HotCumulative => {
+                            if b > a {
+                                b
+                            } else {
+                                a
+                            }
+                        }
+                        BreachType::ColdConsecutive | BreachType::ColdCumulative => {
+                            if b < a {
+                                b
+                            } else {
+                                a
+                            }
+                        }
+                    }),
+                    (a, b) => a.or(b),
+                };
+                merged.trigger_timestamp =
+                    match (merged.trigger_timestamp, breach.trigger_timestamp) {
+                        (Some(a), Some(b)) => Some(a.min(b)),
+                        (a, b) => a.or(b),
+                    };
+                merged.annotation = match (merged.annotation.take(), breach.annotation) {
+                    (Some(a), Some(b)) if a != b => Some(format!("{}; {}", a, b)),
+                    (a, b) => a.or(b),
+                };
+            }
+            _ => merged_breaches.push(breach),
+        }
+    }
+
+    merged_breaches.sort_by(|a, b| {
+        (a.start_timestamp, &a.breach_type).cmp(&(b.start_timestamp, &b.breach_type))
+    });
+    merged_breaches
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// Returns the 64-bit FNV-1a hash of the bytes, continuing from the specified hash.
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Returns the seconds & nanoseconds since the epoch of the (naive) timestamp as bytes.
+fn timestamp_bytes(timestamp: NaiveDateTime) -> [u8; 12] {
+    let timestamp = timestamp.and_utc();
+    let mut bytes = [0; 12];
+    bytes[..8].copy_from_slice(&timestamp.timestamp().to_le_bytes());
+    bytes[8..].copy_from_slice(&timestamp.timestamp_subsec_nanos().to_le_bytes());
+    bytes
+}
+
+/// Returns how far local time in the specified timezone is ahead of UTC (none if there's
+/// no timezone, as the timestamps are then local).
+fn local_time_shift(timezone: Option<FixedOffset>) -> Duration {
+    Duration::seconds(timezone.map_or(0, |timezone| timezone.local_minus_utc().into()))
+}
+
+/// Returns the start and end of a breach clamped to the specified day, i.e. the start is
+/// moved forward to midnight if it's on an earlier day, and the end (start + duration) is
+/// moved back to the following midnight if it would be on a later day. This is how breaches
+/// are calculated from the daily summaries of Fridge-tags, as these can't span midnight.
+pub fn clamp_to_day(
+    day: NaiveDate,
+    start: NaiveDateTime,
+    duration: Duration,
+) -> (NaiveDateTime, NaiveDateTime) {
+    let day_start = NaiveDateTime::new(day, NaiveTime::MIN);
+    let day_end = day_start + Duration::days(1);
+    let start = start.clamp(day_start, day_end);
+
+    (start, (start + duration).min(day_end))
+}
+
+/// Splits a breach into one breach per calendar day it touches, each clamped to that
+/// day's midnight boundaries. The breach duration is shared between the days in
+/// proportion to the time spent in each (so a consecutive breach keeps duration equal
+/// to end - start for each day, and the durations always sum to the original).
+pub fn split_breach_by_day(breach: &TemperatureBreach) -> Vec<TemperatureBreach> {
+    split_breach_by_local_day(breach, None)
+}
+
+/// As split_breach_by_day, but splitting at midnight in the specified timezone (the
+/// breach timestamps being UTC), or at midnight of the timestamps as recorded if None.
+pub fn split_breach_by_local_day(
+    breach: &TemperatureBreach,
+    timezone: Option<FixedOffset>,
+) -> Vec<TemperatureBreach> {
+    let days = breach.days(timezone);
+    let total_time = (breach.end_timestamp - breach.start_timestamp).num_milliseconds();
+    if days.len() <= 1 || total_time <= 0 {
+        return vec![breach.clone()];
+    }
+    let shift = local_time_shift(timezone);
+
+    let mut remaining_duration = breach.duration;
+    let mut day_breaches = Vec::new();
+
+    for (index, day) in days.iter().enumerate() {
+        let day_start =
+            (NaiveDateTime::new(*day, NaiveTime::MIN) - shift).max(breach.start_timestamp);
+        let day_end = (NaiveDateTime::new(*day + Duration::days(1), NaiveTime::MIN) - shift)
+            .min(breach.end_timestamp);
+        let duration = if index == days.len() - 1 {
+            remaining_duration // avoids rounding drift on the last day
+        } else {
+            Duration::milliseconds(
+                breach.duration.num_milliseconds() * (day_end - day_start).num_milliseconds()
+                    / total_time,
+            )
+        };
+        remaining_duration -= duration;
+
+        day_breaches.push(TemperatureBreach {
+            start_timestamp: day_start,
+            end_timestamp: day_end,
+            duration,
+            ..breach.clone()
+        });
+    }
+
+    day_breaches
+}
+
+#[cfg(all(feature = "serde", feature = "std"))]
+#[derive(Serialize)]
+/// Define the structure of each line of the breach JSON-lines export.
+struct BreachRecord<'a> {
+    serial: &'a str,
+    breach_type: &'a BreachType,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    duration_seconds: i64,
+    acknowledged: bool,
+}
+
+#[cfg(all(feature = "serde", feature = "std"))]
+/// Returns the breaches of the specified sensor as newline-delimited JSON (one object
+/// per breach, with each line terminated by a newline), or an empty string if there
+/// are no breaches. Timestamps use the same format as the serde serialization of the
+/// sensor.
+pub fn breaches_to_jsonl(sensor: &Sensor) -> String {
+    let mut jsonl = String::new();
+
+    if let Some(breaches) = &sensor.breaches {
+        for breach in breaches {
+            let record = BreachRecord {
+                serial: &sensor.serial,
+                breach_type: &breach.breach_type,
+                start: breach.start_timestamp,
+                end: breach.end_timestamp,
+                duration_seconds: breach.duration.num_seconds(),
+                acknowledged: breach.acknowledged,
+            };
+            if let Ok(line) = serde_json::to_string(&record) {
+                jsonl.push_str(&line);
+                jsonl.push('\n');
+            }
+        }
+    }
+
+    jsonl
+}
+
+#[cfg(all(feature = "serde", feature = "std"))]
+#[derive(Serialize)]
+/// Define the structure of each line of the log NDJSON export.
+struct LogRecord<'a> {
+    serial: &'a str,
+    timestamp: NaiveDateTime,
+    temperature: f64,
+}
+
+#[cfg(all(feature = "serde", feature = "std"))]
+/// Number of log lines written between flushes by write_logs_ndjson.
+const NDJSON_FLUSH_LINES: usize = 1000;
+
+#[cfg(all(feature = "serde", feature = "std"))]
+/// Writes the logs of the specified sensor to the writer as newline-delimited JSON (one
+/// object per log, with the serial as in breaches_to_jsonl), without building the whole
+/// output in memory. The writer is flushed every thousand lines and at the end.
+pub fn write_logs_ndjson<W: std::io::Write>(sensor: &Sensor, w: &mut W) -> std::io::Result<()> {
+    for (index, log) in sensor.logs.iter().flatten().enumerate() {
+        let record = LogRecord {
+            serial: &sensor.serial,
+            timestamp: log.timestamp,
+            temperature: log.temperature.0,
+        };
+        serde_json::to_writer(&mut *w, &record)?;
+        w.write_all(b"\n")?;
+        if (index + 1) % NDJSON_FLUSH_LINES == 0 {
+            w.flush()?;
+        }
+    }
+
+    w.flush()
+}
+
+/// Returns the breaches of the specified sensor as an iCalendar (RFC 5545) feed, with an
+/// event per breach from its start to its end, summarised as for TemperatureBreach::summary.
+/// The start and end times are in UTC if the sensor's timezone is known, and otherwise are
+/// floating (i.e. shown as is, in the calendar's own timezone). The DTSTAMP (when the sensor
+/// was read) must be in UTC, so is taken as UTC if the timezone isn't known. A sensor without
+/// breaches gives a calendar without events.
+pub fn breaches_to_ics(sensor: &Sensor) -> String {
+    let utc_offset = sensor
+        .timezone
+        .map(|timezone| Duration::seconds(timezone.local_minus_utc().into()));
+    let ics_time = |timestamp: NaiveDateTime| match utc_offset {
+        Some(offset) => format!("{}Z", (timestamp - offset).format("%Y%m%dT%H%M%S")),
+        None => format!("{}", timestamp.format("%Y%m%dT%H%M%S")),
+    };
+
+    let mut lines = vec![
+        String::from("BEGIN:VCALENDAR"),
+        String::from("VERSION:2.0"),
+        String::from("PRODID:-//openMSupply//temperature-sensor//EN"),
+    ];
+    for breach in sensor.breaches.iter().flatten() {
+        let start = breach.start_timestamp.format("%Y%m%dT%H%M%S");
+        lines.push(String::from("BEGIN:VEVENT"));
+        lines.push(format!(
+            "UID:{}-{:?}-{}@temperature-sensor",
+            escape_ics_text(&sensor.serial),
+            breach.breach_type,
+            start
+        ));
+        // the time the event was created is required, so use when the sensor was read
+        let created = sensor
+            .last_connected_timestamp
+            .unwrap_or(breach.start_timestamp);
+        let created_utc = created - utc_offset.unwrap_or_else(Duration::zero);
+        lines.push(format!("DTSTAMP:{}Z", created_utc.format("%Y%m%dT%H%M%S")));
+        lines.push(format!("DTSTART:{}", ics_time(breach.start_timestamp)));
+        lines.push(format!("DTEND:{}", ics_time(breach.end_timestamp)));
+        lines.push(format!("SUMMARY:{}", escape_ics_text(&breach.summary())));
+        lines.push(format!(
+            "DESCRIPTION:Sensor {}",
+            escape_ics_text(&sensor.serial)
+        ));
+        lines.push(String::from("END:VEVENT"));
+    }
+    lines.push(String::from("END:VCALENDAR"));
+
+    let mut ics = String::new();
+    for line in lines {
+        fold_ics_line(&mut ics, &line);
+    }
+    ics
+}
+
+/// Escapes the characters which are special in iCalendar text values.
+fn escape_ics_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(character);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+/// Appends the iCalendar content line to the output, split into lines of at most 75 bytes
+/// (continuation lines start with a space) and each terminated by CRLF.
+fn fold_ics_line(ics: &mut String, line: &str) {
+    const MAX_LINE_BYTES: usize = 75;
+    let mut line_bytes = 0;
+
+    for character in line.chars() {
+        if line_bytes + character.len_utf8() > MAX_LINE_BYTES {
+            ics.push_str("\r\n ");
+            line_bytes = 1;
+        }
+        ics.push(character);
+        line_bytes += character.len_utf8();
+    }
+    ics.push_str("\r\n");
 }
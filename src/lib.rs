@@ -33,9 +33,10 @@
 //! (2) Berlinger Fridge-tags with logging e.g. Fridge-tag 2L:
 //!
 //! These record breaches in the same way and have the same limitations, but they also
-//! record full temperature logs (usually every 5 minutes) => it would be possible to
+//! record full temperature logs (usually every 5 minutes) => it is possible to
 //! "correct" the calculated breach start and end times by processing the temperature
-//! logs for the same day by applying the following 3 sets of rules:
+//! logs for the same day by applying the following 3 sets of rules (see
+//! Sensor::correct_cumulative_breaches):
 //!
 //! (a) expand the start & end times based on the first & last breaching temperature logs
 //! of the day:
@@ -49,6 +50,8 @@
 //!      midnight, then set the breach start time to midnight.
 //!    - if the last breaching temperature log is within the sensor log interval of
 //!      midnight, then set the breach end time to midnight.
+//!    - the tolerance defaults to one log interval, but can be set as a multiple of it
+//!      for sensors which sample slightly off-schedule.
 //!
 //! (c) correct for other discrepancies when it's a non-continuous breach:
 //!    - if the first breaching temperature log is more than the sensor log interval
@@ -79,31 +82,46 @@
 //! true end time can be calculated from the last breaching temperature log of the day.
 //!
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
 pub mod berlinger;
 pub mod common;
 
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
 use std::fs::File;
+#[cfg(feature = "std")]
 use std::io::Write;
+#[cfg(feature = "std")]
+use std::path::Path;
 
 pub use crate::common::{
-    BreachType, Sensor, SensorType, TemperatureBreach, TemperatureBreachConfig, TemperatureLog,
+    BerlingerModel, BreachType, Celsius, IntervalUnit, ParseOptions, ParseWarning, ParsedSensor,
+    ProductProfile, Sensor, SensorDelta, SensorSummary, SensorType, Severity, SeverityThresholds,
+    TemperatureBreach, TemperatureBreachConfig, TemperatureLog, TemperatureSensorError,
 };
 
+#[cfg(feature = "std")]
 use chrono::{Duration, Local, NaiveDateTime};
 
+#[cfg(feature = "std")]
 /// Returns some made-up example temperature sensor data, for use in automated tests.
 pub fn sample_sensor() -> Sensor {
     let config_cold_consecutive = TemperatureBreachConfig {
         breach_type: BreachType::ColdConsecutive,
-        maximum_temperature: 100.0,
-        minimum_temperature: 2.0,
+        maximum_temperature: Celsius(100.0),
+        minimum_temperature: Celsius(2.0),
         duration: Duration::seconds(240),
     };
 
     let config_hot_consecutive = TemperatureBreachConfig {
         breach_type: BreachType::HotConsecutive,
-        maximum_temperature: 8.0,
-        minimum_temperature: -273.0,
+        maximum_temperature: Celsius(8.0),
+        minimum_temperature: Celsius(-273.0),
         duration: Duration::seconds(300),
     };
 
@@ -117,6 +135,7 @@ pub fn sample_sensor() -> Sensor {
 
     let mut temperature_timestamp =
         NaiveDateTime::parse_from_str("2023-05-23 13:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+    let activation_timestamp = temperature_timestamp;
     let interval = Duration::minutes(1);
     let hot_start_timestamp = temperature_timestamp + interval * 4;
     let hot_end_timestamp = temperature_timestamp + interval * 10;
@@ -130,10 +149,10 @@ pub fn sample_sensor() -> Sensor {
 
     for temperature_value in temperature_iterator {
         temperature_logs.push(TemperatureLog {
-            temperature: *temperature_value,
+            temperature: Celsius(*temperature_value),
             timestamp: temperature_timestamp,
         });
-        temperature_timestamp = temperature_timestamp + interval;
+        temperature_timestamp += interval;
     }
 
     let breach_cold_consecutive = TemperatureBreach {
@@ -142,6 +161,9 @@ pub fn sample_sensor() -> Sensor {
         end_timestamp: cold_end_timestamp,
         duration: cold_duration,
         acknowledged: false,
+        peak_temperature: None,
+        trigger_timestamp: None,
+        annotation: None,
     };
 
     let breach_hot_consecutive = TemperatureBreach {
@@ -150,106 +172,172 @@ pub fn sample_sensor() -> Sensor {
         end_timestamp: hot_end_timestamp,
         duration: hot_duration,
         acknowledged: false,
+        peak_temperature: None,
+        trigger_timestamp: None,
+        annotation: None,
     };
 
-    let sensor = Sensor {
-        sensor_type: SensorType::Berlinger,
+    let mut sensor = Sensor {
+        sensor_type: SensorType::Berlinger(None), // not a real model
         serial: String::from("reg 1234"),
         name: String::from("Berlinger 1"),
         last_connected_timestamp: Some(temperature_timestamp),
+        activation_timestamp: Some(activation_timestamp),
+        stop_timestamp: Some(temperature_timestamp),
         log_interval: Some(interval),
         breaches: Some(vec![breach_hot_consecutive, breach_cold_consecutive]),
         configs: Some(vec![config_cold_consecutive, config_hot_consecutive]),
         logs: Some(temperature_logs),
+        excluded_logs: None,
+        battery_low: None,
+        timezone: None,
+        source: None,
+        source_path: None,
+        parsed_at: None,
     };
-
+    sensor.update_peak_temperatures();
+    sensor.normalize();
     sensor
 }
 
+#[cfg(feature = "std")]
 /// Returns all sensors found from currently mounted USB drives up to 8GB capacity
 /// (-> any USB drive containing sensor files if you don't have a physical sensor).
-/// For Berlinger sensors, it expects to find a serial_xxxxx.txt file in the root folder
-/// together with a matching PDF file (USB drives can have multiple pairs of files).
-pub fn read_connected_sensors() -> Result<Vec<Sensor>, String> {
+/// For Berlinger sensors, it expects to find a serial_xxxxx.txt file in the root folder,
+/// usually together with a matching PDF file (USB drives can have multiple pairs of files).
+/// A txt file without a PDF is still read, and if there are several PDF files for the
+/// same serial then the most recently modified one is used.
+/// Each sensor's source is the drive it was read from, as the same serial can be found
+/// on more than one drive (e.g. a backup drive).
+pub fn read_connected_sensors() -> Result<Vec<Sensor>, TemperatureSensorError> {
     if let Some(sensor_array) = berlinger::read_sensors_from_usb() {
         Ok(sensor_array)
     } else {
-        Err("No sensors found".to_string())
+        Err(TemperatureSensorError::NoSensorsFound)
     }
 }
 
+#[cfg(feature = "std")]
 /// Returns all the serials found from currently mounted USB drives up to 8GB capacity
 /// (-> any USB drive containing sensor files if you don't have a physical sensor).
-/// For Berlinger sensors, it expects to find a serial_xxxxx.txt file in the root folder
-/// together with a matching PDF file (USB drives can have multiple pairs of files).
-pub fn read_connected_serials() -> Result<Vec<String>, String> {
+/// For Berlinger sensors, it expects to find a serial_xxxxx.txt file in the root folder,
+/// usually together with a matching PDF file (USB drives can have multiple pairs of files).
+/// A txt file without a PDF is still read, and if there are several PDF files for the
+/// same serial then the most recently modified one is used.
+pub fn read_connected_serials() -> Result<Vec<String>, TemperatureSensorError> {
     if let Some(sensor_serials) = berlinger::read_sensor_serials() {
         log::info!("Serials found: {:?}", sensor_serials);
         Ok(sensor_serials)
     } else {
-        Err("No sensors found".to_string())
+        Err(TemperatureSensorError::NoSensorsFound)
     }
 }
 
+#[cfg(feature = "std")]
 /// Reads sensor data from the specified sensor txt file.
-pub fn read_sensor_file(file_path: &str) -> Result<Sensor, String> {
-    if let Some(sensor) = berlinger::read_sensor_from_file(&file_path) {
-        if cfg!(debug_assertions) {
-            // Generate output file for debugging/reference
-            let output_path = "sensor_".to_owned() + &sensor.serial + "_output.txt";
-            if let Some(mut output) = File::create(&output_path).ok() {
-                if write!(output, "{}", format!("{:?}\n\n", sensor)).is_ok() {
-                    log::info!("Output: {}", &output_path)
-                }
-            }
-        }
+/// If any of the sensor data can't be parsed, the error reports the line number and
+/// content of the offending line, and which field failed.
+pub fn read_sensor_file(file_path: &str) -> Result<Sensor, TemperatureSensorError> {
+    read_sensor_file_with_options(file_path, &ParseOptions::default())
+}
 
-        Ok(sensor)
-    } else {
-        Err("Sensor file not found".to_string())
-    }
+#[cfg(feature = "std")]
+/// Reads sensor data from the specified sensor txt file, applying the specified parse
+/// options (e.g. rounding the temperatures).
+pub fn read_sensor_file_with_options(
+    file_path: &str,
+    options: &ParseOptions,
+) -> Result<Sensor, TemperatureSensorError> {
+    berlinger::read_sensor_from_file_with_options(file_path, options)
+}
+
+#[cfg(feature = "std")]
+/// Reads sensor data from the specified sensor txt file, together with warnings about
+/// anything odd which didn't stop it being read (e.g. a missing PDF file or logging
+/// interval), so that data-quality notes can be shown to the user.
+pub fn read_sensor_file_verbose(file_path: &str) -> Result<ParsedSensor, TemperatureSensorError> {
+    read_sensor_file_verbose_with_options(file_path, &ParseOptions::default())
 }
 
+#[cfg(feature = "std")]
+/// As read_sensor_file_verbose, applying the specified parse options (e.g. lenient, which
+/// reads a truncated file with a warning rather than failing).
+pub fn read_sensor_file_verbose_with_options(
+    file_path: &str,
+    options: &ParseOptions,
+) -> Result<ParsedSensor, TemperatureSensorError> {
+    berlinger::read_sensor_from_file_verbose_with_options(file_path, options)
+}
+
+#[cfg(feature = "std")]
 /// Reads sensor data from the contents of a txt file, by writing the
-/// contents to a local txt file and reading that.
-pub fn parse_sensor(file_contents: &str) -> Result<Sensor, String> {
-    let file_path = format!("sensor_input_{}.txt", Local::now().timestamp());
-    if let Some(mut output) = File::create(&file_path).ok() {
-        if write!(output, "{}", file_contents).is_ok() {
-            log::info!("Reading sensor from: {}", &file_path);
-            return read_sensor_file(&file_path);
+/// contents to a temporary txt file (in the system temp directory) and reading that.
+pub fn parse_sensor(file_contents: &str) -> Result<Sensor, TemperatureSensorError> {
+    parse_sensor_in(file_contents, &std::env::temp_dir())
+}
+
+#[cfg(feature = "std")]
+/// As parse_sensor, writing the temporary txt file to the specified directory.
+/// The temporary file is deleted after parsing, whether or not parsing succeeded.
+pub fn parse_sensor_in(
+    file_contents: &str,
+    tmp_dir: &Path,
+) -> Result<Sensor, TemperatureSensorError> {
+    let file_path = tmp_dir.join(format!(
+        "sensor_input_{}_{}.txt",
+        std::process::id(),
+        Local::now().timestamp_nanos_opt().unwrap_or_default()
+    ));
+    let file_name = file_path.to_string_lossy().to_string();
+
+    if let Ok(mut output) = File::create(&file_path) {
+        let written = write!(output, "{}", file_contents).is_ok();
+        drop(output);
+
+        let result = if written {
+            log::info!("Reading sensor from: {}", &file_name);
+            // parsed from memory, so the temporary file isn't where the sensor came from
+            read_sensor_file(&file_name).map(|sensor| Sensor {
+                source_path: None,
+                parsed_at: None,
+                ..sensor
+            })
+        } else {
+            Err(TemperatureSensorError::FileNotCreated(file_name))
+        };
+        if let Err(err) = fs::remove_file(&file_path) {
+            log::warn!("Could not delete {}: {}", file_path.display(), err);
         }
+        return result;
     }
-    Err("Sensor file not created".to_string())
+    Err(TemperatureSensorError::FileNotCreated(file_name))
 }
 
+#[cfg(feature = "std")]
 /// Reads sensor data from USB for the txt file corresponding to the specified serial.
 /// Note that the serial is expected to match the corresponding serial field inside
-/// the txt file.
-pub fn read_sensor(serial: &str) -> Result<Sensor, String> {
+/// the txt file, ignoring case and leading/trailing whitespace (e.g. from a barcode
+/// scanner), so " reg 1234 " finds the sensor with serial "REG 1234".
+pub fn read_sensor(serial: &str) -> Result<Sensor, TemperatureSensorError> {
     if let Some(sensor_array) = berlinger::read_sensors_from_usb() {
-        for sensor in sensor_array {
-            if sensor.serial == serial.to_string() {
-                log::info!("Found sensor: {}", serial);
-
-                if cfg!(debug_assertions) {
-                    // Generate output file for debugging/reference
-                    let output_path = "sensor_".to_owned() + &sensor.serial + "_output.txt";
-                    if let Some(mut output) = File::create(&output_path).ok() {
-                        if write!(output, "{}", format!("{:?}\n\n", sensor)).is_ok() {
-                            log::info!("Output: {}", &output_path)
-                        }
-                    }
-                }
-
-                return Ok(sensor);
-            }
+        if let Some(sensor) = find_sensor_by_serial(sensor_array, serial) {
+            log::info!("Found sensor: {}", serial);
+            return Ok(sensor);
         }
     }
 
-    return Err("Sensor not found".to_string());
+    Err(TemperatureSensorError::SensorNotFound(serial.to_string()))
+}
+
+#[cfg(feature = "std")]
+fn find_sensor_by_serial(sensors: Vec<Sensor>, serial: &str) -> Option<Sensor> {
+    let serial = serial.trim().to_lowercase();
+    sensors
+        .into_iter()
+        .find(|sensor| sensor.serial.trim().to_lowercase() == serial)
 }
 
+#[cfg(feature = "std")]
 /// Applies optional start/end timestamps to the breaches and temperature logs
 /// of the specified sensor e.g. to include only data since the last time the
 /// sensor was read (or from the start of the last recorded breach if it was
@@ -265,110 +353,150 @@ pub fn read_sensor(serial: &str) -> Result<Sensor, String> {
 /// Note that the difference between the start and end breach timestamps is only
 /// the same as the breach duration for consecutive breaches which start and end
 /// within the specified interval.
+///
+/// If the start timestamp is after the end timestamp (e.g. swapped arguments), the
+/// interval is empty and the sensor is returned with no logs and no breaches.
+///
+/// A sensor which records breaches keeps Some (empty) breaches if none are left, and one
+/// which doesn't keeps None (see Sensor::breaches).
 pub fn filter_sensor(
     mut sensor: Sensor,
     start_timestamp: Option<NaiveDateTime>,
     end_timestamp: Option<NaiveDateTime>,
 ) -> Sensor {
+    if let (Some(start), Some(end)) = (start_timestamp, end_timestamp) {
+        if start > end {
+            log::warn!("Empty filter interval: {} is after {}", start, end);
+            sensor.logs = None;
+            if let Some(breaches) = &mut sensor.breaches {
+                breaches.clear();
+            }
+            return sensor;
+        }
+    }
+
     if let Some(start) = start_timestamp {
         let mut filtered_logs: Vec<TemperatureLog> = Vec::new();
-        match sensor.logs {
-            Some(logs) => {
-                for log in logs {
-                    if log.timestamp >= start {
-                        filtered_logs.push(log);
-                    }
-                }
-                if filtered_logs.len() > 0 {
-                    sensor.logs = Some(filtered_logs);
-                } else {
-                    sensor.logs = None;
+        if let Some(logs) = sensor.logs {
+            for log in logs {
+                if log.timestamp >= start {
+                    filtered_logs.push(log);
                 }
             }
-            None => {}
+            if !filtered_logs.is_empty() {
+                sensor.logs = Some(filtered_logs);
+            } else {
+                sensor.logs = None;
+            }
         };
         let mut filtered_breaches: Vec<TemperatureBreach> = Vec::new();
-        match sensor.breaches {
-            Some(breaches) => {
-                for breach in breaches {
-                    if breach.start_timestamp >= start {
-                        // keep if start of breach is after start timestamp
-                        filtered_breaches.push(breach);
-                    } else if breach.end_timestamp >= start {
-                        // if start of breach is before start timestamp
-                        filtered_breaches.push(breach); // keep if end of breach is after start timestamp
-                    }
-                }
-                if filtered_breaches.len() > 0 {
-                    sensor.breaches = Some(filtered_breaches);
-                } else {
-                    sensor.breaches = None;
+        if let Some(breaches) = sensor.breaches {
+            for breach in breaches {
+                if breach.start_timestamp >= start {
+                    // keep if start of breach is after start timestamp
+                    filtered_breaches.push(breach);
+                } else if breach.end_timestamp >= start {
+                    // if start of breach is before start timestamp
+                    filtered_breaches.push(breach); // keep if end of breach is after start timestamp
                 }
             }
-            None => {}
+            // still recorded (see Sensor::breaches), even if none are left
+            sensor.breaches = Some(filtered_breaches);
         };
     }
 
     if let Some(end) = end_timestamp {
         let mut filtered_logs: Vec<TemperatureLog> = Vec::new();
-        match sensor.logs {
-            Some(logs) => {
-                for log in logs {
-                    if log.timestamp <= end {
-                        filtered_logs.push(log);
-                    }
-                }
-                if filtered_logs.len() > 0 {
-                    sensor.logs = Some(filtered_logs);
-                } else {
-                    sensor.logs = None;
+        if let Some(logs) = sensor.logs {
+            for log in logs {
+                if log.timestamp <= end {
+                    filtered_logs.push(log);
                 }
             }
-            None => {}
+            if !filtered_logs.is_empty() {
+                sensor.logs = Some(filtered_logs);
+            } else {
+                sensor.logs = None;
+            }
         };
         let mut filtered_breaches: Vec<TemperatureBreach> = Vec::new();
-        match sensor.breaches {
-            Some(breaches) => {
-                for breach in breaches {
-                    if breach.end_timestamp <= end {
-                        // keep if end of breach is before end timestamp
-                        filtered_breaches.push(breach);
-                    } else if breach.start_timestamp <= end {
-                        // if end of breach is after end timestamp
-                        filtered_breaches.push(breach); // keep if start of breach is before end timestamp
-                    }
-                }
-                if filtered_breaches.len() > 0 {
-                    sensor.breaches = Some(filtered_breaches);
-                } else {
-                    sensor.breaches = None;
+        if let Some(breaches) = sensor.breaches {
+            for breach in breaches {
+                if breach.end_timestamp <= end {
+                    // keep if end of breach is before end timestamp
+                    filtered_breaches.push(breach);
+                } else if breach.start_timestamp <= end {
+                    // if end of breach is after end timestamp
+                    filtered_breaches.push(breach); // keep if start of breach is before end timestamp
                 }
             }
-            None => {}
+            // still recorded (see Sensor::breaches), even if none are left
+            sensor.breaches = Some(filtered_breaches);
         };
     }
 
-    if cfg!(debug_assertions) {
-        // Generate output file for debugging/reference
-        let output_path = "sensor_".to_owned() + &sensor.serial + "_filtered_output.txt";
-        if let Some(mut output) = File::create(&output_path).ok() {
-            if write!(output, "{}", format!("{:?}\n\n", sensor)).is_ok() {
-                log::info!(
-                    "Filtered output from {:?} - {:?} to: {}",
-                    start_timestamp,
-                    end_timestamp,
-                    &output_path
-                );
-            }
-        }
+    sensor
+}
+
+#[cfg(feature = "std")]
+/// As filter_sensor, but stricter: breaches which are only partly within the interval have
+/// their start/end timestamps clipped to it (the breach duration is left unchanged), and
+/// any consecutive breach which then no longer lasts (end - start) at least the duration
+/// of its config is dropped, as the part inside the interval wouldn't have been a breach
+/// on its own. The configs default to the sensor's own configs; if several have the same
+/// breach type, the shortest duration applies, and breaches without a config of their
+/// type (and cumulative breaches) are kept.
+pub fn filter_sensor_strict(
+    sensor: Sensor,
+    start_timestamp: Option<NaiveDateTime>,
+    end_timestamp: Option<NaiveDateTime>,
+    configs: Option<&[TemperatureBreachConfig]>,
+) -> Sensor {
+    let configs = configs
+        .map(|configs| configs.to_vec())
+        .or_else(|| sensor.configs.clone())
+        .unwrap_or_default();
+    let mut sensor = filter_sensor(sensor, start_timestamp, end_timestamp);
+
+    if let Some(breaches) = sensor.breaches.take() {
+        let filtered_breaches: Vec<TemperatureBreach> = breaches
+            .into_iter()
+            .map(|mut breach| {
+                if let Some(start) = start_timestamp {
+                    breach.start_timestamp = breach.start_timestamp.max(start);
+                }
+                if let Some(end) = end_timestamp {
+                    breach.end_timestamp = breach.end_timestamp.min(end);
+                }
+                breach
+            })
+            .filter(|breach| {
+                if !matches!(
+                    breach.breach_type,
+                    BreachType::HotConsecutive | BreachType::ColdConsecutive
+                ) {
+                    return true;
+                }
+                configs
+                    .iter()
+                    .filter(|config| config.breach_type == breach.breach_type)
+                    .map(|config| config.duration)
+                    .min()
+                    .is_none_or(|duration| {
+                        breach.end_timestamp - breach.start_timestamp >= duration
+                    })
+            })
+            .collect();
+        sensor.breaches = Some(filtered_breaches);
     }
 
-    return sensor;
+    sensor
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
+    use chrono::NaiveDate;
 
     #[test]
     fn test_sample_core() {
@@ -413,8 +541,12 @@ mod tests {
             NaiveDateTime::parse_from_str("2023-05-23 13:15:00", "%Y-%m-%d %H:%M:%S").unwrap();
         let sensor = filter_sensor(sample_sensor(), Some(start_timestamp), Some(end_timestamp));
         if let Some(breaches) = sensor.breaches {
-            assert_eq!(breaches[0].start_timestamp, start_timestamp); // start of hot breach changed
-            assert_eq!(breaches[1].end_timestamp, end_timestamp); // end of cold breach changed
+            // both breaches are partly within the interval, so are kept as recorded
+            let hot_start_timestamp = start_timestamp - Duration::minutes(3);
+            let cold_end_timestamp = end_timestamp + Duration::minutes(2);
+            assert_eq!(breaches.len(), 2);
+            assert_eq!(breaches[0].start_timestamp, hot_start_timestamp);
+            assert_eq!(breaches[1].end_timestamp, cold_end_timestamp);
         }
     }
 
@@ -430,4 +562,2231 @@ mod tests {
             assert_eq!(logs[8].timestamp, end_timestamp); // end of cold breach changed
         }
     }
+
+    fn multi_day_sensor() -> Sensor {
+        let mut sensor = sample_sensor();
+        let mut timestamp =
+            NaiveDateTime::parse_from_str("2023-05-22 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let mut logs: Vec<TemperatureLog> = Vec::new();
+
+        for _ in 0..(3 * 24) {
+            logs.push(TemperatureLog {
+                temperature: Celsius(5.0),
+                timestamp,
+            });
+            timestamp += Duration::hours(1);
+        }
+
+        let breach = |start: &str, end: &str| TemperatureBreach {
+            breach_type: BreachType::HotConsecutive,
+            start_timestamp: NaiveDateTime::parse_from_str(start, "%Y-%m-%d %H:%M:%S").unwrap(),
+            end_timestamp: NaiveDateTime::parse_from_str(end, "%Y-%m-%d %H:%M:%S").unwrap(),
+            duration: Duration::hours(1),
+            acknowledged: false,
+            peak_temperature: None,
+            trigger_timestamp: None,
+            annotation: None,
+        };
+
+        sensor.logs = Some(logs);
+        sensor.breaches = Some(vec![
+            breach("2023-05-22 10:00:00", "2023-05-22 11:00:00"),
+            breach("2023-05-24 10:00:00", "2023-05-24 11:00:00"),
+        ]);
+        sensor
+    }
+
+    #[test]
+    fn test_longest_breach_free_day() {
+        let mut sensor = multi_day_sensor();
+        assert_eq!(
+            sensor.longest_breach_free_day(),
+            NaiveDate::from_ymd_opt(2023, 5, 23)
+        );
+
+        if let Some(breaches) = &mut sensor.breaches {
+            breaches[1].start_timestamp -= Duration::days(1); // now spans the clean day too
+        }
+        assert_eq!(sensor.longest_breach_free_day(), None);
+    }
+
+    #[test]
+    fn test_acknowledge_breach() {
+        let mut sensor = sample_sensor();
+        let hot_start_timestamp =
+            NaiveDateTime::parse_from_str("2023-05-23 13:04:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let no_breach_timestamp =
+            NaiveDateTime::parse_from_str("2023-05-23 13:05:00", "%Y-%m-%d %H:%M:%S").unwrap();
+
+        assert_eq!(sensor.unacknowledged_breaches().len(), 2);
+        assert!(sensor.has_unacknowledged());
+        assert!(!sensor.acknowledge_breach(no_breach_timestamp)); // miss
+        assert!(sensor.acknowledge_breach(hot_start_timestamp)); // hit
+        if let Some(breaches) = &sensor.breaches {
+            assert!(breaches[0].acknowledged);
+            assert!(!breaches[1].acknowledged);
+        }
+        let unacknowledged_breaches = sensor.unacknowledged_breaches();
+        assert_eq!(unacknowledged_breaches.len(), 1);
+        assert_eq!(
+            unacknowledged_breaches[0].breach_type,
+            BreachType::ColdConsecutive
+        );
+        assert!(sensor.has_unacknowledged());
+
+        sensor.acknowledge_all();
+        if let Some(breaches) = &sensor.breaches {
+            assert!(breaches.iter().all(|breach| breach.acknowledged));
+        }
+        assert!(sensor.unacknowledged_breaches().is_empty());
+        assert!(!sensor.has_unacknowledged());
+
+        sensor.breaches = None;
+        assert!(!sensor.acknowledge_breach(hot_start_timestamp));
+        sensor.acknowledge_all();
+        assert!(sensor.unacknowledged_breaches().is_empty());
+        assert!(!sensor.has_unacknowledged());
+    }
+
+    #[test]
+    fn test_annotate_breach() {
+        let mut sensor = sample_sensor();
+        let hot_start_timestamp =
+            NaiveDateTime::parse_from_str("2023-05-23 13:04:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let no_breach_timestamp =
+            NaiveDateTime::parse_from_str("2023-05-23 13:05:00", "%Y-%m-%d %H:%M:%S").unwrap();
+
+        assert!(!sensor.annotate_breach(no_breach_timestamp, "door left open")); // miss
+        assert!(sensor.annotate_breach(hot_start_timestamp, "door left open")); // hit
+        let breaches = sensor.breaches.as_ref().unwrap();
+        assert_eq!(breaches[0].annotation.as_deref(), Some("door left open"));
+        assert_eq!(breaches[1].annotation, None);
+
+        #[cfg(all(feature = "serde", feature = "std"))]
+        {
+            let json: serde_json::Value = serde_json::to_value(&breaches[0]).unwrap();
+            assert_eq!(json["annotation"], "door left open");
+        }
+
+        // the parser never sets annotations
+        let file_path = format!(
+            "{}/data/FridgeTag 2/130400191542_202302140605.txt",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let sensor = berlinger::read_sensor_from_file(&file_path).unwrap();
+        assert!(sensor
+            .breaches
+            .unwrap_or_default()
+            .iter()
+            .all(|breach| breach.annotation.is_none()));
+    }
+
+    #[test]
+    fn test_expected_time_outside() {
+        let sensor = sample_sensor();
+
+        // 7 hot + 5 cold one-minute intervals
+        let deterministic_time = sensor.expected_time_outside(2.0, 8.0, 0.0);
+        assert_eq!(deterministic_time, 720.0);
+
+        // noise makes near-threshold readings (e.g. 7.9 and 2.5) possibly out of range
+        let noisy_time = sensor.expected_time_outside(2.0, 8.0, 0.5);
+        assert!(noisy_time > 0.0 && noisy_time < 1080.0);
+        assert!((noisy_time - deterministic_time).abs() > 1.0);
+
+        let mut empty_sensor = sample_sensor();
+        empty_sensor.logs = None;
+        assert_eq!(empty_sensor.expected_time_outside(2.0, 8.0, 0.5), 0.0);
+    }
+
+    #[test]
+    fn test_logs_during() {
+        let mut sensor = sample_sensor();
+        let hot_breach = sensor
+            .breaches
+            .iter()
+            .flatten()
+            .find(|breach| breach.breach_type == BreachType::HotConsecutive)
+            .unwrap()
+            .clone();
+
+        // 8.8 to 8.1, from the start of the breach to the end
+        let logs = sensor.logs_during(&hot_breach);
+        assert_eq!(logs.len(), 7);
+        assert_eq!(logs[0].timestamp, hot_breach.start_timestamp);
+        assert_eq!(logs[6].timestamp, hot_breach.end_timestamp);
+        assert!(logs
+            .iter()
+            .all(|log| log.temperature >= Celsius(8.1) && log.temperature <= Celsius(9.2)));
+
+        sensor.logs = None;
+        assert!(sensor.logs_during(&hot_breach).is_empty());
+    }
+
+    #[test]
+    fn test_logs_columnar() {
+        let mut sensor = sample_sensor();
+        let logs = sensor.logs.clone().unwrap();
+        let (timestamps, temperatures) = sensor.logs_columnar();
+        assert_eq!(timestamps[..2], [1684846800, 1684846860]); // 2023-05-23 13:00 and 13:01
+        assert_eq!(timestamps.len(), logs.len());
+        assert_eq!(temperatures.len(), logs.len());
+        for (i, log) in logs.iter().enumerate() {
+            assert_eq!(timestamps[i], log.timestamp.and_utc().timestamp());
+            assert_eq!(temperatures[i], log.temperature.0);
+        }
+
+        // local timestamps are converted to UTC
+        sensor.timezone = chrono::FixedOffset::east_opt(2 * 3600);
+        let (timestamps, _) = sensor.logs_columnar();
+        assert_eq!(timestamps[0], 1684846800 - 2 * 3600); // 2023-05-23 11:00 UTC
+        let (utc_timestamps, _) = sensor.clone().to_utc().logs_columnar();
+        assert_eq!(timestamps, utc_timestamps);
+
+        sensor.logs = None;
+        assert_eq!(sensor.logs_columnar(), (vec![], vec![]));
+    }
+
+    #[test]
+    fn test_time_weighted_mean() {
+        let start = NaiveDateTime::parse_from_str("2023-05-23 13:00", "%Y-%m-%d %H:%M").unwrap();
+        let log = |minutes: i64, temperature: f64| TemperatureLog {
+            temperature: Celsius(temperature),
+            timestamp: start + Duration::minutes(minutes),
+        };
+        let mut sensor = sample_sensor();
+        // a brief spike logged more often than the rest of the hour
+        sensor.logs = Some(vec![log(0, 4.0), log(1, 10.0), log(2, 4.0), log(60, 4.0)]);
+
+        let mean = |sensor: &Sensor, start, end| sensor.time_weighted_mean(start, end).unwrap().0;
+        let arithmetic_mean = 22.0 / 4.0;
+        // (7 x 1 + 7 x 1 + 4 x 58) / 60 minutes
+        assert!((mean(&sensor, None, None) - 4.1).abs() < 1e-9);
+        assert!(mean(&sensor, None, None) < arithmetic_mean);
+        assert!((mean(&sensor, None, Some(start + Duration::minutes(2))) - 7.0).abs() < 1e-9);
+        // interpolated half way up the spike
+        assert!(
+            (mean(
+                &sensor,
+                Some(start + Duration::seconds(30)),
+                Some(start + Duration::minutes(1))
+            ) - 8.5)
+                .abs()
+                < 1e-9
+        );
+
+        // a single log
+        assert_eq!(
+            sensor.time_weighted_mean(Some(start + Duration::minutes(60)), None),
+            Some(Celsius(4.0))
+        );
+        // no logs
+        assert_eq!(
+            sensor.time_weighted_mean(Some(start + Duration::minutes(61)), None),
+            None
+        );
+        sensor.logs = None;
+        assert_eq!(sensor.time_weighted_mean(None, None), None);
+    }
+
+    #[test]
+    fn test_extreme_temperatures() {
+        let mut sensor = sample_sensor();
+        assert_eq!(
+            sensor.extreme_temperatures(),
+            Some((Celsius(-0.2), Celsius(9.2)))
+        );
+        // -0.2 is 5.2 below 5°C, and 9.2 only 4.2 above
+        assert_eq!(sensor.worst_temperature(), Some(Celsius(-0.2)));
+        assert_eq!(
+            sensor.worst_temperature_from(Celsius(4.0)),
+            Some(Celsius(9.2))
+        );
+
+        sensor.logs = Some(vec![]);
+        assert_eq!(sensor.extreme_temperatures(), None);
+        sensor.logs = None;
+        assert_eq!(sensor.extreme_temperatures(), None);
+        assert_eq!(sensor.worst_temperature(), None);
+    }
+
+    #[test]
+    fn test_mean_kinetic_temperature() {
+        let start = NaiveDateTime::parse_from_str("2023-05-23 13:00", "%Y-%m-%d %H:%M").unwrap();
+        let mut sensor = sample_sensor();
+        sensor.logs = Some(
+            [2.0, 8.0, 25.0]
+                .iter()
+                .enumerate()
+                .map(|(index, temperature)| TemperatureLog {
+                    temperature: Celsius(*temperature),
+                    timestamp: start + Duration::hours(index as i64),
+                })
+                .collect(),
+        );
+
+        // reference values worked out independently from the formula
+        let mkt = |sensor: &Sensor, activation_energy: f64| {
+            sensor
+                .mean_kinetic_temperature(activation_energy)
+                .unwrap()
+                .0
+        };
+        assert!((mkt(&sensor, 83.144) - 17.0163).abs() < 0.001);
+        assert!((mkt(&sensor, 60.0) - 15.5941).abs() < 0.001);
+
+        // a constant temperature is its own MKT
+        sensor.logs.as_mut().unwrap().truncate(1);
+        assert!((mkt(&sensor, 83.144) - 2.0).abs() < 1e-9);
+
+        // the formula needs a positive (finite) activation energy
+        assert_eq!(sensor.mean_kinetic_temperature(0.0), None);
+        assert_eq!(sensor.mean_kinetic_temperature(-83.144), None);
+        assert_eq!(sensor.mean_kinetic_temperature(f64::NAN), None);
+        assert_eq!(sensor.mean_kinetic_temperature(f64::INFINITY), None);
+
+        sensor.logs = Some(vec![]);
+        assert_eq!(sensor.mean_kinetic_temperature(83.144), None);
+        sensor.logs = None;
+        assert_eq!(sensor.mean_kinetic_temperature(83.144), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_breaches_to_jsonl() {
+        let jsonl = common::breaches_to_jsonl(&sample_sensor());
+        let lines: Vec<&str> = jsonl.lines().collect();
+        assert_eq!(lines.len(), 2);
+
+        let first_breach: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        for field in [
+            "serial",
+            "breach_type",
+            "start",
+            "end",
+            "duration_seconds",
+            "acknowledged",
+        ] {
+            assert!(!first_breach[field].is_null(), "missing {}", field);
+        }
+        assert_eq!(first_breach["serial"], "reg 1234");
+        assert_eq!(first_breach["breach_type"], "HotConsecutive");
+        assert_eq!(first_breach["start"], "2023-05-23T13:04:00");
+        assert_eq!(first_breach["duration_seconds"], 360);
+
+        let mut sensor = sample_sensor();
+        sensor.breaches = None;
+        assert_eq!(common::breaches_to_jsonl(&sensor), "");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_write_logs_ndjson() {
+        let sensor = sample_sensor();
+        let mut ndjson: Vec<u8> = Vec::new();
+        common::write_logs_ndjson(&sensor, &mut ndjson).unwrap();
+        let ndjson = String::from_utf8(ndjson).unwrap();
+        let lines: Vec<&str> = ndjson.lines().collect();
+        assert_eq!(lines.len(), sensor.logs.as_ref().unwrap().len());
+        assert!(ndjson.ends_with('\n'));
+
+        let first_log: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(first_log["serial"], "reg 1234");
+        assert_eq!(first_log["temperature"], 3.5);
+        assert!(first_log["timestamp"].is_string());
+
+        let mut no_logs = sample_sensor();
+        no_logs.logs = None;
+        let mut ndjson: Vec<u8> = Vec::new();
+        common::write_logs_ndjson(&no_logs, &mut ndjson).unwrap();
+        assert!(ndjson.is_empty());
+    }
+
+    #[test]
+    fn test_sample_filter_empty_window() {
+        let start_timestamp =
+            NaiveDateTime::parse_from_str("2023-05-23 13:15:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let end_timestamp =
+            NaiveDateTime::parse_from_str("2023-05-23 13:07:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let sensor = filter_sensor(sample_sensor(), Some(start_timestamp), Some(end_timestamp));
+        assert!(sensor.logs.is_none());
+        assert_eq!(sensor.breaches.map(|breaches| breaches.len()), Some(0));
+        assert!(sensor.configs.is_some()); // only logs & breaches are filtered
+
+        // a sensor which doesn't record breaches still has none
+        let mut no_breaches = sample_sensor();
+        no_breaches.breaches = None;
+        let sensor = filter_sensor(no_breaches, Some(end_timestamp), None);
+        assert!(sensor.breaches.is_none());
+    }
+
+    #[test]
+    fn test_read_sensor_file_parse_error() {
+        let file_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/test/broken_temperature.txt"
+        );
+        let error = read_sensor_file(file_path).unwrap_err();
+        assert_eq!(
+            error,
+            TemperatureSensorError::ParseError {
+                line: 32,
+                content: "\t2023-05-01 09:10\t8.y6\tx".to_string(),
+                field: "Temperature".to_string(),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "Invalid Temperature at line 32: 2023-05-01 09:10\t8.y6\tx"
+        );
+    }
+
+    #[test]
+    fn test_celsius() {
+        assert_eq!(Celsius(0.0).to_fahrenheit(), 32.0);
+        assert_eq!(Celsius(100.0).to_fahrenheit(), 212.0);
+        assert_eq!(Celsius(-40.0).to_fahrenheit(), -40.0);
+        assert_eq!(Celsius::from(8.5), Celsius(8.5));
+        assert_eq!(Celsius(8.5).to_string(), "8.5°C");
+
+        assert!(Celsius(2.0) < Celsius(8.0));
+        assert!(Celsius(-0.2) < Celsius(0.0));
+        assert!(Celsius(8.0) >= Celsius(8.0));
+
+        let sensor = sample_sensor();
+        let logs = sensor.logs.unwrap();
+        let hottest = logs
+            .iter()
+            .map(|log| log.temperature)
+            .reduce(|hottest, temperature| {
+                if temperature > hottest {
+                    temperature
+                } else {
+                    hottest
+                }
+            });
+        assert_eq!(hottest, Some(Celsius(9.2)));
+    }
+
+    #[test]
+    fn test_split_breach_by_day() {
+        let timestamp = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+        let breach = TemperatureBreach {
+            breach_type: BreachType::ColdCumulative,
+            start_timestamp: timestamp("2023-05-01 18:00"),
+            end_timestamp: timestamp("2023-05-03 06:00"),
+            duration: Duration::hours(36),
+            acknowledged: false,
+            peak_temperature: None,
+            trigger_timestamp: None,
+            annotation: None,
+        };
+
+        let day_breaches = common::split_breach_by_day(&breach);
+        let days: Vec<(NaiveDateTime, NaiveDateTime, Duration)> = day_breaches
+            .iter()
+            .map(|breach| {
+                (
+                    breach.start_timestamp,
+                    breach.end_timestamp,
+                    breach.duration,
+                )
+            })
+            .collect();
+        assert_eq!(
+            days,
+            vec![
+                (
+                    timestamp("2023-05-01 18:00"),
+                    timestamp("2023-05-02 00:00"),
+                    Duration::hours(6)
+                ),
+                (
+                    timestamp("2023-05-02 00:00"),
+                    timestamp("2023-05-03 00:00"),
+                    Duration::hours(24)
+                ),
+                (
+                    timestamp("2023-05-03 00:00"),
+                    timestamp("2023-05-03 06:00"),
+                    Duration::hours(6)
+                ),
+            ]
+        );
+        assert!(day_breaches
+            .iter()
+            .all(|day_breach| day_breach.breach_type == BreachType::ColdCumulative));
+
+        // a breach within a single day (even if it ends at midnight) isn't split
+        let breach = TemperatureBreach {
+            start_timestamp: timestamp("2023-05-01 18:00"),
+            end_timestamp: timestamp("2023-05-02 00:00"),
+            duration: Duration::hours(2),
+            ..breach
+        };
+        let day_breaches = common::split_breach_by_day(&breach);
+        assert_eq!(day_breaches.len(), 1);
+        assert_eq!(day_breaches[0].duration, Duration::hours(2));
+
+        // a sub-second breach over midnight is shared in milliseconds
+        let midnight = timestamp("2023-05-02 00:00");
+        let breach = TemperatureBreach {
+            start_timestamp: midnight - Duration::milliseconds(250),
+            end_timestamp: midnight + Duration::milliseconds(750),
+            duration: Duration::milliseconds(1000),
+            ..breach
+        };
+        let day_breaches = common::split_breach_by_day(&breach);
+        assert_eq!(
+            day_breaches
+                .iter()
+                .map(|day_breach| day_breach.duration)
+                .collect::<Vec<Duration>>(),
+            vec![Duration::milliseconds(250), Duration::milliseconds(750)]
+        );
+    }
+
+    fn assert_normalized(sensor: &Sensor) {
+        if let Some(logs) = &sensor.logs {
+            assert!(logs
+                .windows(2)
+                .all(|pair| pair[0].timestamp <= pair[1].timestamp));
+        }
+        if let Some(breaches) = &sensor.breaches {
+            assert!(breaches.windows(2).all(|pair| {
+                (pair[0].start_timestamp, &pair[0].breach_type)
+                    <= (pair[1].start_timestamp, &pair[1].breach_type)
+            }));
+        }
+    }
+
+    #[test]
+    fn test_sample_peak_temperatures() {
+        let breaches = sample_sensor().breaches.unwrap();
+        assert_eq!(breaches[0].breach_type, BreachType::HotConsecutive);
+        assert_eq!(breaches[0].peak_temperature, Some(Celsius(9.2)));
+        assert_eq!(breaches[1].breach_type, BreachType::ColdConsecutive);
+        assert_eq!(breaches[1].peak_temperature, Some(Celsius(-0.2)));
+    }
+
+    #[test]
+    fn test_normalize() {
+        let mut sensor = sample_sensor();
+        assert_normalized(&sensor);
+
+        sensor.logs.as_mut().unwrap().reverse();
+        sensor.breaches.as_mut().unwrap().reverse();
+        sensor.normalize();
+        assert_normalized(&sensor);
+        let breaches = sensor.breaches.unwrap();
+        assert_eq!(breaches[0].breach_type, BreachType::HotConsecutive);
+        assert_eq!(breaches[1].breach_type, BreachType::ColdConsecutive);
+
+        // the parser always returns normalized sensors
+        for file_path in [
+            "/data/FridgeTag 2/130400191542_202302140605.txt",
+            "/data/FridgeTag UL/100800001215_202304201410.txt",
+            "/data/QTag CLm doc LR/RCPJ00010_03_202304201359.txt",
+        ] {
+            let file_path = format!("{}{}", env!("CARGO_MANIFEST_DIR"), file_path);
+            let sensor = berlinger::read_sensor_from_file(&file_path).unwrap();
+            assert_normalized(&sensor);
+        }
+    }
+
+    #[test]
+    fn test_merge_adjacent_breaches() {
+        let breaches = sample_sensor().breaches.unwrap();
+        let hot_breach = &breaches[0];
+        assert_eq!(hot_breach.breach_type, BreachType::HotConsecutive);
+        let start = hot_breach.start_timestamp;
+        let breach = |start_minute: i64, end_minute: i64, peak: f64| TemperatureBreach {
+            start_timestamp: start + Duration::minutes(start_minute),
+            end_timestamp: start + Duration::minutes(end_minute),
+            duration: Duration::minutes(end_minute - start_minute),
+            peak_temperature: Some(Celsius(peak)),
+            ..hot_breach.clone()
+        };
+
+        // a 1 minute gap (e.g. a single in-range log) is within a 1 minute tolerance
+        let merged = common::merge_adjacent_breaches(
+            vec![breach(7, 10, 9.5), breach(0, 6, 9.2)],
+            Duration::minutes(1),
+        );
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].start_timestamp, start);
+        assert_eq!(merged[0].end_timestamp, start + Duration::minutes(10));
+        assert_eq!(merged[0].duration, Duration::minutes(9));
+        assert_eq!(merged[0].peak_temperature, Some(Celsius(9.5)));
+
+        // overlapping breaches only count the overlap once
+        let merged = common::merge_adjacent_breaches(
+            vec![breach(0, 6, 9.2), breach(4, 10, 9.5)],
+            Duration::zero(),
+        );
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].duration, Duration::minutes(10));
+
+        // too far apart, or of different types
+        let merged = common::merge_adjacent_breaches(
+            vec![breach(0, 6, 9.2), breach(8, 10, 9.5)],
+            Duration::minutes(1),
+        );
+        assert_eq!(merged.len(), 2);
+        let cold_breach = TemperatureBreach {
+            breach_type: BreachType::ColdConsecutive,
+            ..breach(7, 10, 1.0)
+        };
+        let merged = common::merge_adjacent_breaches(
+            vec![breach(0, 6, 9.2), cold_breach],
+            Duration::minutes(1),
+        );
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].breach_type, BreachType::HotConsecutive);
+        assert_eq!(merged[1].breach_type, BreachType::ColdConsecutive);
+    }
+
+    #[test]
+    fn test_dedupe_breaches() {
+        let existing = sample_sensor().breaches.unwrap();
+        let hot_breach = &existing[0];
+        assert_eq!(hot_breach.breach_type, BreachType::HotConsecutive);
+
+        // identical
+        assert!(common::breaches_overlap(hot_breach, hot_breach));
+        assert!(common::dedupe_breaches(&existing, &existing).is_empty());
+
+        // partially overlapping (same type counts as a duplicate, a different type doesn't)
+        let overlapping = TemperatureBreach {
+            start_timestamp: hot_breach.start_timestamp + Duration::minutes(3),
+            end_timestamp: hot_breach.end_timestamp + Duration::minutes(3),
+            ..hot_breach.clone()
+        };
+        assert!(common::breaches_overlap(hot_breach, &overlapping));
+        assert!(common::dedupe_breaches(&existing, std::slice::from_ref(&overlapping)).is_empty());
+        let overlapping_cumulative = TemperatureBreach {
+            breach_type: BreachType::HotCumulative,
+            ..overlapping
+        };
+        assert_eq!(
+            common::dedupe_breaches(&existing, &[overlapping_cumulative]).len(),
+            1
+        );
+
+        // disjoint, including one which starts exactly when the existing one ends
+        let touching = TemperatureBreach {
+            start_timestamp: hot_breach.end_timestamp,
+            end_timestamp: hot_breach.end_timestamp + Duration::minutes(2),
+            ..hot_breach.clone()
+        };
+        let later = TemperatureBreach {
+            start_timestamp: hot_breach.end_timestamp + Duration::hours(1),
+            end_timestamp: hot_breach.end_timestamp + Duration::hours(2),
+            ..hot_breach.clone()
+        };
+        assert!(!common::breaches_overlap(hot_breach, &touching));
+        assert!(!common::breaches_overlap(hot_breach, &later));
+        let new_breaches = common::dedupe_breaches(&existing, &[touching, later]);
+        assert_eq!(new_breaches.len(), 2);
+    }
+
+    #[test]
+    fn test_sensor_delta() {
+        let previous = sample_sensor();
+        let mut current = previous.clone();
+
+        let last_log = previous.logs.as_ref().unwrap().last().unwrap().clone();
+        let extra_logs: Vec<TemperatureLog> = [9.0, 9.5]
+            .iter()
+            .zip(1..)
+            .map(|(temperature, i)| TemperatureLog {
+                temperature: Celsius(*temperature),
+                timestamp: last_log.timestamp + Duration::minutes(i),
+            })
+            .collect();
+        current.logs.as_mut().unwrap().extend(extra_logs.clone());
+        let new_breach = TemperatureBreach {
+            breach_type: BreachType::HotConsecutive,
+            start_timestamp: extra_logs[0].timestamp,
+            end_timestamp: extra_logs[1].timestamp,
+            duration: Duration::minutes(1),
+            acknowledged: false,
+            peak_temperature: Some(Celsius(9.5)),
+            trigger_timestamp: None,
+            annotation: None,
+        };
+        current.breaches.as_mut().unwrap().push(new_breach);
+
+        let delta = common::sensor_delta(&previous, &current);
+        let log_values = |logs: &[TemperatureLog]| {
+            logs.iter()
+                .map(|log| (log.timestamp, log.temperature))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(log_values(&delta.new_logs), log_values(&extra_logs));
+        assert_eq!(delta.new_breaches.len(), 1);
+        assert_eq!(
+            delta.new_breaches[0].start_timestamp,
+            extra_logs[0].timestamp
+        );
+        assert!(delta.newly_acknowledged.is_empty());
+
+        // acknowledging a breach from the previous read
+        let first_breach_start = current.breaches.as_ref().unwrap()[0].start_timestamp;
+        assert!(current.acknowledge_breach(first_breach_start));
+        let delta = common::sensor_delta(&previous, &current);
+        assert_eq!(delta.new_breaches.len(), 1);
+        assert_eq!(delta.newly_acknowledged.len(), 1);
+        assert_eq!(
+            delta.newly_acknowledged[0].start_timestamp,
+            first_breach_start
+        );
+
+        // nothing new when reading the same sensor again
+        let delta = common::sensor_delta(&current, &current);
+        assert!(delta.new_logs.is_empty());
+        assert!(delta.new_breaches.is_empty());
+        assert!(delta.newly_acknowledged.is_empty());
+    }
+
+    #[test]
+    fn test_parse_sensor_in() {
+        let tmp_dir = std::env::temp_dir().join("temperature_sensor_parse_sensor_in");
+        let _ = fs::remove_dir_all(&tmp_dir);
+        fs::create_dir_all(&tmp_dir).unwrap();
+
+        let contents = fs::read_to_string(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/test/logs_standard_columns.txt"
+        ))
+        .unwrap();
+        let sensor = parse_sensor_in(&contents, &tmp_dir).unwrap();
+        assert_eq!(sensor.serial, "130500100001");
+        assert_eq!(fs::read_dir(&tmp_dir).unwrap().count(), 0);
+        assert!(sensor.source_path.is_none());
+        assert!(sensor.parsed_at.is_none());
+
+        // also removed when parsing fails
+        let contents = fs::read_to_string(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/test/broken_temperature.txt"
+        ))
+        .unwrap();
+        assert!(parse_sensor_in(&contents, &tmp_dir).is_err());
+        assert_eq!(fs::read_dir(&tmp_dir).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_time_in_breach() {
+        let mut sensor = sample_sensor();
+        let hot_breach = sensor.breaches.as_ref().unwrap()[0].clone();
+        assert_eq!(hot_breach.duration, Duration::minutes(6));
+        assert_eq!(
+            sensor.time_in_breach(BreachType::HotConsecutive, None, None),
+            Duration::minutes(6)
+        );
+        assert_eq!(
+            sensor.time_in_breach(BreachType::HotCumulative, None, None),
+            Duration::zero()
+        );
+
+        // a second hot breach overlapping the last 2 minutes of the first isn't double counted
+        sensor.breaches.as_mut().unwrap().push(TemperatureBreach {
+            start_timestamp: hot_breach.start_timestamp + Duration::minutes(4),
+            end_timestamp: hot_breach.end_timestamp + Duration::minutes(4),
+            ..hot_breach.clone()
+        });
+        assert_eq!(
+            sensor.time_in_breach(BreachType::HotConsecutive, None, None),
+            Duration::minutes(10)
+        );
+
+        // clipped to the window
+        assert_eq!(
+            sensor.time_in_breach(
+                BreachType::HotConsecutive,
+                Some(hot_breach.start_timestamp + Duration::minutes(1)),
+                Some(hot_breach.end_timestamp),
+            ),
+            Duration::minutes(5)
+        );
+        assert_eq!(
+            sensor.time_in_breach(
+                BreachType::ColdConsecutive,
+                None,
+                Some(hot_breach.start_timestamp)
+            ),
+            Duration::zero()
+        );
+
+        // sub-second breaches are clipped in milliseconds, and zero-length ones are skipped
+        sensor.breaches = Some(vec![
+            TemperatureBreach {
+                end_timestamp: hot_breach.start_timestamp + Duration::seconds(1),
+                duration: Duration::seconds(1),
+                ..hot_breach.clone()
+            },
+            TemperatureBreach {
+                start_timestamp: hot_breach.end_timestamp,
+                duration: Duration::zero(),
+                ..hot_breach.clone()
+            },
+        ]);
+        assert_eq!(
+            sensor.time_in_breach(
+                BreachType::HotConsecutive,
+                Some(hot_breach.start_timestamp + Duration::milliseconds(500)),
+                None,
+            ),
+            Duration::milliseconds(500)
+        );
+    }
+
+    #[test]
+    fn test_detect_consecutive_breaches() {
+        let sensor = sample_sensor();
+        let logs = sensor.logs.unwrap();
+        let configs = sensor.configs.unwrap();
+        let mut breaches = common::detect_consecutive_breaches(&logs, &configs);
+        breaches.sort_by_key(|breach| breach.start_timestamp);
+
+        let expected = sensor.breaches.unwrap();
+        assert_eq!(breaches.len(), expected.len());
+        for (breach, expected) in breaches.iter().zip(expected.iter()) {
+            assert_eq!(breach.breach_type, expected.breach_type);
+            assert_eq!(breach.start_timestamp, expected.start_timestamp);
+            assert_eq!(breach.end_timestamp, expected.end_timestamp);
+            assert_eq!(breach.duration, expected.duration);
+        }
+
+        // the hot breach has 7 logs and the cold one 5
+        assert_eq!(
+            common::detect_consecutive_breaches_with_min_samples(&logs, &configs, 5).len(),
+            2
+        );
+        let breaches = common::detect_consecutive_breaches_with_min_samples(&logs, &configs, 6);
+        assert_eq!(breaches.len(), 1);
+        assert_eq!(breaches[0].breach_type, BreachType::HotConsecutive);
+    }
+
+    #[test]
+    fn test_detect_consecutive_breaches_min_samples() {
+        let start = NaiveDateTime::parse_from_str("2023-05-23 13:00", "%Y-%m-%d %H:%M").unwrap();
+        let log = |minutes: i64, temperature: f64| TemperatureLog {
+            temperature: Celsius(temperature),
+            timestamp: start + Duration::minutes(minutes),
+        };
+        // hot for 5 minutes above 8.0
+        let configs = sample_sensor().configs.unwrap();
+        // only two (sparse) breaching logs, but 10 minutes apart
+        let sparse_logs = vec![log(0, 5.0), log(1, 9.0), log(11, 9.5), log(12, 5.0)];
+
+        let breaches = common::detect_consecutive_breaches(&sparse_logs, &configs);
+        assert_eq!(breaches.len(), 1);
+        assert_eq!(breaches[0].duration, Duration::minutes(10));
+        assert_eq!(
+            common::detect_consecutive_breaches_with_min_samples(&sparse_logs, &configs, 2).len(),
+            1
+        );
+        assert!(
+            common::detect_consecutive_breaches_with_min_samples(&sparse_logs, &configs, 3)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_read_sensor_file_origin() {
+        let txt_file_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/FridgeTag 2L/130500109088_202206081014.txt"
+        );
+        let before = Local::now().naive_local();
+        let sensor = read_sensor_file(txt_file_path).unwrap();
+        assert_eq!(sensor.source_path.as_deref(), Some(txt_file_path));
+        let parsed_at = sensor.parsed_at.unwrap();
+        assert!(parsed_at >= before && parsed_at <= Local::now().naive_local());
+
+        let parsed_sensor = read_sensor_file_verbose(txt_file_path).unwrap();
+        assert_eq!(
+            parsed_sensor.sensor.source_path.as_deref(),
+            Some(txt_file_path)
+        );
+        assert!(parsed_sensor.sensor.parsed_at.is_some());
+
+        // the same sensor data, however long apart it's read
+        let mut reread_sensor = parsed_sensor.sensor;
+        reread_sensor.parsed_at = Some(parsed_at - Duration::days(1));
+        assert_eq!(reread_sensor.content_hash(), sensor.content_hash());
+    }
+
+    #[test]
+    fn test_read_sensor_file_verbose() {
+        let txt_file_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/FridgeTag 2L/130500109088_202206081014.txt"
+        );
+        let parsed_sensor = read_sensor_file_verbose(txt_file_path).unwrap();
+        assert_eq!(parsed_sensor.sensor.serial, "130500109088");
+        assert!(parsed_sensor.warnings.is_empty());
+
+        let dir = std::env::temp_dir().join("temperature_sensor_verbose");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let copied_file_path = dir.join("130500109088_202206081014.txt");
+        fs::copy(txt_file_path, &copied_file_path).unwrap();
+
+        let parsed_sensor = read_sensor_file_verbose(copied_file_path.to_str().unwrap()).unwrap();
+        assert_eq!(parsed_sensor.warnings, vec![ParseWarning::MissingPdf]);
+        assert_eq!(
+            parsed_sensor.warnings[0].to_string(),
+            "No matching PDF file found"
+        );
+
+        // FridgeTag 2 files don't record the logging interval
+        let parsed_sensor = read_sensor_file_verbose(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/FridgeTag 2/130400191542_202302140605.txt"
+        ))
+        .unwrap();
+        assert_eq!(
+            parsed_sensor.warnings,
+            vec![ParseWarning::MissingLogInterval]
+        );
+    }
+
+    #[test]
+    fn test_downsample_logs() {
+        let logs = sample_sensor().logs.unwrap();
+        assert_eq!(common::downsample_logs(&logs, 100).len(), logs.len());
+
+        let downsampled = common::downsample_logs(&logs, 6);
+        assert!(downsampled.len() <= 6);
+        assert!(downsampled
+            .windows(2)
+            .all(|pair| pair[0].timestamp < pair[1].timestamp));
+        // the hot breach peak (9.2) and cold breach trough (-0.2) are retained
+        assert!(downsampled
+            .iter()
+            .any(|log| log.temperature == Celsius(9.2)));
+        assert!(downsampled
+            .iter()
+            .any(|log| log.temperature == Celsius(-0.2)));
+
+        // never more than max_points
+        let downsampled = common::downsample_logs(&logs, 2);
+        let temperatures: Vec<Celsius> = downsampled.iter().map(|log| log.temperature).collect();
+        assert_eq!(temperatures, vec![Celsius(9.2), Celsius(-0.2)]);
+        let downsampled = common::downsample_logs(&logs, 1);
+        assert_eq!(downsampled.len(), 1);
+        assert_eq!(downsampled[0].timestamp, logs[0].timestamp);
+        assert!(common::downsample_logs(&logs, 0).is_empty());
+    }
+
+    #[test]
+    fn test_resample_logs() {
+        use common::ResampleMethod;
+
+        let timestamp = |time: &str| {
+            NaiveDateTime::parse_from_str(&format!("2023-05-23 {}", time), "%Y-%m-%d %H:%M")
+                .unwrap()
+        };
+        let log = |time: &str, temperature: f64| TemperatureLog {
+            temperature: Celsius(temperature),
+            timestamp: timestamp(time),
+        };
+        // 10 minute logs with a 40 minute gap before the last one
+        let logs = vec![
+            log("00:00", 2.0),
+            log("00:10", 4.0),
+            log("00:20", 8.0),
+            log("01:00", 6.0),
+        ];
+        let resampled = |logs: &[TemperatureLog], method: ResampleMethod| {
+            common::resample_logs(logs, Duration::minutes(15), method)
+                .into_iter()
+                .map(|log| (log.timestamp.format("%H:%M").to_string(), log.temperature.0))
+                .collect::<Vec<(String, f64)>>()
+        };
+        let points = |points: &[(&str, f64)]| {
+            points
+                .iter()
+                .map(|(time, temperature)| (time.to_string(), *temperature))
+                .collect::<Vec<(String, f64)>>()
+        };
+
+        assert_eq!(
+            resampled(&logs, ResampleMethod::CarryForward),
+            points(&[
+                ("00:00", 2.0),
+                ("00:15", 4.0),
+                ("00:30", 8.0),
+                ("00:45", 8.0),
+                ("01:00", 6.0)
+            ])
+        );
+        assert_eq!(
+            resampled(&logs, ResampleMethod::Linear),
+            points(&[
+                ("00:00", 2.0),
+                ("00:15", 6.0),
+                ("00:30", 7.5),
+                ("00:45", 6.75),
+                ("01:00", 6.0)
+            ])
+        );
+        // no log within 7.5 minutes of 00:30 or 00:45, and a tie goes to the earlier log
+        assert_eq!(
+            resampled(&logs, ResampleMethod::Nearest),
+            points(&[("00:00", 2.0), ("00:15", 4.0), ("01:00", 6.0)])
+        );
+
+        // the grid is aligned to the interval rather than the first log
+        assert_eq!(
+            resampled(&logs[1..], ResampleMethod::CarryForward),
+            points(&[
+                ("00:15", 4.0),
+                ("00:30", 8.0),
+                ("00:45", 8.0),
+                ("01:00", 6.0)
+            ])
+        );
+
+        assert!(resampled(&[], ResampleMethod::Linear).is_empty());
+        assert!(common::resample_logs(&logs, Duration::zero(), ResampleMethod::Linear).is_empty());
+    }
+
+    #[test]
+    fn test_recording_gaps() {
+        let sensor = sample_sensor();
+        let interval = sensor.log_interval.unwrap();
+        let mut logs = sensor.logs.unwrap();
+        assert!(common::recording_gaps(&logs, interval, Duration::seconds(30)).is_empty());
+
+        // 2 hour gap after the 10th log
+        let gap_start = logs[9].timestamp;
+        for log in logs.iter_mut().skip(10) {
+            log.timestamp += Duration::hours(2);
+        }
+        let gaps = common::recording_gaps(&logs, interval, Duration::seconds(30));
+        assert_eq!(
+            gaps,
+            vec![(gap_start, gap_start + interval + Duration::hours(2))]
+        );
+
+        // a tolerance larger than the gap ignores it
+        assert!(common::recording_gaps(&logs, interval, Duration::hours(3)).is_empty());
+    }
+
+    #[test]
+    fn test_detect_stuck_sensor() {
+        let mut logs = sample_sensor().logs.unwrap();
+        // the longest run of the same temperature is a single log
+        assert!(common::detect_stuck_sensor(&logs, 2).is_empty());
+
+        // flat line from the 3rd to the 8th log
+        for log in &mut logs[2..8] {
+            log.temperature = Celsius(5.0);
+        }
+        let flat_line = (logs[2].timestamp, logs[7].timestamp);
+        assert_eq!(common::detect_stuck_sensor(&logs, 6), vec![flat_line]);
+        assert!(common::detect_stuck_sensor(&logs, 7).is_empty());
+
+        // temperatures which only nearly match don't count
+        logs[5].temperature = Celsius(5.0 + f64::EPSILON * 4.0);
+        assert!(common::detect_stuck_sensor(&logs, 4).is_empty());
+        assert_eq!(
+            common::detect_stuck_sensor(&logs, 2),
+            vec![
+                (logs[2].timestamp, logs[4].timestamp),
+                (logs[6].timestamp, logs[7].timestamp)
+            ]
+        );
+
+        assert!(common::detect_stuck_sensor(&[], 1).is_empty());
+    }
+
+    #[test]
+    fn test_log_completeness() {
+        let start = NaiveDateTime::parse_from_str("2023-05-23 13:00", "%Y-%m-%d %H:%M").unwrap();
+        let minute = Duration::minutes(1);
+        assert_eq!(common::expected_log_count(start, start, minute), 1);
+        assert_eq!(
+            common::expected_log_count(start, start + minute * 18, minute),
+            19
+        );
+        // a partial interval at the end doesn't add a log
+        assert_eq!(
+            common::expected_log_count(start, start + Duration::seconds(150), minute),
+            3
+        );
+        assert_eq!(common::expected_log_count(start + minute, start, minute), 0);
+        assert_eq!(
+            common::expected_log_count(start, start + minute, Duration::zero()),
+            0
+        );
+
+        // the sample trip stops a minute after the 19th log, so one log is missing
+        let sensor = sample_sensor();
+        assert_eq!(sensor.log_completeness(), Some(19.0 / 20.0));
+        let mut sensor = Sensor {
+            stop_timestamp: None,
+            ..sensor
+        };
+        assert_eq!(sensor.log_completeness(), Some(1.0));
+
+        // every other log dropped, and a duplicated timestamp only counted once
+        let logs = sensor.logs.as_mut().unwrap();
+        *logs = logs.iter().step_by(2).cloned().collect();
+        logs.push(logs[0].clone());
+        assert_eq!(sensor.log_completeness(), Some(10.0 / 19.0));
+
+        sensor.log_interval = None;
+        assert_eq!(sensor.log_completeness(), None);
+        sensor.log_interval = Some(minute);
+        sensor.logs = None;
+        assert_eq!(sensor.log_completeness(), None);
+    }
+
+    #[test]
+    fn test_breaches_by_day() {
+        let mut sensor = sample_sensor();
+        let timestamp = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+        sensor.breaches = Some(vec![
+            TemperatureBreach {
+                breach_type: BreachType::HotConsecutive,
+                start_timestamp: timestamp("2023-05-23 22:00"),
+                end_timestamp: timestamp("2023-05-24 02:00"),
+                duration: Duration::hours(4),
+                acknowledged: false,
+                peak_temperature: None,
+                trigger_timestamp: None,
+                annotation: None,
+            },
+            TemperatureBreach {
+                breach_type: BreachType::ColdConsecutive,
+                start_timestamp: timestamp("2023-05-24 10:00"),
+                end_timestamp: timestamp("2023-05-25 00:00"), // ends at midnight -> one day
+                duration: Duration::hours(14),
+                acknowledged: false,
+                peak_temperature: None,
+                trigger_timestamp: None,
+                annotation: None,
+            },
+        ]);
+
+        let breaches_by_day = sensor.breaches_by_day();
+        let day = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        assert_eq!(
+            breaches_by_day.keys().copied().collect::<Vec<NaiveDate>>(),
+            vec![day("2023-05-23"), day("2023-05-24")]
+        );
+        let breach_types = |date: &str| -> Vec<BreachType> {
+            breaches_by_day[&day(date)]
+                .iter()
+                .map(|breach| breach.breach_type.clone())
+                .collect()
+        };
+        assert_eq!(breach_types("2023-05-23"), vec![BreachType::HotConsecutive]);
+        assert_eq!(
+            breach_types("2023-05-24"),
+            vec![BreachType::HotConsecutive, BreachType::ColdConsecutive]
+        );
+    }
+
+    #[test]
+    fn test_split_by_day() {
+        let mut sensor = sample_sensor();
+        let timestamp = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+        let log = |s: &str, temperature: f64| TemperatureLog {
+            temperature: Celsius(temperature),
+            timestamp: timestamp(s),
+        };
+        sensor.logs = Some(vec![
+            log("2023-05-23 20:00", 5.0),
+            log("2023-05-23 23:00", 9.0),
+            log("2023-05-24 01:00", 9.5),
+            log("2023-05-24 04:00", 6.0),
+        ]);
+        sensor.breaches = Some(vec![TemperatureBreach {
+            breach_type: BreachType::HotConsecutive,
+            start_timestamp: timestamp("2023-05-23 22:00"),
+            end_timestamp: timestamp("2023-05-24 02:00"),
+            duration: Duration::hours(4),
+            acknowledged: false,
+            peak_temperature: Some(Celsius(9.5)),
+            trigger_timestamp: None,
+            annotation: None,
+        }]);
+
+        let days = sensor.split_by_day();
+        let day = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        assert_eq!(
+            days.iter().map(|(day, _)| *day).collect::<Vec<NaiveDate>>(),
+            vec![day("2023-05-23"), day("2023-05-24")]
+        );
+        for (_, day_sensor) in &days {
+            assert_eq!(day_sensor.serial, "reg 1234");
+            assert_eq!(day_sensor.configs.as_ref().unwrap().len(), 2);
+        }
+
+        let log_timestamps = |sensor: &Sensor| -> Vec<NaiveDateTime> {
+            sensor
+                .logs
+                .iter()
+                .flatten()
+                .map(|log| log.timestamp)
+                .collect()
+        };
+        assert_eq!(
+            log_timestamps(&days[0].1),
+            vec![timestamp("2023-05-23 20:00"), timestamp("2023-05-23 23:00")]
+        );
+        assert_eq!(
+            log_timestamps(&days[1].1),
+            vec![timestamp("2023-05-24 01:00"), timestamp("2023-05-24 04:00")]
+        );
+
+        // the breach is clipped at midnight
+        let first_breach = &days[0].1.breaches.as_ref().unwrap()[0];
+        assert_eq!(first_breach.start_timestamp, timestamp("2023-05-23 22:00"));
+        assert_eq!(first_breach.end_timestamp, timestamp("2023-05-24 00:00"));
+        assert_eq!(first_breach.duration, Duration::hours(2));
+        let second_breach = &days[1].1.breaches.as_ref().unwrap()[0];
+        assert_eq!(second_breach.start_timestamp, timestamp("2023-05-24 00:00"));
+        assert_eq!(second_breach.end_timestamp, timestamp("2023-05-24 02:00"));
+        assert_eq!(second_breach.duration, Duration::hours(2));
+    }
+
+    #[test]
+    fn test_find_sensor_by_serial() {
+        let sensors = || vec![sample_sensor()]; // serial "reg 1234"
+        for serial in ["reg 1234", "REG 1234", " reg 1234 "] {
+            let sensor = find_sensor_by_serial(sensors(), serial);
+            assert_eq!(
+                sensor.map(|sensor| sensor.serial),
+                Some("reg 1234".to_string())
+            );
+        }
+        assert!(find_sensor_by_serial(sensors(), "reg 12345").is_none());
+        assert!(find_sensor_by_serial(sensors(), "reg1234").is_none());
+    }
+
+    #[test]
+    fn test_counts_and_date_range() {
+        let sensor = sample_sensor();
+        assert_eq!(sensor.log_count(), 19);
+        assert_eq!(sensor.breach_count(), 2);
+        assert_eq!(sensor.config_count(), 2);
+        let start = NaiveDateTime::parse_from_str("2023-05-23 13:00", "%Y-%m-%d %H:%M").unwrap();
+        assert_eq!(
+            sensor.date_range(),
+            Some((start, start + Duration::minutes(18)))
+        );
+
+        let empty_sensor = Sensor {
+            logs: None,
+            breaches: None,
+            configs: None,
+            ..sample_sensor()
+        };
+        assert_eq!(empty_sensor.log_count(), 0);
+        assert_eq!(empty_sensor.breach_count(), 0);
+        assert_eq!(empty_sensor.config_count(), 0);
+        assert_eq!(empty_sensor.date_range(), None);
+    }
+
+    #[test]
+    fn test_breach_counts() {
+        let sensor = sample_sensor();
+        assert_eq!(
+            sensor.breach_counts().into_iter().collect::<Vec<_>>(),
+            vec![
+                (BreachType::HotConsecutive, 1),
+                (BreachType::ColdConsecutive, 1),
+                (BreachType::HotCumulative, 0),
+                (BreachType::ColdCumulative, 0),
+            ]
+        );
+
+        let empty_sensor = Sensor {
+            breaches: None,
+            ..sensor
+        };
+        assert_eq!(empty_sensor.breach_counts().len(), 4);
+        assert!(empty_sensor
+            .breach_counts()
+            .values()
+            .all(|count| *count == 0));
+    }
+
+    #[test]
+    fn test_summary() {
+        let mut sensor = sample_sensor();
+        let summary = sensor.summary();
+        assert_eq!(summary.serial, "reg 1234");
+        assert_eq!(summary.name, "Berlinger 1");
+        assert!(matches!(summary.sensor_type, SensorType::Berlinger(_)));
+        assert_eq!(
+            summary.last_connected_timestamp,
+            sensor.last_connected_timestamp
+        );
+        assert_eq!(summary.log_count, 19);
+        assert_eq!(summary.breach_count, 2);
+        assert_eq!(summary.unacknowledged_breach_count, 2);
+        assert_eq!(summary.min_temperature, Some(Celsius(-0.2)));
+        assert_eq!(summary.max_temperature, Some(Celsius(9.2)));
+        assert_eq!(
+            summary.min_temperature.zip(summary.max_temperature),
+            sensor.extreme_temperatures()
+        );
+
+        let first_start = sensor.breaches.as_ref().unwrap()[0].start_timestamp;
+        sensor.acknowledge_breach(first_start);
+        sensor.logs = None;
+        let summary = sensor.summary();
+        assert_eq!(summary.breach_count, 2);
+        assert_eq!(summary.unacknowledged_breach_count, 1);
+        assert_eq!(summary.log_count, 0);
+        assert_eq!(summary.min_temperature, None);
+        assert_eq!(summary.max_temperature, None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_sensor_type_json() {
+        let sensor_type = SensorType::Berlinger(Some(BerlingerModel::FridgeTag2));
+        let json = serde_json::to_string(&sensor_type).unwrap();
+        assert_eq!(json, r#"{"Berlinger":"FridgeTag2"}"#);
+        assert_eq!(
+            serde_json::to_string(&SensorType::Berlinger(None)).unwrap(),
+            r#"{"Berlinger":null}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<SensorType>(&json).unwrap(),
+            sensor_type
+        );
+        // the 0.2 format (see the migration notes in the README)
+        assert!(serde_json::from_str::<SensorType>(r#""Berlinger""#).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_to_summary_json() {
+        let summary: serde_json::Value =
+            serde_json::from_str(&sample_sensor().to_summary_json()).unwrap();
+        assert_eq!(summary["serial"], "reg 1234");
+        assert_eq!(summary["last_connected_timestamp"], "2023-05-23T13:19:00");
+        assert_eq!(summary["log_count"], 19);
+        assert_eq!(summary["unacknowledged_breach_count"], 2);
+        assert_eq!(summary["max_temperature"], 9.2);
+        assert!(summary.get("logs").is_none());
+    }
+
+    #[test]
+    fn test_correct_cumulative_breaches() {
+        let timestamp = |time: &str| {
+            NaiveDateTime::parse_from_str(&format!("2023-05-23 {}", time), "%Y-%m-%d %H:%M")
+                .unwrap()
+        };
+        let log = |time: &str, temperature: f64| TemperatureLog {
+            temperature: Celsius(temperature),
+            timestamp: timestamp(time),
+        };
+        // breaching logs 8 minutes after and 10 minutes before midnight (i.e. more than
+        // one but less than two log intervals)
+        let sensor = Sensor {
+            log_interval: Some(Duration::minutes(5)),
+            configs: Some(vec![TemperatureBreachConfig {
+                breach_type: BreachType::HotCumulative,
+                maximum_temperature: Celsius(8.0),
+                minimum_temperature: Celsius(-273.0),
+                duration: Duration::minutes(60),
+            }]),
+            breaches: Some(vec![TemperatureBreach {
+                breach_type: BreachType::HotCumulative,
+                start_timestamp: timestamp("06:00"),
+                end_timestamp: timestamp("07:00"),
+                duration: Duration::minutes(60),
+                acknowledged: false,
+                peak_temperature: None,
+                trigger_timestamp: None,
+                annotation: None,
+            }]),
+            logs: Some(vec![
+                log("00:08", 9.0),
+                log("12:00", 5.0),
+                log("23:50", 9.5),
+            ]),
+            ..sample_sensor()
+        };
+        let corrected = |mut sensor: Sensor, tolerance: Option<f64>| {
+            match tolerance {
+                Some(tolerance) => sensor.correct_cumulative_breaches_with_tolerance(tolerance),
+                None => sensor.correct_cumulative_breaches(),
+            }
+            let breach = sensor.breaches.unwrap().remove(0);
+            (breach.start_timestamp, breach.end_timestamp)
+        };
+
+        let next_midnight = timestamp("00:00") + Duration::days(1);
+        assert_eq!(
+            corrected(sensor.clone(), None),
+            (timestamp("00:08"), timestamp("23:50"))
+        );
+        assert_eq!(
+            corrected(sensor.clone(), Some(1.0)),
+            (timestamp("00:08"), timestamp("23:50"))
+        );
+        assert_eq!(
+            corrected(sensor.clone(), Some(2.0)),
+            (timestamp("00:00"), next_midnight)
+        );
+
+        // within one log interval of midnight, so only snapped if tolerance isn't 0
+        let mut near_midnight = sensor.clone();
+        near_midnight.logs = Some(vec![log("00:03", 9.0), log("23:56", 9.5)]);
+        assert_eq!(
+            corrected(near_midnight.clone(), Some(1.0)),
+            (timestamp("00:00"), next_midnight)
+        );
+        assert_eq!(
+            corrected(near_midnight, Some(0.0)),
+            (timestamp("00:03"), timestamp("23:56"))
+        );
+
+        let mut corrected_sensor = sensor;
+        corrected_sensor.correct_cumulative_breaches();
+        let breach = &corrected_sensor.breaches.unwrap()[0];
+        assert_eq!(breach.duration, Duration::minutes(60));
+        assert_eq!(breach.peak_temperature, Some(Celsius(9.5)));
+    }
+
+    #[test]
+    fn test_snap_breach_endpoints_to_logs() {
+        let mut sensor = sample_sensor();
+        let logged_breaches = sensor.breaches.clone().unwrap();
+        // worked out breaches ending and starting between the (minutely) logs
+        for breach in sensor.breaches.iter_mut().flatten() {
+            breach.start_timestamp += Duration::seconds(20);
+            breach.end_timestamp += Duration::seconds(20);
+        }
+        sensor.breaches.as_mut().unwrap().push(TemperatureBreach {
+            breach_type: BreachType::HotCumulative,
+            start_timestamp: logged_breaches[0].start_timestamp - Duration::seconds(40),
+            end_timestamp: logged_breaches[0].end_timestamp + Duration::seconds(40),
+            duration: Duration::minutes(8),
+            acknowledged: false,
+            peak_temperature: None,
+            trigger_timestamp: None,
+            annotation: None,
+        });
+        sensor.normalize();
+        let unsnapped = sensor.breaches.clone().unwrap();
+
+        sensor.snap_breach_endpoints_to_logs();
+        let snapped = sensor.breaches.unwrap();
+        let endpoints = |breaches: &[TemperatureBreach]| {
+            breaches
+                .iter()
+                .map(|breach| {
+                    (
+                        breach.breach_type.clone(),
+                        breach.start_timestamp,
+                        breach.end_timestamp,
+                        breach.duration,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let time = |time: &str| {
+            NaiveDateTime::parse_from_str(&format!("2023-05-23 {}", time), "%Y-%m-%d %H:%M:%S")
+                .unwrap()
+        };
+        assert_eq!(
+            endpoints(&unsnapped),
+            vec![
+                (
+                    BreachType::HotCumulative,
+                    time("13:03:20"),
+                    time("13:10:40"),
+                    Duration::minutes(8)
+                ),
+                (
+                    BreachType::HotConsecutive,
+                    time("13:04:20"),
+                    time("13:10:20"),
+                    Duration::minutes(6)
+                ),
+                (
+                    BreachType::ColdConsecutive,
+                    time("13:13:20"),
+                    time("13:17:20"),
+                    Duration::minutes(4)
+                ),
+            ]
+        );
+        // consecutive breaches lose the time before their first log and after their last,
+        // whereas cumulative ones keep their recorded duration
+        assert_eq!(
+            endpoints(&snapped),
+            vec![
+                (
+                    BreachType::HotCumulative,
+                    time("13:04:00"),
+                    time("13:10:00"),
+                    Duration::minutes(8)
+                ),
+                (
+                    BreachType::HotConsecutive,
+                    time("13:05:00"),
+                    time("13:10:00"),
+                    Duration::minutes(5)
+                ),
+                (
+                    BreachType::ColdConsecutive,
+                    time("13:14:00"),
+                    time("13:17:00"),
+                    Duration::minutes(3)
+                ),
+            ]
+        );
+
+        // breaches without logs are left as they are
+        let mut sensor = sample_sensor();
+        sensor.logs = None;
+        let unsnapped = sensor.breaches.clone().unwrap();
+        sensor.snap_breach_endpoints_to_logs();
+        assert_eq!(
+            format!("{:?}", sensor.breaches.unwrap()),
+            format!("{:?}", unsnapped)
+        );
+    }
+
+    #[test]
+    fn test_reclassify_continuous_cumulative_breaches() {
+        let timestamp = |time: &str| {
+            NaiveDateTime::parse_from_str(&format!("2023-05-23 {}", time), "%Y-%m-%d %H:%M")
+                .unwrap()
+        };
+        let log = |time: &str, temperature: f64| TemperatureLog {
+            temperature: Celsius(temperature),
+            timestamp: timestamp(time),
+        };
+        let breach = |breach_type: BreachType, start: &str, end: &str| TemperatureBreach {
+            breach_type,
+            start_timestamp: timestamp(start),
+            end_timestamp: timestamp(end),
+            // cumulative, so shorter than end - start
+            duration: Duration::minutes(10),
+            acknowledged: false,
+            peak_temperature: None,
+            trigger_timestamp: None,
+            annotation: None,
+        };
+        let sensor = Sensor {
+            configs: Some(vec![
+                TemperatureBreachConfig {
+                    breach_type: BreachType::HotCumulative,
+                    maximum_temperature: Celsius(8.0),
+                    minimum_temperature: Celsius(-273.0),
+                    duration: Duration::minutes(10),
+                },
+                TemperatureBreachConfig {
+                    breach_type: BreachType::ColdCumulative,
+                    maximum_temperature: Celsius(100.0),
+                    minimum_temperature: Celsius(2.0),
+                    duration: Duration::minutes(10),
+                },
+            ]),
+            breaches: Some(vec![
+                breach(BreachType::HotCumulative, "06:00", "06:15"), // continuous
+                breach(BreachType::HotCumulative, "08:00", "08:15"), // gap at 08:05
+                breach(BreachType::ColdCumulative, "10:00", "10:15"), // no logs
+            ]),
+            logs: Some(vec![
+                log("06:00", 9.0),
+                log("06:05", 9.5),
+                log("06:10", 8.5),
+                log("06:15", 8.1),
+                log("08:00", 9.0),
+                log("08:05", 8.0),
+                log("08:10", 9.0),
+                log("08:15", 9.0),
+            ]),
+            ..sample_sensor()
+        };
+
+        let mut reclassified = sensor.clone();
+        reclassified.reclassify_continuous_cumulative_breaches();
+        let breach_types = |sensor: &Sensor| {
+            sensor
+                .breaches
+                .iter()
+                .flatten()
+                .map(|breach| breach.breach_type.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            breach_types(&reclassified),
+            vec![
+                BreachType::HotConsecutive,
+                BreachType::HotCumulative,
+                BreachType::ColdCumulative
+            ]
+        );
+        let continuous_breach = &reclassified.breaches.as_ref().unwrap()[0];
+        assert_eq!(continuous_breach.start_timestamp, timestamp("06:00"));
+        assert_eq!(continuous_breach.end_timestamp, timestamp("06:15"));
+        assert_eq!(continuous_breach.duration, Duration::minutes(15));
+        let gap_breach = &reclassified.breaches.as_ref().unwrap()[1];
+        assert_eq!(gap_breach.duration, Duration::minutes(10));
+
+        // logs only breaching a config of another type are gaps too
+        let mut sensor = sensor;
+        sensor.breaches = Some(vec![breach(BreachType::ColdCumulative, "06:00", "06:15")]);
+        sensor.reclassify_continuous_cumulative_breaches();
+        assert_eq!(breach_types(&sensor), vec![BreachType::ColdCumulative]);
+    }
+
+    #[test]
+    fn test_breach_severity() {
+        let sensor = sample_sensor();
+        let configs = sensor.configs.as_deref().unwrap();
+        let (cold_config, hot_config) = (&configs[0], &configs[1]);
+        let breaches = sensor.breaches.as_deref().unwrap();
+        let (hot_breach, cold_breach) = (&breaches[0], &breaches[1]);
+
+        // 9.2 is 1.2 above 8.0 for 6 minutes, -0.2 is 2.2 below 2.0 for 4 minutes
+        assert_eq!(hot_breach.severity(hot_config), Severity::Minor);
+        assert_eq!(cold_breach.severity(cold_config), Severity::Major);
+
+        let peak_breach = |peak: f64, minutes: i64| TemperatureBreach {
+            peak_temperature: Some(Celsius(peak)),
+            duration: Duration::minutes(minutes),
+            ..hot_breach.clone()
+        };
+        assert_eq!(peak_breach(10.0, 10).severity(hot_config), Severity::Major);
+        assert_eq!(
+            peak_breach(13.0, 10).severity(hot_config),
+            Severity::Critical
+        );
+        assert_eq!(
+            peak_breach(9.0, 4 * 60).severity(hot_config),
+            Severity::Major
+        );
+        assert_eq!(
+            peak_breach(9.0, 24 * 60).severity(hot_config),
+            Severity::Critical
+        );
+
+        // without a peak temperature, only the duration counts
+        let duration_breach = |minutes: i64| TemperatureBreach {
+            peak_temperature: None,
+            duration: Duration::minutes(minutes),
+            ..cold_breach.clone()
+        };
+        assert_eq!(duration_breach(60).severity(cold_config), Severity::Minor);
+        assert_eq!(
+            duration_breach(5 * 60).severity(cold_config),
+            Severity::Major
+        );
+        assert_eq!(
+            duration_breach(25 * 60).severity(cold_config),
+            Severity::Critical
+        );
+
+        let strict_thresholds = SeverityThresholds {
+            major_excess: 1.0,
+            critical_excess: 1.5,
+            ..SeverityThresholds::default()
+        };
+        assert_eq!(
+            hot_breach.severity_with_thresholds(hot_config, &strict_thresholds),
+            Severity::Major
+        );
+        assert_eq!(
+            cold_breach.severity_with_thresholds(cold_config, &strict_thresholds),
+            Severity::Critical
+        );
+    }
+
+    #[test]
+    fn test_config_for_breach() {
+        let mut sensor = sample_sensor();
+        let breaches = sensor.breaches.clone().unwrap();
+        for breach in &breaches {
+            let config = sensor.config_for_breach(breach).unwrap();
+            assert_eq!(config.breach_type, breach.breach_type);
+        }
+        let hot_breach = breaches
+            .iter()
+            .find(|breach| breach.breach_type == BreachType::HotConsecutive)
+            .unwrap();
+        let cold_breach = breaches
+            .iter()
+            .find(|breach| breach.breach_type == BreachType::ColdConsecutive)
+            .unwrap();
+        assert_eq!(
+            sensor
+                .config_for_breach(hot_breach)
+                .unwrap()
+                .maximum_temperature,
+            Celsius(8.0)
+        );
+        assert_eq!(
+            sensor
+                .config_for_breach(cold_breach)
+                .unwrap()
+                .minimum_temperature,
+            Celsius(2.0)
+        );
+
+        // no config of the breach type, or none at all
+        sensor.configs.as_mut().unwrap().remove(0);
+        assert!(sensor.config_for_breach(cold_breach).is_none());
+        sensor.configs = None;
+        assert!(sensor.config_for_breach(hot_breach).is_none());
+    }
+
+    #[test]
+    fn test_breach_duration_rounding() {
+        let breach = |duration: Duration| TemperatureBreach {
+            duration,
+            ..sample_sensor().breaches.unwrap()[0].clone()
+        };
+
+        let five_fifty_nine = breach(Duration::minutes(5) + Duration::seconds(59));
+        assert_eq!(five_fifty_nine.duration_minutes(), 6);
+        assert_eq!(
+            five_fifty_nine.duration_rounded(Duration::minutes(5)),
+            Duration::minutes(5)
+        );
+        assert_eq!(breach(Duration::seconds(329)).duration_minutes(), 5);
+        assert_eq!(breach(Duration::seconds(330)).duration_minutes(), 6);
+        assert_eq!(
+            breach(Duration::seconds(449)).duration_rounded(Duration::minutes(5)),
+            Duration::minutes(5)
+        );
+        assert_eq!(
+            breach(Duration::seconds(450)).duration_rounded(Duration::minutes(5)),
+            Duration::minutes(10)
+        );
+        assert_eq!(
+            five_fifty_nine.duration_rounded(Duration::zero()),
+            five_fifty_nine.duration
+        );
+    }
+
+    #[test]
+    fn test_breach_exceeds_config() {
+        let sensor = sample_sensor();
+        let hot_config = &sensor.configs.as_deref().unwrap()[1];
+        assert_eq!(hot_config.alarm_delay(), Duration::seconds(300));
+
+        let breach = |duration: Duration| TemperatureBreach {
+            duration,
+            ..sensor.breaches.as_deref().unwrap()[0].clone()
+        };
+        assert!(breach(Duration::seconds(300)).exceeds_config(hot_config)); // exactly at the threshold
+        assert!(breach(Duration::seconds(301)).exceeds_config(hot_config));
+        assert!(!breach(Duration::seconds(299)).exceeds_config(hot_config));
+    }
+
+    #[test]
+    fn test_breaches_sorted_by_severity() {
+        let mut sensor = sample_sensor();
+        let configs = sensor.configs.clone().unwrap();
+        let breaches = sensor.breaches.clone().unwrap();
+        let (hot_breach, cold_breach) = (&breaches[0], &breaches[1]);
+        let later = |breach: &TemperatureBreach, hours: i64, minutes: i64| TemperatureBreach {
+            start_timestamp: breach.start_timestamp + Duration::hours(hours),
+            end_timestamp: breach.start_timestamp
+                + Duration::hours(hours)
+                + Duration::minutes(minutes),
+            duration: Duration::minutes(minutes),
+            ..breach.clone()
+        };
+        let hot_cumulative = TemperatureBreach {
+            breach_type: BreachType::HotCumulative,
+            peak_temperature: None,
+            ..later(hot_breach, 2, 25 * 60)
+        };
+        sensor.breaches = Some(vec![
+            hot_breach.clone(),         // minor, 6 minutes
+            cold_breach.clone(),        // major (2.2 below), 4 minutes
+            later(hot_breach, 1, 6),    // minor, 6 minutes, later
+            later(hot_breach, 3, 10),   // minor, 10 minutes
+            later(cold_breach, 4, 300), // major, 5 hours
+            hot_cumulative,             // critical on duration, without a config
+        ]);
+
+        let sorted: Vec<(BreachType, NaiveDateTime)> = sensor
+            .breaches_sorted_by_severity(&configs)
+            .into_iter()
+            .map(|breach| (breach.breach_type.clone(), breach.start_timestamp))
+            .collect();
+        let start = |breach: &TemperatureBreach, hours: i64| {
+            breach.start_timestamp + Duration::hours(hours)
+        };
+        assert_eq!(
+            sorted,
+            vec![
+                (BreachType::HotCumulative, start(hot_breach, 2)),
+                (BreachType::ColdConsecutive, start(cold_breach, 4)),
+                (BreachType::ColdConsecutive, start(cold_breach, 0)),
+                (BreachType::HotConsecutive, start(hot_breach, 3)),
+                (BreachType::HotConsecutive, start(hot_breach, 0)),
+                (BreachType::HotConsecutive, start(hot_breach, 1)),
+            ]
+        );
+
+        sensor.breaches = None;
+        assert!(sensor.breaches_sorted_by_severity(&configs).is_empty());
+    }
+
+    #[test]
+    fn test_breaching_spans() {
+        let sensor = sample_sensor();
+        let logs = sensor.logs.as_deref().unwrap();
+        let configs = sensor.configs.as_deref().unwrap();
+        let timestamp = |minutes: i64| logs[0].timestamp + Duration::minutes(minutes);
+        let spans = |config: &TemperatureBreachConfig| {
+            common::breaching_spans(logs, config)
+                .map(|(first, last)| (first.timestamp, last.timestamp))
+                .collect::<Vec<_>>()
+        };
+
+        // configs are cold then hot
+        assert_eq!(spans(&configs[0]), vec![(timestamp(13), timestamp(17))]);
+        assert_eq!(spans(&configs[1]), vec![(timestamp(4), timestamp(10))]);
+
+        // spans are returned however short, even a single log
+        let strict_config = TemperatureBreachConfig {
+            maximum_temperature: Celsius(9.0),
+            ..configs[1].clone()
+        };
+        assert_eq!(
+            spans(&strict_config),
+            vec![(timestamp(5), timestamp(5)), (timestamp(7), timestamp(7))]
+        );
+        assert!(common::breaching_spans(&[], &strict_config)
+            .next()
+            .is_none());
+    }
+
+    #[test]
+    fn test_near_miss_spans() {
+        let sensor = sample_sensor();
+        let logs = sensor.logs.as_deref().unwrap();
+        let configs = sensor.configs.as_deref().unwrap();
+        let timestamp = |minutes: i64| logs[0].timestamp + Duration::minutes(minutes);
+
+        // 7.5 and 7.9 are within a degree of the hot limit of 8.0, but the logs above it
+        // are breaches rather than near misses
+        assert_eq!(
+            common::near_miss_spans(logs, &configs[1], 1.0),
+            vec![(timestamp(3), timestamp(3)), (timestamp(11), timestamp(11))]
+        );
+        // 2.5 is within a degree of the cold limit of 2.0
+        assert_eq!(
+            common::near_miss_spans(logs, &configs[0], 1.0),
+            vec![(timestamp(18), timestamp(18))]
+        );
+        // a wider margin joins up the runs (3.5, 4.0, 5.0, 7.5)
+        assert_eq!(
+            common::near_miss_spans(logs, &configs[1], 5.0),
+            vec![(timestamp(0), timestamp(3)), (timestamp(11), timestamp(12))]
+        );
+        assert!(common::near_miss_spans(logs, &configs[1], 0.0).is_empty());
+        assert!(common::near_miss_spans(&[], &configs[1], 1.0).is_empty());
+    }
+
+    #[test]
+    fn test_breaches_to_ics() {
+        let mut sensor = sample_sensor();
+        let ics = common::breaches_to_ics(&sensor);
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT\r\n").count(), 2);
+        assert_eq!(ics.matches("END:VEVENT\r\n").count(), 2);
+        // floating times, as the sensor's timezone isn't known
+        assert!(ics.contains("\r\nDTSTART:20230523T130400\r\nDTEND:20230523T131000\r\n"));
+        // but the DTSTAMP is always UTC, so the recorded time is taken as UTC
+        let last_connected = sensor.last_connected_timestamp.unwrap();
+        assert!(ics.contains(&format!(
+            "\r\nDTSTAMP:{}Z\r\n",
+            last_connected.format("%Y%m%dT%H%M%S")
+        )));
+        assert!(ics.contains(
+            "\r\nSUMMARY:Hot breach: 9.2°C for 6 min from 2023-05-23 13:04 to 13:10\r\n"
+        ));
+        assert!(ics.lines().all(|line| line.len() <= 75));
+
+        // UTC times when the timezone is known (13:04 at UTC+05:30 is 07:34 UTC)
+        sensor.timezone = chrono::FixedOffset::east_opt(5 * 3600 + 30 * 60);
+        let ics = common::breaches_to_ics(&sensor);
+        assert!(ics.contains("\r\nDTSTART:20230523T073400Z\r\n"));
+        let dtstamp = last_connected - Duration::minutes(5 * 60 + 30);
+        assert!(ics.contains(&format!(
+            "\r\nDTSTAMP:{}Z\r\n",
+            dtstamp.format("%Y%m%dT%H%M%S")
+        )));
+
+        sensor.breaches = None;
+        let ics = common::breaches_to_ics(&sensor);
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 0);
+        assert!(ics.contains("BEGIN:VCALENDAR"));
+    }
+
+    #[test]
+    fn test_compliance_percentage() {
+        let mut sensor = sample_sensor();
+        let start = NaiveDateTime::parse_from_str("2023-05-23 13:00", "%Y-%m-%d %H:%M").unwrap();
+
+        // 10 minutes of breaches in 18 minutes of logs
+        let percentage = sensor.compliance_percentage(None, None).unwrap();
+        assert!((percentage - 100.0 * 8.0 / 18.0).abs() < 1e-9);
+
+        // only the 6 minute hot breach is in the first 10 minutes
+        let window_end = Some(start + Duration::minutes(10));
+        assert_eq!(sensor.compliance_percentage(None, window_end), Some(40.0));
+
+        // an overlapping breach of another type isn't counted twice
+        if let Some(breaches) = &mut sensor.breaches {
+            let hot_breach = breaches[0].clone();
+            breaches.push(TemperatureBreach {
+                breach_type: BreachType::HotCumulative,
+                ..hot_breach
+            });
+        }
+        assert_eq!(sensor.compliance_percentage(None, window_end), Some(40.0));
+
+        // no monitored time outside the logs
+        let after_logs = Some(start + Duration::hours(1));
+        assert_eq!(sensor.compliance_percentage(after_logs, None), None);
+        sensor.logs = None;
+        assert_eq!(sensor.compliance_percentage(None, None), None);
+    }
+
+    #[test]
+    fn test_collapse_same_timestamp() {
+        use common::ExtremePreference;
+
+        let start = NaiveDateTime::parse_from_str("2023-05-22 00:00", "%Y-%m-%d %H:%M").unwrap();
+        let log = |minutes: i64, temperature: f64| TemperatureLog {
+            temperature: Celsius(temperature),
+            timestamp: start + Duration::minutes(minutes),
+        };
+        // daily max & min pairs as from a Fridge-tag 2, one pair in the same minute
+        let logs = vec![
+            log(0, 6.2),
+            log(0, 3.1),
+            log(10, 5.0),
+            log(20, 4.0),
+            log(20, 4.0),
+        ];
+        let collapsed = |keep| {
+            common::collapse_same_timestamp(logs.clone(), keep)
+                .iter()
+                .map(|log| (log.timestamp - start, log.temperature.0))
+                .collect::<Vec<_>>()
+        };
+        let minutes = Duration::minutes;
+
+        assert_eq!(
+            collapsed(ExtremePreference::Min),
+            vec![(minutes(0), 3.1), (minutes(10), 5.0), (minutes(20), 4.0)]
+        );
+        assert_eq!(
+            collapsed(ExtremePreference::Max),
+            vec![(minutes(0), 6.2), (minutes(10), 5.0), (minutes(20), 4.0)]
+        );
+        assert_eq!(
+            collapsed(ExtremePreference::Both),
+            vec![
+                (minutes(0), 3.1),
+                (minutes(0), 6.2),
+                (minutes(10), 5.0),
+                (minutes(20), 4.0)
+            ]
+        );
+        assert!(common::collapse_same_timestamp(Vec::new(), ExtremePreference::Both).is_empty());
+    }
+
+    #[test]
+    fn test_breach_summaries() {
+        let mut sensor = sample_sensor();
+        let hot_summary = "Hot breach: 9.2°C for 6 min from 2023-05-23 13:04 to 13:10";
+        let cold_summary = "Cold breach: -0.2°C for 4 min from 2023-05-23 13:13 to 13:17";
+        let breaches = sensor.breaches.clone().unwrap();
+        assert_eq!(breaches[0].summary(), hot_summary);
+        assert_eq!(breaches[1].summary(), cold_summary);
+
+        let cumulative_breach = TemperatureBreach {
+            breach_type: BreachType::ColdCumulative,
+            end_timestamp: breaches[1].start_timestamp + Duration::days(1),
+            duration: Duration::minutes(75),
+            peak_temperature: None,
+            trigger_timestamp: None,
+            annotation: None,
+            ..breaches[1].clone()
+        };
+        assert_eq!(
+            cumulative_breach.summary(),
+            "Cold cumulative breach for 1 h 15 min from 2023-05-23 13:13 to 2023-05-24 13:13"
+        );
+        let rounded_breach = TemperatureBreach {
+            duration: Duration::seconds(59 * 60 + 30),
+            ..cumulative_breach
+        };
+        assert!(rounded_breach.summary().contains(" for 1 h 0 min "));
+
+        assert_eq!(
+            sensor.alert_summary(),
+            format!("{}\n{}", hot_summary, cold_summary)
+        );
+        sensor.acknowledge_breach(breaches[0].start_timestamp);
+        assert_eq!(sensor.alert_summary(), cold_summary);
+        sensor.acknowledge_all();
+        assert_eq!(sensor.alert_summary(), "");
+    }
+
+    #[test]
+    fn test_concurrent_usb_scans() {
+        let scans: Vec<std::thread::JoinHandle<_>> = (0..2)
+            .map(|_| std::thread::spawn(read_connected_serials))
+            .collect();
+        let results: Vec<Result<Vec<String>, TemperatureSensorError>> = scans
+            .into_iter()
+            .map(|scan| scan.join().expect("USB scan panicked"))
+            .collect();
+
+        // both scans see the same drives (usually none on a build machine)
+        assert_eq!(results[0], results[1]);
+    }
+
+    #[test]
+    fn test_concurrent_sensor_file_reads() {
+        let dir = std::env::temp_dir().join("temperature_sensor_concurrent_reads");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let contents = fs::read_to_string(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/FridgeTag 2/130400191542_202302140605.txt"
+        ))
+        .unwrap();
+
+        // the same serial in each file, as when re-reading a sensor
+        let file_paths: Vec<String> = (0..4)
+            .map(|index| {
+                let file_path = dir.join(format!("concurrent_{}.txt", index));
+                let serial = format!("CONCURRENT-{}", index % 2);
+                fs::write(&file_path, contents.replace("130400191542", &serial)).unwrap();
+                file_path.to_string_lossy().to_string()
+            })
+            .collect();
+        let reads: Vec<std::thread::JoinHandle<_>> = file_paths
+            .iter()
+            .cloned()
+            .map(|file_path| std::thread::spawn(move || read_sensor_file(&file_path)))
+            .collect();
+        let serials: Vec<String> = reads
+            .into_iter()
+            .map(|read| read.join().expect("read panicked").unwrap().serial)
+            .collect();
+        assert_eq!(
+            serials,
+            vec![
+                "CONCURRENT-0",
+                "CONCURRENT-1",
+                "CONCURRENT-0",
+                "CONCURRENT-1"
+            ]
+        );
+
+        // nothing is written outside the sensor files
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), file_paths.len());
+        for serial in ["CONCURRENT-0", "CONCURRENT-1"] {
+            assert!(!Path::new(&format!("sensor_{}_output.txt", serial)).exists());
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_filter_sensor_strict() {
+        // the hot breach runs 13:04 - 13:10 with a 5 minute config, the cold one
+        // 13:13 - 13:17 with a 4 minute config
+        let start = NaiveDateTime::parse_from_str("2023-05-23 13:00", "%Y-%m-%d %H:%M").unwrap();
+        let window_start = Some(start + Duration::minutes(7));
+
+        // clipped to 3 minutes, so no longer a hot breach
+        let sensor = filter_sensor(sample_sensor(), window_start, None);
+        assert_eq!(sensor.breach_count(), 2);
+        let sensor = filter_sensor_strict(sample_sensor(), window_start, None, None);
+        assert_eq!(sensor.breach_count(), 1);
+        let breaches = sensor.breaches.unwrap();
+        assert_eq!(breaches[0].breach_type, BreachType::ColdConsecutive);
+
+        // a shorter config keeps the clipped breach
+        let lenient_configs = vec![TemperatureBreachConfig {
+            duration: Duration::minutes(2),
+            ..sample_sensor().configs.unwrap()[1].clone()
+        }];
+        let sensor =
+            filter_sensor_strict(sample_sensor(), window_start, None, Some(&lenient_configs));
+        assert_eq!(sensor.breach_count(), 2);
+        let breaches = sensor.breaches.unwrap();
+        assert_eq!(breaches[0].start_timestamp, start + Duration::minutes(7)); // clipped
+
+        // both breaches dropped
+        let window_end = Some(start + Duration::minutes(15));
+        let sensor = filter_sensor_strict(sample_sensor(), window_start, window_end, None);
+        assert_eq!(sensor.breaches.map(|breaches| breaches.len()), Some(0));
+    }
+
+    #[test]
+    fn test_clamp_to_day() {
+        let timestamp =
+            |timestamp: &str| NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M").unwrap();
+        let day = NaiveDate::from_ymd_opt(2023, 5, 23).unwrap();
+
+        // within the day
+        assert_eq!(
+            common::clamp_to_day(day, timestamp("2023-05-23 10:00"), Duration::hours(2)),
+            (timestamp("2023-05-23 10:00"), timestamp("2023-05-23 12:00"))
+        );
+        // would start before midnight (e.g. triggered at 00:30 by a 60 minute config)
+        assert_eq!(
+            common::clamp_to_day(day, timestamp("2023-05-22 23:30"), Duration::hours(2)),
+            (timestamp("2023-05-23 00:00"), timestamp("2023-05-23 02:00"))
+        );
+        // would end after midnight
+        assert_eq!(
+            common::clamp_to_day(day, timestamp("2023-05-23 23:00"), Duration::hours(2)),
+            (timestamp("2023-05-23 23:00"), timestamp("2023-05-24 00:00"))
+        );
+    }
+
+    #[test]
+    fn test_is_breaching() {
+        let sensor = sample_sensor();
+        let configs = sensor.configs.as_deref().unwrap();
+        let cold_config = &configs[0]; // below 2.0
+        let hot_config = &configs[1]; // above 8.0
+        assert_eq!(cold_config.limit(), Celsius(2.0));
+        assert_eq!(hot_config.limit(), Celsius(8.0));
+
+        assert!(cold_config.is_breaching(Celsius(1.9)));
+        assert!(!cold_config.is_breaching(Celsius(2.0)));
+        assert!(!cold_config.is_breaching(Celsius(2.1)));
+        assert!(!cold_config.is_breaching(Celsius(100.0)));
+
+        assert!(hot_config.is_breaching(Celsius(8.1)));
+        assert!(!hot_config.is_breaching(Celsius(8.0)));
+        assert!(!hot_config.is_breaching(Celsius(7.9)));
+        assert!(!hot_config.is_breaching(Celsius(-273.0)));
+
+        // only the limit for the breach type counts, whatever the other one is
+        let hot_config = TemperatureBreachConfig {
+            minimum_temperature: Celsius(2.0),
+            ..hot_config.clone()
+        };
+        assert!(!hot_config.is_breaching(Celsius(1.0)));
+        assert!(hot_config.is_breaching(Celsius(9.0)));
+    }
+
+    #[test]
+    fn test_matches_profile() {
+        let sensor = sample_sensor();
+        let configs = sensor.configs.as_deref().unwrap();
+        // below 2.0 and above 8.0
+        assert_eq!(configs[0].matches_profile(), Some(ProductProfile::Fridge));
+        assert_eq!(configs[1].matches_profile(), Some(ProductProfile::Fridge));
+        let freezing_config = TemperatureBreachConfig {
+            minimum_temperature: Celsius(-0.5),
+            ..configs[0].clone()
+        };
+        assert_eq!(
+            freezing_config.matches_profile(),
+            Some(ProductProfile::Fridge)
+        );
+
+        let freezer_config = TemperatureBreachConfig {
+            maximum_temperature: Celsius(-15.0),
+            ..configs[1].clone()
+        };
+        assert_eq!(
+            freezer_config.matches_profile(),
+            Some(ProductProfile::Freezer)
+        );
+
+        // neither, e.g. a hot config for room temperature products
+        let room_config = TemperatureBreachConfig {
+            maximum_temperature: Celsius(25.0),
+            ..configs[1].clone()
+        };
+        assert_eq!(room_config.matches_profile(), None);
+    }
+
+    #[test]
+    fn test_breaching_configs() {
+        let sensor = sample_sensor();
+        let configs = sensor.configs.as_deref().unwrap();
+        let timestamp = sensor.logs.as_ref().unwrap()[0].timestamp;
+        let log = |temperature| TemperatureLog {
+            temperature: Celsius(temperature),
+            timestamp,
+        };
+
+        let hot = log(9.2).breaching_configs(configs);
+        assert_eq!(hot.len(), 1);
+        assert!(matches!(hot[0].breach_type, BreachType::HotConsecutive));
+
+        let cold = log(1.2).breaching_configs(configs);
+        assert_eq!(cold.len(), 1);
+        assert!(matches!(cold[0].breach_type, BreachType::ColdConsecutive));
+
+        assert!(log(5.0).breaching_configs(configs).is_empty());
+        assert!(log(8.0).breaching_configs(configs).is_empty()); // at the limit
+        assert!(log(9.2).breaching_configs(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_defaults() {
+        let sensor = Sensor::default();
+        assert_eq!(sensor.sensor_type, SensorType::Berlinger(None));
+        assert_eq!(sensor.serial, "");
+        assert_eq!(sensor.name, "");
+        assert!(sensor.logs.is_none());
+        assert!(sensor.breaches.is_none());
+        assert!(sensor.configs.is_none());
+        assert!(sensor.last_connected_timestamp.is_none());
+        assert!(sensor.log_interval.is_none());
+
+        let config = TemperatureBreachConfig::default();
+        assert_eq!(config.duration, Duration::zero());
+        for temperature in [-90.0, 0.0, 5.0, 45.0] {
+            assert!(!config.is_breaching(Celsius(temperature)));
+        }
+    }
+
+    #[test]
+    fn test_empty_sensor() {
+        let sensor = Sensor::empty("130400191542");
+        assert_eq!(sensor.serial, "130400191542");
+        assert!(sensor.logs.is_none());
+        assert!(sensor.breaches.is_none());
+        assert!(sensor.configs.is_none());
+        assert!(sensor.is_empty());
+
+        // empty lists are no data either, but any log or breach is
+        let mut sensor = sample_sensor();
+        assert!(!sensor.is_empty());
+        sensor.logs = Some(vec![]);
+        assert!(!sensor.is_empty());
+        sensor.breaches = Some(vec![]);
+        assert!(sensor.is_empty());
+        sensor.logs = sample_sensor().logs;
+        assert!(!sensor.is_empty());
+    }
 }
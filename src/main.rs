@@ -11,13 +11,14 @@ fn main() -> Result<(), Box<dyn Error>> {
     if args.len() > 1 {
         // try specified file name
         let file_path = args[1].trim();
-        if let Some(file_contents) = read_to_string(&file_path).ok() {
+        if let Ok(file_contents) = read_to_string(file_path) {
             sensor = temperature_sensor::parse_sensor(&file_contents)?;
             if let Some(timestamp) = sensor.last_connected_timestamp {
                 start_timestamp = Some(timestamp - Duration::days(3)); // go back from 3 days
             }
 
-            temperature_sensor::filter_sensor(sensor, start_timestamp, None);
+            let filtered_sensor = temperature_sensor::filter_sensor(sensor, start_timestamp, None);
+            println!("{:#?}", filtered_sensor);
         }
     } else {
         // read from USB
@@ -30,7 +31,8 @@ fn main() -> Result<(), Box<dyn Error>> {
                 start_timestamp = Some(timestamp - Duration::days(3)); // go back from 3 days
             }
 
-            temperature_sensor::filter_sensor(sensor, start_timestamp, None);
+            let filtered_sensor = temperature_sensor::filter_sensor(sensor, start_timestamp, None);
+            println!("{:#?}", filtered_sensor);
         }
     }
 